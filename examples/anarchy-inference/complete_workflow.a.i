@@ -21,7 +21,7 @@
     
     // 2. Process the information
     ⌽(:step2);
-    ιstored_intro = 📖("rust_intro");
+    ιstored_intro = 📚("rust_intro");
     
     // Count words
     ιwords = stored_intro.split(/\s+/);
@@ -12,9 +12,9 @@
     
     // Retrieve values from memory
     ⌽(:retrieving_values);
-    ιname = 📖("user_name");
-    ιcolor = 📖("favorite_color");
-    ιcount = 📖("login_count");
+    ιname = 📚("user_name");
+    ιcolor = 📚("favorite_color");
+    ιcount = 📚("login_count");
     
     ⌽(`${:user_format}${name}, ${:favorite_color}${color}, ${:login_count}${count}`);
     
@@ -24,7 +24,7 @@
     📝("login_count", 🔤(new_count));
     
     // Retrieve the updated value
-    ιupdated_count = 📖("login_count");
+    ιupdated_count = 📚("login_count");
     ⌽(`${:updated_count}${updated_count}`);
     
     // Delete a value
@@ -33,7 +33,7 @@
     
     // Try to retrieve the deleted value
     ÷{
-        ιdeleted_color = 📖("favorite_color");
+        ιdeleted_color = 📚("favorite_color");
         ⌽(`${:error_message}${deleted_color}`);
     }{
         ⌽(:delete_success);
@@ -55,7 +55,7 @@
     
     // Retrieve and parse complex data
     ⌽(:retrieving_complex);
-    ιstored_data = 📖("user_profile");
+    ιstored_data = 📚("user_profile");
     ιparsed_data = ⎋(stored_data);
     
     ⌽(`${:user_profile}${parsed_data.name}, ${parsed_data.age} years old`);
@@ -24,7 +24,7 @@ pub fn complete_workflow_example() -> Result<()> {
     
     // 2. Process the information
     ⌽("Step 2: Processing the information...");
-    ιstored_intro = 📖("rust_intro");
+    ιstored_intro = 📚("rust_intro");
     
     // Count words
     ιwords = stored_intro.split(/\s+/);
@@ -15,9 +15,9 @@ pub fn memory_example() -> Result<()> {
     
     // Retrieve values from memory
     ⌽("Retrieving values from memory...");
-    ιname = 📖("user_name");
-    ιcolor = 📖("favorite_color");
-    ιcount = 📖("login_count");
+    ιname = 📚("user_name");
+    ιcolor = 📚("favorite_color");
+    ιcount = 📚("login_count");
     
     ⌽(`User: ${name}, Favorite color: ${color}, Login count: ${count}`);
     
@@ -27,7 +27,7 @@ pub fn memory_example() -> Result<()> {
     📝("login_count", 🔤(new_count));
     
     // Retrieve the updated value
-    ιupdated_count = 📖("login_count");
+    ιupdated_count = 📚("login_count");
     ⌽(`Updated login count: ${updated_count}`);
     
     // Delete a value
@@ -36,7 +36,7 @@ pub fn memory_example() -> Result<()> {
     
     // Try to retrieve the deleted value
     ÷{
-        ιdeleted_color = 📖("favorite_color");
+        ιdeleted_color = 📚("favorite_color");
         ⌽(`This should not print: ${deleted_color}`);
     }{
         ⌽("Value was successfully deleted from memory");
@@ -58,7 +58,7 @@ pub fn memory_example() -> Result<()> {
     
     // Retrieve and parse complex data
     ⌽("Retrieving and parsing complex data...");
-    ιstored_data = 📖("user_profile");
+    ιstored_data = 📚("user_profile");
     ιparsed_data = ⎋(stored_data);
     
     ⌽(`User profile: ${parsed_data.name}, ${parsed_data.age} years old`);
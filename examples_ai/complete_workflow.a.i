@@ -18,7 +18,7 @@
     
     // 2. Process the information
     ⌽("Step 2: Processing the information...");
-    ιstored_intro = 📖("rust_intro");
+    ιstored_intro = 📚("rust_intro");
     
     // Count words
     ιwords = stored_intro.split(/\s+/);
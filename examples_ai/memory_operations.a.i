@@ -9,9 +9,9 @@
     
     // Retrieve values from memory
     ⌽("Retrieving values from memory...");
-    ιname = 📖("user_name");
-    ιcolor = 📖("favorite_color");
-    ιcount = 📖("login_count");
+    ιname = 📚("user_name");
+    ιcolor = 📚("favorite_color");
+    ιcount = 📚("login_count");
     
     ⌽(`User: ${name}, Favorite color: ${color}, Login count: ${count}`);
     
@@ -21,7 +21,7 @@
     📝("login_count", 🔤(new_count));
     
     // Retrieve the updated value
-    ιupdated_count = 📖("login_count");
+    ιupdated_count = 📚("login_count");
     ⌽(`Updated login count: ${updated_count}`);
     
     // Delete a value
@@ -30,7 +30,7 @@
     
     // Try to retrieve the deleted value
     ÷{
-        ιdeleted_color = 📖("favorite_color");
+        ιdeleted_color = 📚("favorite_color");
         ⌽(`This should not print: ${deleted_color}`);
     }{
         ⌽("Value was successfully deleted from memory");
@@ -52,7 +52,7 @@
     
     // Retrieve and parse complex data
     ⌽("Retrieving and parsing complex data...");
-    ιstored_data = 📖("user_profile");
+    ιstored_data = 📚("user_profile");
     ιparsed_data = ⎋(stored_data);
     
     ⌽(`User profile: ${parsed_data.name}, ${parsed_data.age} years old`);
@@ -5,6 +5,8 @@ use tokio;
 
 use anarchy_agent::core::Agent;
 use anarchy_agent::core::Config as CoreConfig;
+use anarchy_agent::executor::{check_syntax, format_code, migrate_code_with_keys, CapabilityProfile, Engine, ExecutionReport, Trace};
+use anarchy_agent::planner::migrate_patterns;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -13,6 +15,14 @@ async fn main() -> Result<()> {
 
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
+    
+    // The formatter and the migration need no agent
+    match args.get(1).map(String::as_str) {
+        Some("fmt") => std::process::exit(run_rewrite(&args[2..], &format_code, "formatted")?),
+        Some("migrate") => std::process::exit(run_migrate(&args[2..])?),
+        _ => {}
+    }
+    
     let config = parse_args(&args)?;
 
     // Create and initialize the agent
@@ -24,14 +34,33 @@ async fn main() -> Result<()> {
     if let Some(file_path) = &config.file_path {
         // Run a specific Anarchy-Inference file
         let file_content = std::fs::read_to_string(file_path)?;
-        let result = agent.run_code(&file_content).await?;
-        println!("Execution result: {}", result);
-    } else if let Some(example_name) = &config.example_name {
+        let name = file_path.display().to_string();
+        if config.dry_run && !approve_code(&agent, &file_content, Some(name.as_str()), config.json_output).await? {
+            agent.shutdown().await?;
+            return Ok(());
+        }
+        let report = agent.run_code(&file_content).await?;
+        save_trace(&config, &report)?;
+        print_report("Execution result", &report, Some((name, file_content.as_str())), config.json_output);
+    } else if let Some(example_path) = config.script_path() {
         // Run an example
-        let example_path = format!("examples/anarchy-inference/{}.a.i", example_name);
+        let example_path = example_path.display().to_string();
         let file_content = std::fs::read_to_string(&example_path)?;
-        let result = agent.run_code(&file_content).await?;
-        println!("Example execution result: {}", result);
+        if config.dry_run && !approve_code(&agent, &file_content, Some(example_path.as_str()), config.json_output).await? {
+            agent.shutdown().await?;
+            return Ok(());
+        }
+        let report = agent.run_code(&file_content).await?;
+        save_trace(&config, &report)?;
+        let source = Some((example_path.clone(), file_content.as_str()));
+        print_report("Example execution result", &report, source, config.json_output);
+    } else if let Some(trace_path) = &config.replay_path {
+        // Replay the script recorded in a trace
+        let trace = Trace::load(trace_path)?;
+        let report = agent.run_code(&trace.code).await?;
+        save_trace(&config, &report)?;
+        let source = Some((trace_path.display().to_string(), trace.code.as_str()));
+        print_report("Replay result", &report, source, config.json_output);
     } else if config.repl_mode {
         // Run in REPL mode
         run_repl(&agent).await?;
@@ -44,8 +73,18 @@ async fn main() -> Result<()> {
         std::io::stdin().read_line(&mut input)?;
         
         while input.trim() != "exit" && input.trim() != "quit" {
-            let result = agent.run_task(&input).await?;
-            println!("Task result: {}", result);
+            if config.dry_run {
+                let code = agent.plan_task(&input).await?;
+                if approve_code(&agent, &code, None, config.json_output).await? {
+                    let report = agent.run_plan(&input, &code).await?;
+                    save_trace(&config, &report)?;
+                    print_report("Task result", &report, None, config.json_output);
+                }
+            } else {
+                let report = agent.run_task(&input).await?;
+                save_trace(&config, &report)?;
+                print_report("Task result", &report, None, config.json_output);
+            }
             
             input.clear();
             println!("Enter another task (or 'exit' to quit):");
@@ -96,6 +135,53 @@ fn parse_args(args: &[String]) -> Result<CoreConfig> {
                 config.verbose = true;
                 i += 1;
             },
+            "--json" => {
+                config.json_output = true;
+                i += 1;
+            },
+            "--read-only" => {
+                config.capabilities = CapabilityProfile::read_only();
+                i += 1;
+            },
+            "--dry-run" => {
+                config.dry_run = true;
+                i += 1;
+            },
+            "--debug" => {
+                config.debug = true;
+                i += 1;
+            },
+            "--bytecode" => {
+                config.engine = Engine::Bytecode;
+                i += 1;
+            },
+            "--record" => {
+                if i + 1 < args.len() {
+                    config.record_path = Some(PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    eprintln!("Missing trace path");
+                    std::process::exit(1);
+                }
+            },
+            "--lib" => {
+                if i + 1 < args.len() {
+                    config.library_path.push(PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    eprintln!("Missing library directory");
+                    std::process::exit(1);
+                }
+            },
+            "--replay" => {
+                if i + 1 < args.len() {
+                    config.replay_path = Some(PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    eprintln!("Missing trace path");
+                    std::process::exit(1);
+                }
+            },
             _ => {
                 // Assume it's a file path if it doesn't start with --
                 if !args[i].starts_with("--") {
@@ -130,6 +216,26 @@ fn print_usage() {
     println!("  --repl                 Start an interactive REPL session");
     println!("  --model <path>         Specify path to a local LLM model");
     println!("  --verbose              Enable verbose logging");
+    println!("  --json                 Print execution reports as JSON");
+    println!("  --read-only            Disallow shell commands and writes to files or memory");
+    println!("  --dry-run              Show what a script would change and ask before running it");
+    println!("  --debug                Step through scripts in the debugger");
+    println!("  --bytecode             Compile scripts to bytecode and run them on the VM");
+    println!("  --lib <dir>            Also look for imported modules in <dir> (may be repeated)");
+    println!("  --record <path>        Write the symbol calls of the script and their results to a trace");
+    println!("  --replay <path>        Answer symbol calls from a trace; runs the traced script if no FILE is given");
+    println!("");
+    println!("Formatting:");
+    println!("  anarchy-agent fmt [--check | --write] [FILE...]");
+    println!("    Print FILEs (or stdin) in canonical format. --check exits with 1 if a");
+    println!("    file is not formatted, --write rewrites the files in place.");
+    println!("");
+    println!("Migration:");
+    println!("  anarchy-agent migrate [--check | --write] [--key KEY...] [FILE... | --patterns DIR]");
+    println!("    Update FILEs (or stdin) written for older versions of the language,");
+    println!("    e.g. memory reads with 📖 become 📚. Options as for fmt. --key names a");
+    println!("    memory entry stored by an earlier run (may be repeated), --patterns");
+    println!("    migrates the templates of the planner patterns in DIR instead.");
     println!("");
     println!("Examples:");
     println!("  anarchy-agent script.a.i");
@@ -137,10 +243,201 @@ fn print_usage() {
     println!("  anarchy-agent --repl");
 }
 
+// Migrate Anarchy-Inference files or pattern templates, returning the
+// process exit code
+//
+// Takes the options of `run_rewrite` plus `--key` and `--patterns`.
+fn run_migrate(args: &[String]) -> Result<i32> {
+    let mut keys = Vec::new();
+    let mut patterns_dir = None;
+    let mut rest = Vec::new();
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" | "--patterns" => {
+                let Some(value) = args.next() else {
+                    eprintln!("{} requires a value", arg);
+                    return Ok(1);
+                };
+                if arg == "--key" {
+                    keys.push(value.clone());
+                } else {
+                    patterns_dir = Some(PathBuf::from(value));
+                }
+            },
+            _ => rest.push(arg.clone()),
+        }
+    }
+    
+    let Some(patterns_dir) = patterns_dir else {
+        return run_rewrite(&rest, &|code| migrate_code_with_keys(code, &keys), "migrated");
+    };
+    
+    let mut check = false;
+    let mut write = false;
+    for arg in &rest {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--write" | "-w" => write = true,
+            _ => {
+                eprintln!("Unexpected argument with --patterns: {}", arg);
+                return Ok(1);
+            },
+        }
+    }
+    
+    let migrated = migrate_patterns(&patterns_dir, &keys, write)?;
+    for path in &migrated {
+        if write {
+            println!("Migrated {}", path.display());
+        } else {
+            println!("{} is not migrated", path.display());
+        }
+    }
+    
+    Ok((check && !migrated.is_empty()) as i32)
+}
+
+// Format or migrate Anarchy-Inference files with `rewrite`, returning the
+// process exit code
+//
+// `done` describes rewritten code in messages, e.g. "formatted".
+fn run_rewrite(args: &[String], rewrite: &dyn Fn(&str) -> Result<String>, done: &str) -> Result<i32> {
+    let mut check = false;
+    let mut write = false;
+    let mut files = Vec::new();
+    
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--write" | "-w" => write = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option: {}", arg);
+                return Ok(1);
+            },
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    
+    if files.is_empty() {
+        let mut code = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut code)?;
+        return Ok(match rewrite_file("<stdin>", &code, rewrite) {
+            Some(rewritten) if check => (rewritten != code) as i32,
+            Some(rewritten) => {
+                print!("{}", rewritten);
+                0
+            },
+            None => 1,
+        });
+    }
+    
+    let mut status = 0;
+    for path in &files {
+        let code = std::fs::read_to_string(path)?;
+        let name = path.display().to_string();
+        let Some(rewritten) = rewrite_file(&name, &code, rewrite) else {
+            status = 1;
+            continue;
+        };
+        
+        if check {
+            if rewritten != code {
+                println!("{} is not {}", name, done);
+                status = 1;
+            }
+        } else if write {
+            if rewritten != code {
+                std::fs::write(path, &rewritten)?;
+            }
+        } else {
+            print!("{}", rewritten);
+        }
+    }
+    
+    Ok(status)
+}
+
+// Rewrite one file, printing syntax errors as diagnostics
+fn rewrite_file(name: &str, code: &str, rewrite: &dyn Fn(&str) -> Result<String>) -> Option<String> {
+    match rewrite(code) {
+        Ok(rewritten) => Some(rewritten),
+        Err(err) => {
+            let diagnostics = check_syntax(code);
+            if diagnostics.is_empty() {
+                eprintln!("{}: {}", name, err);
+            }
+            for diagnostic in diagnostics {
+                eprint!("{}", diagnostic.with_file(name).render(code));
+            }
+            None
+        }
+    }
+}
+
+// Dry-run code and ask the user whether to run it for real
+//
+// Returns `false` if the dry run failed or the user declined. Code without
+// side effects is approved without asking.
+async fn approve_code(agent: &Agent, code: &str, file: Option<&str>, json: bool) -> Result<bool> {
+    let report = agent.dry_run_code(code).await?;
+    let source = file.map(|file| (file.to_string(), code));
+    
+    if file.is_none() && !json {
+        println!("Generated code:\n{}", code);
+    }
+    print_report("Dry run result", &report, source, json);
+    
+    if !report.is_success() {
+        return Ok(false);
+    }
+    if report.effects.is_empty() {
+        return Ok(true);
+    }
+    
+    eprint!("Run for real? [y/N] ");
+    std::io::Write::flush(&mut std::io::stderr())?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+// Print an execution report, either readable or as JSON
+//
+// `source` is the file name and content of the script, used to show
+// errors as diagnostics pointing into the code.
+fn print_report(label: &str, report: &ExecutionReport, source: Option<(String, &str)>, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report.to_json()).unwrap_or_default());
+        return;
+    }
+    
+    match (&report.error, source) {
+        (Some(error), Some((file, code))) => {
+            if !report.output.is_empty() {
+                println!("{}", report.output.join("\n"));
+            }
+            eprint!("{}", error.to_diagnostic().with_file(file).render(code));
+        }
+        _ => println!("{}: {}", label, report),
+    }
+}
+
 // Run the REPL (Read-Eval-Print Loop)
+// Write the trace of a recorded run to the path given with --record
+fn save_trace(config: &CoreConfig, report: &ExecutionReport) -> Result<()> {
+    if let (Some(path), Some(trace)) = (&config.record_path, &report.trace) {
+        trace.save(path)?;
+        eprintln!("Trace written to {}", path.display());
+    }
+    Ok(())
+}
+
 async fn run_repl(agent: &Agent) -> Result<()> {
     println!("Starting Anarchy Agent REPL...");
-    println!("Type 'exit' or 'quit' to exit.");
+    println!("Type ':symbols' to list available symbols, 'exit' or 'quit' to exit.");
     
     let mut input = String::new();
     print!("> ");
@@ -148,7 +445,9 @@ async fn run_repl(agent: &Agent) -> Result<()> {
     std::io::stdin().read_line(&mut input)?;
     
     while input.trim() != "exit" && input.trim() != "quit" {
-        if !input.trim().is_empty() {
+        if input.trim() == ":symbols" {
+            print_symbols(agent).await;
+        } else if !input.trim().is_empty() {
             match agent.run_code(&input).await {
                 Ok(result) => println!("{}", result),
                 Err(e) => eprintln!("Error: {}", e),
@@ -164,3 +463,17 @@ async fn run_repl(agent: &Agent) -> Result<()> {
     println!("REPL session ended.");
     Ok(())
 }
+
+// Print the symbols available to Anarchy-Inference code, grouped by capability
+async fn print_symbols(agent: &Agent) {
+    let signatures = agent.symbol_signatures().await;
+    let mut current = None;
+    
+    for signature in signatures {
+        if current != Some(signature.capability) {
+            println!("[{}]", signature.capability);
+            current = Some(signature.capability);
+        }
+        println!("  {:<40} {}", signature.usage(), signature.help);
+    }
+}
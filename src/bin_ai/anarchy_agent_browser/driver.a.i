@@ -0,0 +1,12 @@
+// Browser module, imported by main.a.i as anarchy_agent_browser/driver
+ι Browser = ⟰("../browser");
+
+ι driver = {};
+
+// Create the browser driver for the configuration
+driver.new = λ(config) {
+    ↩ Browser();
+};
+
+// Export the module
+⟼(driver);
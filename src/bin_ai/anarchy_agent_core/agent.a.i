@@ -0,0 +1,30 @@
+// Agent module, imported by main.a.i as anarchy_agent_core/agent
+ι Core = ⟰("../core");
+
+ι agent_module = {};
+
+// Create an agent from the configuration and its components
+agent_module.new = λ(config, planner, executor, memory, browser, file_system, shell) {
+    ι agent = Core();
+    agent.config = config;
+    agent.planner = planner;
+    agent.executor = executor;
+    agent.memory = memory;
+    agent.browser = browser;
+    agent.system = file_system;
+    agent.shell = shell;
+    ↩ agent;
+};
+
+// Run the file named by the configuration
+agent_module.run = λ(agent) {
+    ÷{
+        ι result = agent.run_code(📖(agent.config.file_path));
+        ↩ { success: true, result: result };
+    }⚠(e){
+        ↩ { success: false, error: e.message };
+    }
+};
+
+// Export the module
+⟼(agent_module);
@@ -0,0 +1,18 @@
+// Configuration module, imported by main.a.i as anarchy_agent_core/config
+ι Config = ⟰("../system", "Config");
+
+ι config_module = {};
+
+// Configuration before any command line argument is applied
+config_module.default = λ() {
+    ι config = Config();
+    config.example_name = null;
+    config.repl_mode = false;
+    config.model_path = null;
+    config.verbose = false;
+    config.file_path = null;
+    ↩ config;
+};
+
+// Export the module
+⟼(config_module);
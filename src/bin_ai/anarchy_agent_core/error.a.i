@@ -0,0 +1,7 @@
+// Error module, imported by main.a.i as anarchy_agent_core/error
+ι Error = ⟰("../system", "Error");
+
+ι error_module = Error();
+
+// Export the module
+⟼(error_module);
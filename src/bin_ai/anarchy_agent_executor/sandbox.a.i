@@ -0,0 +1,12 @@
+// Executor module, imported by main.a.i as anarchy_agent_executor/sandbox
+ι Executor = ⟰("../executor");
+
+ι sandbox = {};
+
+// Create the executor for the configuration
+sandbox.new = λ(config) {
+    ↩ Executor();
+};
+
+// Export the module
+⟼(sandbox);
@@ -0,0 +1,12 @@
+// Memory module, imported by main.a.i as anarchy_agent_memory/storage
+ι Memory = ⟰("../memory");
+
+ι storage = {};
+
+// Create the memory module for the configuration
+storage.new = λ(config) {
+    ↩ Memory();
+};
+
+// Export the module
+⟼(storage);
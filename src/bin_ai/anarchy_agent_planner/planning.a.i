@@ -0,0 +1,12 @@
+// Planner module, imported by main.a.i as anarchy_agent_planner/planning
+ι Planner = ⟰("../planner");
+
+ι planning = {};
+
+// Create the planner for the configuration
+planning.new = λ(config) {
+    ↩ Planner();
+};
+
+// Export the module
+⟼(planning);
@@ -0,0 +1,12 @@
+// File system module, imported by main.a.i as anarchy_agent_system/file
+ι File = ⟰("../file");
+
+ι file = {};
+
+// Create the file system module for the configuration
+file.new = λ(config) {
+    ↩ File();
+};
+
+// Export the module
+⟼(file);
@@ -0,0 +1,12 @@
+// Shell module, imported by main.a.i as anarchy_agent_system/shell
+ι Shell = ⟰("../shell");
+
+ι shell = {};
+
+// Create the shell module for the configuration
+shell.new = λ(config) {
+    ↩ Shell();
+};
+
+// Export the module
+⟼(shell);
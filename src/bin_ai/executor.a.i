@@ -110,8 +110,8 @@
         // Register 📝 (set memory)
         self.sandbox.register("📝", self.memory.set_memory);
         
-        // Register 📖 (get memory)
-        self.sandbox.register("📖", self.memory.get_memory);
+        // Register 📚 (get memory)
+        self.sandbox.register("📚", self.memory.get_memory);
         
         // Register 🗑 (delete memory)
         self.sandbox.register("🗑", self.memory.forget_key);
@@ -47,7 +47,7 @@
         ↩ true;
     };
     
-    // Retrieve a value by key from memory (for Anarchy-Inference 📖 symbol)
+    // Retrieve a value by key from memory (for Anarchy-Inference 📚 symbol)
     self.get_memory = λ(key) {
         ι value = self.db.get_key_value(key);
         ↩ value;
@@ -6,6 +6,9 @@ pub enum Error {
     #[error("Browser initialization error: {0}")]
     BrowserInitializationError(String),
     
+    #[error("No page is open: {0}")]
+    NoPageOpen(String),
+    
     #[error("Navigation error: {0}")]
     NavigationError(String),
     
@@ -14,6 +14,9 @@ pub use error::Error;
 pub struct Browser {
     config: Config,
     driver: Option<driver::WebDriver>,
+    
+    /// Handle of the page that was opened last
+    page: u64,
 }
 
 impl Browser {
@@ -27,6 +30,7 @@ impl Browser {
         Ok(Self {
             config,
             driver: None,
+            page: 0,
         })
     }
     
@@ -37,7 +41,11 @@ impl Browser {
     }
     
     /// Open a web page (for Anarchy-Inference 🌐 symbol)
-    pub async fn open_page(&mut self, url: &str) -> Result<()> {
+    ///
+    /// Returns a handle that the other operations take to say which page they
+    /// act on. The browser shows one page at a time, so opening another page
+    /// makes the handles to earlier ones stale.
+    pub async fn open_page(&mut self, url: &str) -> Result<u64> {
         // Initialize the driver if not already done
         if self.driver.is_none() {
             self.driver = Some(driver::WebDriver::new(self.config.headless).await?);
@@ -48,47 +56,44 @@ impl Browser {
             driver.navigate(url).await?;
         }
         
-        Ok(())
+        self.page += 1;
+        Ok(self.page)
     }
     
     /// Click on an element (for Anarchy-Inference 🖱 symbol)
-    pub async fn click_element(&self, selector: &str) -> Result<()> {
-        if let Some(driver) = &self.driver {
-            driver.click(selector).await?;
-        }
-        
-        Ok(())
+    pub async fn click_element(&self, page: u64, selector: &str) -> Result<()> {
+        self.page(page)?.click(selector).await
     }
     
     /// Input text into an element (for Anarchy-Inference ⌨ symbol)
-    pub async fn input_text(&self, selector: &str, text: &str) -> Result<()> {
-        if let Some(driver) = &self.driver {
-            driver.input(selector, text).await?;
-        }
-        
-        Ok(())
+    pub async fn input_text(&self, page: u64, selector: &str, text: &str) -> Result<()> {
+        self.page(page)?.input(selector, text).await
     }
     
     /// Get text from an element (for Anarchy-Inference 👁 symbol)
-    pub async fn get_text(&self, selector: &str) -> Result<String> {
-        if let Some(driver) = &self.driver {
-            return driver.get_text(selector).await;
-        }
-        
-        Ok(String::new())
+    pub async fn get_text(&self, page: u64, selector: &str) -> Result<String> {
+        self.page(page)?.get_text(selector).await
     }
     
     /// Execute JavaScript in the browser (for Anarchy-Inference 🧠 symbol)
-    pub async fn execute_js(&self, script: &str) -> Result<String> {
-        if let Some(driver) = &self.driver {
-            return driver.execute_js(script).await;
+    pub async fn execute_js(&self, page: u64, script: &str) -> Result<String> {
+        self.page(page)?.execute_js(script).await
+    }
+    
+    /// Close the browser (for Anarchy-Inference ❌ symbol)
+    ///
+    /// Closing when no page is open does nothing, but closing a page that
+    /// another one replaced is an error since that would close the newer one.
+    pub async fn close(&mut self, page: u64) -> Result<()> {
+        if self.driver.is_some() {
+            self.page(page)?;
         }
         
-        Ok(String::new())
+        self.quit().await
     }
     
-    /// Close the browser (for Anarchy-Inference ❌ symbol)
-    pub async fn close(&mut self) -> Result<()> {
+    /// Close the browser whichever page it shows
+    async fn quit(&mut self) -> Result<()> {
         if let Some(driver) = &self.driver {
             driver.close().await?;
             self.driver = None;
@@ -97,10 +102,30 @@ impl Browser {
         Ok(())
     }
     
+    /// The driver showing `page`
+    ///
+    /// Interactions need a page to act on, so they fail rather than quietly
+    /// doing nothing when none has been opened or when `page` is not the one
+    /// the browser shows.
+    fn page(&self, page: u64) -> Result<&driver::WebDriver> {
+        let driver = self.driver.as_ref().ok_or_else(|| {
+            Error::NoPageOpen("open a page before interacting with it".to_string())
+        })?;
+        if page != self.page {
+            return Err(Error::NoPageOpen(format!(
+                "page {} was replaced by page {}",
+                page, self.page
+            ))
+            .into());
+        }
+        
+        Ok(driver)
+    }
+    
     /// Shutdown the browser module
     pub async fn shutdown(&mut self) -> Result<()> {
         // Close the browser if it's open
-        self.close().await?;
+        self.quit().await?;
         
         Ok(())
     }
@@ -1,8 +1,11 @@
 use anyhow::Result;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::Core;
+use executor::{CapabilityProfile, ExecutionOptions, ExecutionReport, SymbolProvider, SymbolSignature, TerminalDebugger, Trace};
+
+use crate::{Core, CoreBuilder};
 use crate::Config;
 
 /// Agent struct that provides a simplified interface to the Core functionality
@@ -13,17 +16,19 @@ pub struct Agent {
 impl Agent {
     /// Create a new Agent instance with default configuration
     pub async fn new() -> Result<Self> {
-        let config = Config::default();
-        Self::with_config(config).await
+        Self::builder().build().await
     }
     
     /// Create a new Agent instance with custom configuration
     pub async fn with_config(config: Config) -> Result<Self> {
-        let core = Arc::new(Core::new(config).await?);
-        
-        Ok(Self {
-            core,
-        })
+        Self::builder().config(config).build().await
+    }
+    
+    /// Start building an Agent instance, e.g. one with extra symbols
+    pub fn builder() -> AgentBuilder {
+        AgentBuilder {
+            core: Core::builder(Config::default()),
+        }
     }
     
     /// Initialize the agent
@@ -32,18 +37,138 @@ impl Agent {
     }
     
     /// Run a task using natural language description
-    pub async fn run_task(&self, task_description: &str) -> Result<String> {
-        self.core.run_task(task_description).await
+    pub async fn run_task(&self, task_description: &str) -> Result<ExecutionReport> {
+        self.run_task_with_profile(task_description, &self.core.config.capabilities).await
+    }
+    
+    /// Run a task whose script may only use the capabilities in `profile`
+    pub async fn run_task_with_profile(&self, task_description: &str, profile: &CapabilityProfile) -> Result<ExecutionReport> {
+        let anarchy_code = self.core.plan_task(task_description).await?;
+        let options = self.run_options(profile)?;
+        self.core.run_plan(task_description, &anarchy_code, &options).await
+    }
+    
+    /// Generate the Anarchy-Inference code for a task without running it
+    pub async fn plan_task(&self, task_description: &str) -> Result<String> {
+        self.core.plan_task(task_description).await
+    }
+    
+    /// Run code generated for a task with `plan_task`, storing the result in memory
+    pub async fn run_plan(&self, task_description: &str, anarchy_code: &str) -> Result<ExecutionReport> {
+        let options = self.run_options(&self.core.config.capabilities)?;
+        self.core.run_plan(task_description, anarchy_code, &options).await
     }
     
     /// Run Anarchy-Inference code directly
-    pub async fn run_code(&self, anarchy_code: &str) -> Result<String> {
+    pub async fn run_code(&self, anarchy_code: &str) -> Result<ExecutionReport> {
+        self.run_code_with_profile(anarchy_code, &self.core.config.capabilities).await
+    }
+    
+    /// Run Anarchy-Inference code that may only use the capabilities in `profile`
+    pub async fn run_code_with_profile(&self, anarchy_code: &str, profile: &CapabilityProfile) -> Result<ExecutionReport> {
+        let options = self.run_options(profile)?;
+        let executor = self.core.executor.lock().await;
+        executor.execute_with_options(anarchy_code, &options).await
+    }
+    
+    /// Run Anarchy-Inference code without side effects
+    ///
+    /// Calls to symbols that would change files, memory, the browser or
+    /// remote systems are recorded in `ExecutionReport::effects` and return
+    /// stub values instead.
+    pub async fn dry_run_code(&self, anarchy_code: &str) -> Result<ExecutionReport> {
+        let options = self.script_options(&self.core.config.capabilities).dry_run();
         let executor = self.core.executor.lock().await;
-        executor.execute_code(anarchy_code).await
+        executor.execute_with_options(anarchy_code, &options).await
+    }
+    
+    /// Signatures of the symbols available to Anarchy-Inference code
+    pub async fn symbol_signatures(&self) -> Vec<SymbolSignature> {
+        self.core.executor.lock().await.symbol_signatures()
     }
     
     /// Shutdown the agent
     pub async fn shutdown(&self) -> Result<()> {
         self.core.shutdown().await
     }
+    
+    /// Options for running a script with the capabilities in `profile`
+    ///
+    /// Recording, replaying and debugging follow the configuration.
+    fn run_options(&self, profile: &CapabilityProfile) -> Result<ExecutionOptions> {
+        let config = &self.core.config;
+        let mut options = self.script_options(profile);
+        
+        if config.record_path.is_some() {
+            options = options.record();
+        }
+        if let Some(path) = &config.replay_path {
+            options = options.replay(Trace::load(path)?);
+        }
+        if config.debug {
+            options = options.debugger(TerminalDebugger::new());
+        }
+        
+        Ok(options)
+    }
+    
+    /// Options telling a script with the capabilities in `profile` where
+    /// its files and modules are and which engine runs it
+    ///
+    /// Relative paths in the script refer to the directory of the file or
+    /// example being run.
+    fn script_options(&self, profile: &CapabilityProfile) -> ExecutionOptions {
+        let config = &self.core.config;
+        let mut options = ExecutionOptions::new()
+            .capabilities(profile.clone())
+            .engine(config.engine);
+        
+        if let Some(dir) = config.script_path().as_deref().and_then(Path::parent) {
+            options = options.script_dir(dir);
+        }
+        for dir in &config.library_path {
+            options = options.library_dir(dir);
+        }
+        
+        options
+    }
+}
+
+/// Builder for an `Agent` instance
+///
+/// ```ignore
+/// let agent = Agent::builder()
+///     .config(config)
+///     .symbol_provider(Tickets::new())
+///     .build()
+///     .await?;
+/// agent.initialize().await?;
+/// ```
+pub struct AgentBuilder {
+    core: CoreBuilder,
+}
+
+impl AgentBuilder {
+    /// Use a custom configuration instead of the default one
+    pub fn config(mut self, config: Config) -> Self {
+        self.core.config = config;
+        self
+    }
+    
+    /// Make the symbols of `provider` available to scripts
+    pub fn symbol_provider(mut self, provider: impl SymbolProvider + 'static) -> Self {
+        self.core = self.core.symbol_provider(provider);
+        self
+    }
+    
+    /// Create the Agent instance
+    ///
+    /// The symbols of the providers are registered by `Agent::initialize`.
+    pub async fn build(self) -> Result<Agent> {
+        let core = Arc::new(self.core.build().await?);
+        
+        Ok(Agent {
+            core,
+        })
+    }
 }
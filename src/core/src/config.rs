@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use executor::{CapabilityProfile, Engine};
+
 /// Configuration for the Core module
 #[derive(Clone)]
 pub struct Config {
@@ -29,6 +31,30 @@ pub struct Config {
     
     /// Whether to enable verbose logging
     pub verbose: bool,
+    
+    /// Whether to print execution reports as JSON
+    pub json_output: bool,
+    
+    /// Capabilities scripts may use unless a task asks for a narrower profile
+    pub capabilities: CapabilityProfile,
+    
+    /// Whether to show the effects of a script and ask for approval before running it
+    pub dry_run: bool,
+    
+    /// Whether to run scripts under the step debugger
+    pub debug: bool,
+    
+    /// File to write a trace of each script's symbol calls to
+    pub record_path: Option<PathBuf>,
+    
+    /// Trace to answer symbol calls from instead of running them
+    pub replay_path: Option<PathBuf>,
+    
+    /// Directories searched for modules scripts import with `⟰`
+    pub library_path: Vec<PathBuf>,
+    
+    /// Engine that runs scripts
+    pub engine: Engine,
 }
 
 impl Default for Config {
@@ -43,6 +69,25 @@ impl Default for Config {
             example_name: None,
             repl_mode: false,
             verbose: false,
+            json_output: false,
+            capabilities: CapabilityProfile::all(),
+            dry_run: false,
+            debug: false,
+            record_path: None,
+            replay_path: None,
+            library_path: Vec::new(),
+            engine: Engine::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Path of the file or example to run, if any
+    pub fn script_path(&self) -> Option<PathBuf> {
+        match (&self.file_path, &self.example_name) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Some(name)) => Some(PathBuf::from(format!("examples/anarchy-inference/{}.a.i", name))),
+            (None, None) => None,
         }
     }
 }
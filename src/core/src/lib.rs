@@ -8,10 +8,10 @@ mod agent;
 
 pub use config::Config;
 pub use error::Error;
-pub use agent::Agent;
+pub use agent::{Agent, AgentBuilder};
 
 use planner::Planner;
-use executor::Executor;
+use executor::{CapabilityProfile, ExecutionOptions, ExecutionReport, Executor, SymbolProvider};
 use memory::Memory;
 use browser::Browser;
 use system::System;
@@ -29,34 +29,12 @@ pub struct Core {
 impl Core {
     /// Create a new Core instance with the provided configuration
     pub async fn new(config: Config) -> Result<Self> {
-        let memory = Arc::new(Mutex::new(Memory::new(&config.memory_path)?));
-        
-        let planner = Arc::new(Mutex::new(Planner::new(
-            &config.llm_model_path,
-            Arc::clone(&memory),
-        )?));
-        
-        let browser = Arc::new(Mutex::new(Browser::new(config.headless)?));
-        
-        let system = Arc::new(Mutex::new(System::new(
-            &config.working_directory,
-            config.sandbox_enabled,
-        )?));
-        
-        let executor = Arc::new(Mutex::new(Executor::new(
-            Arc::clone(&memory),
-            Arc::clone(&browser),
-            Arc::clone(&system),
-        )?));
-        
-        Ok(Self {
-            config,
-            planner,
-            executor,
-            memory,
-            browser,
-            system,
-        })
+        Self::builder(config).build().await
+    }
+    
+    /// Start building a Core instance, e.g. one with extra symbols
+    pub fn builder(config: Config) -> CoreBuilder {
+        CoreBuilder::new(config)
     }
     
     /// Initialize the agent and all its components
@@ -65,6 +43,10 @@ impl Core {
         self.memory.lock().await.initialize().await?;
         self.planner.lock().await.initialize().await?;
         self.executor.lock().await.initialize().await?;
+        
+        // Let the planner check generated code against the registered symbols
+        let symbols = self.executor.lock().await.symbol_signatures();
+        self.planner.lock().await.set_symbols(symbols);
         self.browser.lock().await.initialize().await?;
         self.system.lock().await.initialize().await?;
         
@@ -72,17 +54,37 @@ impl Core {
     }
     
     /// Run a task using the Anarchy-Inference language
-    pub async fn run_task(&self, task_description: &str) -> Result<String> {
+    pub async fn run_task(&self, task_description: &str) -> Result<ExecutionReport> {
+        self.run_task_with_profile(task_description, &self.config.capabilities).await
+    }
+    
+    /// Run a task whose script may only use the capabilities in `profile`,
+    /// e.g. `CapabilityProfile::read_only()` for research tasks
+    pub async fn run_task_with_profile(&self, task_description: &str, profile: &CapabilityProfile) -> Result<ExecutionReport> {
         // 1. Generate a plan using the planner
-        let anarchy_code = self.planner.lock().await.generate_plan(task_description).await?;
+        let anarchy_code = self.plan_task(task_description).await?;
         
-        // 2. Execute the plan using the executor
-        let result = self.executor.lock().await.execute_code(&anarchy_code).await?;
+        // 2. Execute the plan and remember the result
+        let options = ExecutionOptions::new().capabilities(profile.clone());
+        self.run_plan(task_description, &anarchy_code, &options).await
+    }
+    
+    /// Generate the Anarchy-Inference code for a task without running it
+    pub async fn plan_task(&self, task_description: &str) -> Result<String> {
+        self.planner.lock().await.generate_plan(task_description).await
+    }
+    
+    /// Execute the code generated for a task
+    ///
+    /// The result is stored in memory, except for dry runs, which change nothing.
+    pub async fn run_plan(&self, task_description: &str, anarchy_code: &str, options: &ExecutionOptions) -> Result<ExecutionReport> {
+        let report = self.executor.lock().await.execute_with_options(anarchy_code, options).await?;
         
-        // 3. Store the result in memory
-        self.memory.lock().await.store_result(task_description, &anarchy_code, &result).await?;
+        if !options.dry_run {
+            self.memory.lock().await.store_result(task_description, anarchy_code, &report.to_json()).await?;
+        }
         
-        Ok(result)
+        Ok(report)
     }
     
     /// Shutdown the agent and all its components
@@ -97,3 +99,66 @@ impl Core {
         Ok(())
     }
 }
+
+/// Builder for a `Core` instance
+///
+/// Symbol providers add tools of their own, such as ticketing or CSV
+/// processing, to the ones the executor has built in.
+pub struct CoreBuilder {
+    config: Config,
+    providers: Vec<Arc<dyn SymbolProvider>>,
+}
+
+impl CoreBuilder {
+    /// Start building a Core instance with the provided configuration
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            providers: Vec::new(),
+        }
+    }
+    
+    /// Make the symbols of `provider` available to scripts
+    pub fn symbol_provider(mut self, provider: impl SymbolProvider + 'static) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+    
+    /// Create the Core instance
+    ///
+    /// The symbols of the providers are registered by `Core::initialize`.
+    pub async fn build(self) -> Result<Core> {
+        let memory = Arc::new(Mutex::new(Memory::new(&self.config.memory_path)?));
+        
+        let planner = Arc::new(Mutex::new(Planner::new(
+            &self.config.llm_model_path,
+            Arc::clone(&memory),
+        )?));
+        
+        let browser = Arc::new(Mutex::new(Browser::new(self.config.headless)?));
+        
+        let system = Arc::new(Mutex::new(System::new(
+            &self.config.working_directory,
+            self.config.sandbox_enabled,
+        )?));
+        
+        let mut executor = Executor::new(
+            Arc::clone(&memory),
+            Arc::clone(&browser),
+            Arc::clone(&system),
+        )?;
+        for provider in self.providers {
+            executor.add_provider(provider);
+        }
+        let executor = Arc::new(Mutex::new(executor));
+        
+        Ok(Core {
+            config: self.config,
+            planner,
+            executor,
+            memory,
+            browser,
+            system,
+        })
+    }
+}
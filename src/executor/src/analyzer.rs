@@ -0,0 +1,588 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::*;
+use crate::builtins;
+use crate::diagnostics::{codes, Diagnostic, Severity};
+use crate::interpreter::Scope;
+use crate::parser;
+use crate::registry::{normalize, SymbolSignature};
+
+/// Names `new` understands without them being declared
+const CONSTRUCTORS: [&str; 4] = ["Date", "Error", "Object", "Array"];
+
+/// Check a program for mistakes that would make it fail or misbehave at runtime
+///
+/// Reports undefined variables, unknown symbols, symbol calls with the wrong
+/// number of arguments, unreachable code, a `main` function that is never
+/// called and `📖` file reads of keys the program stores in memory.
+/// `symbols` are the symbols the program may call, usually
+/// `Executor::symbol_signatures()`.
+pub fn analyze(program: &Program, symbols: &[SymbolSignature]) -> Vec<Diagnostic> {
+    analyze_with_memory_keys(program, symbols, &[])
+}
+
+/// [`analyze`], also reporting `📖` reads of `memory_keys`, keys that are in
+/// memory without the program storing them, e.g. because an earlier run did
+pub(crate) fn analyze_with_memory_keys(
+    program: &Program,
+    symbols: &[SymbolSignature],
+    memory_keys: &[String],
+) -> Vec<Diagnostic> {
+    let mut analyzer = Analyzer::new(symbols);
+    analyzer.known_keys = memory_keys.iter().map(|key| format!("{:?}", key)).collect();
+    analyzer.program(program);
+    analyzer.diagnostics
+}
+
+/// Parse and analyze code; syntax errors are reported instead of analysis results
+pub fn analyze_code(code: &str, symbols: &[SymbolSignature]) -> Vec<Diagnostic> {
+    if code.trim().is_empty() {
+        return vec![Diagnostic::error(codes::SYNTAX_ERROR, "Empty code", None)];
+    }
+
+    match parser::parse_spanned(code) {
+        Ok(parsed) => analyze(&parsed.program, symbols),
+        Err(err) => vec![err.into()],
+    }
+}
+
+/// Whether any of the diagnostics is an error
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+/// Variables visible in a block or function body
+struct Frame {
+    /// Every name declared in the frame, wherever the declaration is
+    all: HashSet<String>,
+
+    /// Names declared before the statement being analyzed
+    declared: HashSet<String>,
+
+    /// Whether the frame is the outermost frame of a function body
+    function: bool,
+}
+
+impl Frame {
+    /// Record the declarations in a statement list
+    fn collect(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Let { name, .. } => {
+                    self.all.insert(name.name.clone());
+                }
+                // Functions are hoisted, so they are usable from the start
+                StmtKind::Function(decl) => {
+                    if let Some(name) = &decl.name {
+                        self.all.insert(name.name.clone());
+                        self.declared.insert(name.name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+struct Analyzer<'a> {
+    symbols: HashMap<String, &'a SymbolSignature>,
+    frames: Vec<Frame>,
+    diagnostics: Vec<Diagnostic>,
+
+    /// Names of the functions being analyzed, innermost last
+    functions: Vec<Option<String>>,
+
+    /// Whether `main()` is called from outside `main` itself
+    main_called: bool,
+
+    /// Keys passed to `📝`, as written in the source
+    memory_keys: HashSet<String>,
+
+    /// Keys in memory before the program runs, quoted like string literals
+    known_keys: HashSet<String>,
+
+    /// Keys passed to `📖` and the span of the symbol
+    file_reads: Vec<(String, Span)>,
+}
+
+impl<'a> Analyzer<'a> {
+    fn new(symbols: &'a [SymbolSignature]) -> Self {
+        // The globals a script starts with are whatever the interpreter installs
+        let globals = Scope::root();
+        builtins::install_globals(&globals);
+        let mut names = HashSet::new();
+        globals.visit_bindings(|name, _| {
+            names.insert(name.to_string());
+        });
+
+        Self {
+            symbols: symbols
+                .iter()
+                .map(|signature| (normalize(&signature.name), signature))
+                .collect(),
+            frames: vec![Frame {
+                all: names.clone(),
+                declared: names,
+                function: true,
+            }],
+            diagnostics: Vec::new(),
+            functions: Vec::new(),
+            main_called: false,
+            memory_keys: HashSet::new(),
+            known_keys: HashSet::new(),
+            file_reads: Vec::new(),
+        }
+    }
+
+    fn program(&mut self, program: &Program) {
+        self.stmts(&program.body, false);
+
+        let main = program.body.iter().find_map(|stmt| match &stmt.kind {
+            StmtKind::Function(decl) => decl.name.as_ref().filter(|name| name.name == "main"),
+            _ => None,
+        });
+        if let Some(main) = main {
+            if !self.main_called {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        codes::MISSING_MAIN_CALL,
+                        "Function 'main' is declared but never called",
+                        Some(main.span),
+                    )
+                    .with_note("add `main();` after the declaration to run it"),
+                );
+            }
+        }
+
+        // `📖` used to read memory as well as files; a key that the program
+        // stores with `📝` is almost certainly meant to be read with `📚`
+        for (key, span) in std::mem::take(&mut self.file_reads) {
+            let message = if self.memory_keys.contains(&key) {
+                format!("📖 reads a file, but {} is stored in memory with 📝", key)
+            } else if self.known_keys.contains(&key) {
+                format!("📖 reads a file, but {} is a key in memory", key)
+            } else {
+                continue;
+            };
+            self.diagnostics.push(
+                Diagnostic::warning(codes::MEMORY_READ_AS_FILE, message, Some(span))
+                    .with_note("read memory entries with 📚"),
+            );
+        }
+    }
+
+    // ----- scopes -----
+
+    fn push_frame(&mut self, stmts: &[Stmt], function: bool) {
+        let mut frame = Frame {
+            all: HashSet::new(),
+            declared: HashSet::new(),
+            function,
+        };
+        frame.collect(stmts);
+        self.frames.push(frame);
+    }
+
+    fn declare(&mut self, name: &str) {
+        let frame = self.frames.last_mut().expect("analyzer has a global frame");
+        frame.all.insert(name.to_string());
+        frame.declared.insert(name.to_string());
+    }
+
+    /// Whether `name` is defined at this point
+    ///
+    /// Inside the current function only declarations that already ran count;
+    /// enclosing scopes may declare the name later, since the function body
+    /// usually runs after they finished.
+    fn is_defined(&self, name: &str) -> bool {
+        let mut inside_function = true;
+
+        for frame in self.frames.iter().rev() {
+            let names = if inside_function {
+                &frame.declared
+            } else {
+                &frame.all
+            };
+            if names.contains(name) {
+                return true;
+            }
+            if frame.function {
+                inside_function = false;
+            }
+        }
+
+        false
+    }
+
+    fn check_defined(&mut self, name: &str, span: Span, assignment: bool) {
+        if self.is_defined(name) {
+            return;
+        }
+
+        let message = if assignment {
+            format!("Assignment to undeclared variable '{}'", name)
+        } else {
+            format!("Undefined variable '{}'", name)
+        };
+        self.diagnostics.push(
+            Diagnostic::error(codes::UNDEFINED_VARIABLE, message, Some(span))
+                .with_note(format!("declare it first with `ι{} = ...;`", name)),
+        );
+    }
+
+    // ----- statements -----
+
+    fn block(&mut self, block: &Block) {
+        self.push_frame(&block.stmts, false);
+        self.stmts(&block.stmts, true);
+        self.frames.pop();
+    }
+
+    /// Analyze a statement list; `scoped` is false for the program body,
+    /// which uses the global frame
+    fn stmts(&mut self, stmts: &[Stmt], scoped: bool) {
+        if !scoped {
+            let frame = self.frames.last_mut().expect("analyzer has a global frame");
+            frame.collect(stmts);
+        }
+
+        // Only the first unreachable statement of a list is reported
+        let mut exit: Option<&Stmt> = None;
+        let mut reported = false;
+        for stmt in stmts {
+            if let Some(exit) = exit.filter(|_| !reported) {
+                reported = true;
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        codes::UNREACHABLE_CODE,
+                        "Unreachable code",
+                        Some(stmt.span),
+                    )
+                    .with_note(format!(
+                        "control never gets past the {} on line {}",
+                        exit_name(exit),
+                        exit.span.line
+                    )),
+                );
+            }
+
+            self.stmt(stmt);
+
+            if matches!(
+                stmt.kind,
+                StmtKind::Return(_) | StmtKind::Export(_) | StmtKind::Break | StmtKind::Continue
+            ) && exit.is_none()
+            {
+                exit = Some(stmt);
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { name, init } => {
+                if let Some(init) = init {
+                    self.expr(init);
+                }
+                self.declare(&name.name);
+            }
+            StmtKind::Function(decl) => self.function(decl),
+            StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Print(args) => self.exprs(args),
+            StmtKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            StmtKind::ForEach { iterable, body } => {
+                self.expr(iterable);
+                match body {
+                    ForEachBody::Inline { params, body } => {
+                        self.push_frame(&body.stmts, false);
+                        self.params(params);
+                        self.stmts(&body.stmts, true);
+                        self.frames.pop();
+                    }
+                    ForEachBody::Callback(callback) => self.expr(callback),
+                }
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Export(value) => self.expr(value),
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::Try {
+                body,
+                param,
+                handler,
+            } => {
+                self.block(body);
+                self.push_frame(&handler.stmts, false);
+                if let Some(param) = param {
+                    self.declare(&param.name);
+                }
+                self.stmts(&handler.stmts, true);
+                self.frames.pop();
+            }
+            StmtKind::Block(block) => self.block(block),
+        }
+    }
+
+    fn function(&mut self, decl: &FunctionDecl) {
+        self.functions
+            .push(decl.name.as_ref().map(|name| name.name.clone()));
+        self.push_frame(&decl.body.stmts, true);
+        self.params(&decl.params);
+        self.stmts(&decl.body.stmts, true);
+        self.frames.pop();
+        self.functions.pop();
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            self.declare(&param.name.name);
+        }
+    }
+
+    // ----- expressions -----
+
+    fn exprs(&mut self, exprs: &[Expr]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Number(_)
+            | ExprKind::Str(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Null
+            | ExprKind::StringRef(_)
+            | ExprKind::Regex { .. } => {}
+            ExprKind::Template(parts) => {
+                for part in parts {
+                    if let TemplatePart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::Ident(name) => self.check_defined(name, expr.span, false),
+            ExprKind::Array(elements) => self.exprs(elements),
+            ExprKind::Object(props) => {
+                for prop in props {
+                    self.expr(&prop.value);
+                }
+            }
+            ExprKind::Lambda(decl) => self.function(decl),
+            ExprKind::Unary { op, operand } => {
+                // `typeof x` is the way to test whether `x` exists
+                let probe = *op == UnaryOp::Typeof && matches!(operand.kind, ExprKind::Ident(_));
+                if !probe {
+                    self.expr(operand);
+                }
+            }
+            ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Assign { target, value, .. } => {
+                self.expr(value);
+                self.target(target);
+            }
+            ExprKind::Update { target, .. } => self.target(target),
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            ExprKind::Member { object, .. } => self.expr(object),
+            ExprKind::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            ExprKind::Call { callee, args, .. } => {
+                if let ExprKind::Ident(name) = &callee.kind {
+                    let in_main = self.functions.iter().any(|f| f.as_deref() == Some("main"));
+                    if name == "main" && !in_main {
+                        self.main_called = true;
+                    }
+                }
+                self.expr(callee);
+                self.exprs(args);
+            }
+            ExprKind::SymbolCall { symbol, args } => {
+                self.symbol_call(symbol, args, expr.span);
+                self.exprs(args);
+            }
+            ExprKind::New { callee, args } => {
+                let builtin = matches!(&callee.kind, ExprKind::Ident(name) if CONSTRUCTORS.contains(&name.as_str()));
+                if !builtin {
+                    self.expr(callee);
+                }
+                self.exprs(args);
+            }
+            ExprKind::Spread(inner) => self.expr(inner),
+            ExprKind::Block(block) => self.block(block),
+        }
+    }
+
+    /// Check the target of an assignment
+    fn target(&mut self, target: &Expr) {
+        match &target.kind {
+            ExprKind::Ident(name) => self.check_defined(name, target.span, true),
+            _ => self.expr(target),
+        }
+    }
+
+    fn symbol_call(&mut self, symbol: &str, args: &[Expr], span: Span) {
+        // Point at the symbol rather than the whole call
+        let symbol_span = Span::new(
+            span.start,
+            (span.start + symbol.len()).min(span.end),
+            span.line,
+            span.column,
+        );
+
+        let key = args.first().and_then(key_text);
+        match (normalize(symbol).as_str(), key) {
+            ("📝", Some(key)) => {
+                self.memory_keys.insert(key);
+            }
+            ("📖", Some(key)) => self.file_reads.push((key, symbol_span)),
+            _ => {}
+        }
+
+        let signature = match self.symbols.get(&normalize(symbol)) {
+            Some(signature) => *signature,
+            None => {
+                self.diagnostics.push(Diagnostic::error(
+                    codes::UNKNOWN_SYMBOL,
+                    format!("Unknown symbol '{}'", symbol),
+                    Some(symbol_span),
+                ));
+                return;
+            }
+        };
+
+        // The number of spread arguments is only known at runtime
+        if args
+            .iter()
+            .any(|arg| matches!(arg.kind, ExprKind::Spread(_)))
+        {
+            return;
+        }
+
+        if !signature.accepts(args.len()) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    codes::WRONG_ARITY,
+                    format!(
+                        "{} expects {}, got {}",
+                        signature.name,
+                        signature.arity_description(),
+                        args.len()
+                    ),
+                    Some(symbol_span),
+                )
+                .with_note(format!("usage: {}", signature.usage())),
+            );
+        }
+    }
+}
+
+/// A key or path argument as written: string literals quoted, variables by name
+fn key_text(expr: &Expr) -> Option<String> {
+    match &expr.kind {
+        ExprKind::Str(text) => Some(format!("{:?}", text)),
+        ExprKind::Ident(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// How a control-flow statement is written, for notes
+fn exit_name(stmt: &Stmt) -> &'static str {
+    match stmt.kind {
+        StmtKind::Return(_) => "return",
+        StmtKind::Export(_) => "⟼",
+        StmtKind::Break => "↵",
+        _ => "continue",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{Capability, ValueType};
+
+    /// Codes of the diagnostics for `code`, which may call the core symbols
+    /// and `📝`
+    fn codes_for(code: &str) -> Vec<String> {
+        let mut symbols = builtins::core_symbols();
+        symbols.push(
+            SymbolSignature::new("📝", Capability::MemoryWrite)
+                .param("key", ValueType::String)
+                .param("value", ValueType::String)
+                .returns(ValueType::String),
+        );
+
+        analyze_code(code, &symbols)
+            .into_iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+
+    #[test]
+    fn undefined_variables() {
+        assert_eq!(codes_for("ι x = 1; ⌽(y);"), [codes::UNDEFINED_VARIABLE]);
+        assert!(codes_for("ι x = 1; ⌽(x);").is_empty());
+    }
+
+    #[test]
+    fn symbol_calls_with_the_wrong_arity() {
+        assert_eq!(codes_for(r#"📝("theme");"#), [codes::WRONG_ARITY]);
+        assert!(codes_for(r#"📝("theme", "dark");"#).is_empty());
+    }
+
+    #[test]
+    fn unknown_symbols() {
+        assert_eq!(codes_for(r#"📒("notes");"#), [codes::UNKNOWN_SYMBOL]);
+        assert!(codes_for(r#"🔤(1);"#).is_empty());
+    }
+
+    #[test]
+    fn code_after_a_return() {
+        assert_eq!(
+            codes_for("ƒf() { ⟼ 1; ⌽(2); } f();"),
+            [codes::UNREACHABLE_CODE]
+        );
+        assert!(codes_for("ƒf(x) { ↪(x) { ⟼ 1; } ⌽(2); } f(true);").is_empty());
+    }
+
+    #[test]
+    fn main_functions_that_are_never_called() {
+        assert_eq!(codes_for("ƒmain() { ⌽(1); }"), [codes::MISSING_MAIN_CALL]);
+        assert!(codes_for("ƒmain() { ⌽(1); } main();").is_empty());
+    }
+}
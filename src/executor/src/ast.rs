@@ -0,0 +1,439 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Location of a node in the source code
+///
+/// `start` and `end` are byte offsets into the source, `line` and `column`
+/// are 1-based and point at the first character of the node. Columns count
+/// characters, not bytes, so emoji symbols occupy a single column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+
+    /// Byte offset one past the last character
+    pub end: usize,
+
+    /// Line of the first character
+    pub line: usize,
+
+    /// Column of the first character
+    pub column: usize,
+}
+
+impl Span {
+    /// Create a new span
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Create a span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        if other.end <= self.start {
+            return Span::new(other.start, self.end, other.line, other.column);
+        }
+
+        Span::new(self.start, other.end.max(self.end), self.line, self.column)
+    }
+}
+
+/// An identifier together with its location
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A complete Anarchy-Inference program
+#[derive(Debug, Clone, Serialize)]
+pub struct Program {
+    pub body: Vec<Stmt>,
+    pub span: Span,
+
+    /// Comments in source order, kept for the formatter
+    pub comments: Vec<Comment>,
+}
+
+/// A `// line` or `/* block */` comment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comment {
+    /// Source text of the comment, including its delimiters
+    pub text: String,
+
+    pub span: Span,
+
+    /// Whether the comment follows code on the same line
+    pub trailing: bool,
+}
+
+/// A sequence of statements delimited by braces
+#[derive(Debug, Clone, Serialize)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub span: Span,
+}
+
+/// A function declared with `ƒ` or created with `λ`
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionDecl {
+    /// Name of the function, `None` for anonymous lambdas
+    pub name: Option<Ident>,
+
+    /// Parameter list
+    pub params: Vec<Param>,
+
+    /// Function body
+    pub body: Block,
+
+    pub span: Span,
+}
+
+/// A function parameter
+#[derive(Debug, Clone, Serialize)]
+pub struct Param {
+    pub name: Ident,
+
+    /// Optional type sigil written before the name (e.g. `σ` in `σtask`)
+    pub sigil: Option<char>,
+
+    /// Default value used when the argument is missing
+    pub default: Option<Expr>,
+
+    /// Whether this is a rest parameter (`...args`) collecting remaining arguments
+    pub rest: bool,
+}
+
+/// A statement
+#[derive(Debug, Clone, Serialize)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+/// The different kinds of statements
+#[derive(Debug, Clone, Serialize)]
+pub enum StmtKind {
+    /// `ιname = value;`
+    Let { name: Ident, init: Option<Expr> },
+
+    /// `ƒname(params) { ... }`
+    Function(Arc<FunctionDecl>),
+
+    /// An expression evaluated for its side effects
+    Expr(Expr),
+
+    /// `⌽(args)`
+    Print(Vec<Expr>),
+
+    /// `↪(cond) { ... } ↛ { ... }` or `if (cond) { ... } else { ... }`
+    ///
+    /// The else branch is either a `Block` statement or another `If`.
+    If {
+        cond: Expr,
+        then_branch: Block,
+        else_branch: Option<Box<Stmt>>,
+    },
+
+    /// `↻(cond) { ... }` or `while (cond) { ... }`; a bare `↺ { ... }` loop
+    /// is parsed as `while (true)`
+    While { cond: Expr, body: Block },
+
+    /// `∀(iterable, λitem { ... })`
+    ForEach { iterable: Expr, body: ForEachBody },
+
+    /// `↩ value;` or `⟼(value)` inside a function
+    Return(Option<Expr>),
+
+    /// `⟼(value)` at the top level of a file
+    Export(Expr),
+
+    /// `↵` or `break`
+    Break,
+
+    /// `continue` or `↻;`
+    Continue,
+
+    /// `÷{ ... }{ ... }` or `↺ { ... } ⚠(e) { ... }`
+    Try {
+        body: Block,
+        param: Option<Ident>,
+        handler: Block,
+    },
+
+    /// A nested block
+    Block(Block),
+}
+
+/// Body of a `∀` loop
+#[derive(Debug, Clone, Serialize)]
+pub enum ForEachBody {
+    /// An inline lambda whose body runs as the loop body
+    Inline { params: Vec<Param>, body: Block },
+
+    /// Any other expression, called once per element
+    Callback(Expr),
+}
+
+/// An expression
+#[derive(Debug, Clone, Serialize)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+/// The different kinds of expressions
+#[derive(Debug, Clone, Serialize)]
+pub enum ExprKind {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+
+    /// Template string with `${...}` interpolations
+    Template(Vec<TemplatePart>),
+
+    /// Regular expression literal `/pattern/flags`
+    Regex {
+        pattern: String,
+        flags: String,
+    },
+
+    Ident(String),
+
+    /// `:key`, a string from the dictionaries loaded with `🔠`
+    StringRef(String),
+
+    Array(Vec<Expr>),
+    Object(Vec<Property>),
+
+    /// `λ(params) { ... }` or an anonymous `ƒ(params) { ... }`
+    Lambda(Arc<FunctionDecl>),
+
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Logical {
+        op: LogicalOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+
+    /// Assignment to an identifier, member or index expression
+    Assign {
+        op: AssignOp,
+        target: Box<Expr>,
+        value: Box<Expr>,
+    },
+
+    /// `x++`, `--x`, ...
+    Update {
+        op: UpdateOp,
+        prefix: bool,
+        target: Box<Expr>,
+    },
+
+    /// `cond ? then : else`
+    Conditional {
+        cond: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+
+    /// `object.property` or `object?.property`
+    Member {
+        object: Box<Expr>,
+        property: Ident,
+        optional: bool,
+    },
+
+    /// `object[index]` or `object?.[index]`
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+        optional: bool,
+    },
+
+    /// `callee(args)`
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+        optional: bool,
+    },
+
+    /// `📂(args)`, `!(args)`, ... dispatched to a registered symbol handler
+    SymbolCall {
+        symbol: String,
+        args: Vec<Expr>,
+    },
+
+    /// `new Callee(args)`
+    New {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+
+    /// `...expr` inside call arguments or an array literal
+    Spread(Box<Expr>),
+
+    /// A block used as an expression, as in `cond ? { ... } : { ... }`
+    Block(Block),
+}
+
+/// A segment of a template string
+#[derive(Debug, Clone, Serialize)]
+pub enum TemplatePart {
+    Text(String),
+    Expr(Expr),
+}
+
+/// A property in an object literal
+#[derive(Debug, Clone, Serialize)]
+pub struct Property {
+    pub key: String,
+    pub value: Expr,
+
+    /// Whether the property was written in shorthand form (`{ name }`)
+    pub shorthand: bool,
+
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UnaryOp {
+    Not,
+    Neg,
+    Plus,
+    Typeof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    NotEq,
+    StrictEq,
+    StrictNotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LogicalOp {
+    And,
+    Or,
+    Nullish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AssignOp {
+    Assign,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UpdateOp {
+    Increment,
+    Decrement,
+}
+
+impl UnaryOp {
+    /// Source representation of the operator
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOp::Not => "!",
+            UnaryOp::Neg => "-",
+            UnaryOp::Plus => "+",
+            UnaryOp::Typeof => "typeof ",
+        }
+    }
+}
+
+impl BinaryOp {
+    /// Source representation of the operator
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::StrictEq => "===",
+            BinaryOp::StrictNotEq => "!==",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+        }
+    }
+}
+
+impl LogicalOp {
+    /// Source representation of the operator
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogicalOp::And => "&&",
+            LogicalOp::Or => "||",
+            LogicalOp::Nullish => "??",
+        }
+    }
+}
+
+impl AssignOp {
+    /// Source representation of the operator
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignOp::Assign => "=",
+            AssignOp::Add => "+=",
+            AssignOp::Sub => "-=",
+            AssignOp::Mul => "*=",
+            AssignOp::Div => "/=",
+        }
+    }
+
+    /// The binary operator applied by a compound assignment
+    pub fn binary(&self) -> Option<BinaryOp> {
+        match self {
+            AssignOp::Assign => None,
+            AssignOp::Add => Some(BinaryOp::Add),
+            AssignOp::Sub => Some(BinaryOp::Sub),
+            AssignOp::Mul => Some(BinaryOp::Mul),
+            AssignOp::Div => Some(BinaryOp::Div),
+        }
+    }
+}
+
+impl Expr {
+    /// Create a new expression
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl Stmt {
+    /// Create a new statement
+    pub fn new(kind: StmtKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
@@ -0,0 +1,633 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::interpreter::Env;
+use crate::registry::{Capability, SymbolSignature, ValueType};
+use crate::value::{format_number, RegexValue, Value};
+
+/// Longest array an index assignment may grow an array to
+///
+/// Assigning past the end fills the gap with `null`, so without a bound a
+/// single `a[1e9] = 1` would allocate gigabytes.
+pub const MAX_ARRAY_LENGTH: usize = 1 << 20;
+
+impl RegexValue {
+    /// Compile a regex literal, translating JavaScript-style flags
+    pub fn new(pattern: &str, flags: &str) -> Result<Self> {
+        let mut inline = String::new();
+        for flag in flags.chars() {
+            match flag {
+                'i' | 'm' | 's' => inline.push(flag),
+                'g' | 'u' | 'y' => {}
+                other => return Err(anyhow!("Unsupported regex flag '{}'", other)),
+            }
+        }
+
+        let source = if inline.is_empty() {
+            pattern.to_string()
+        } else {
+            format!("(?{}){}", inline, pattern)
+        };
+
+        let regex = regex::Regex::new(&source)
+            .map_err(|e| anyhow!("Invalid regular expression /{}/: {}", pattern, e))?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            flags: flags.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether the `g` flag is set
+    pub fn is_global(&self) -> bool {
+        self.flags.contains('g')
+    }
+}
+
+/// Signatures of the symbols implemented by the interpreter itself
+pub fn core_symbols() -> Vec<SymbolSignature> {
+    vec![
+        SymbolSignature::new("＋", Capability::Core)
+            .param("array", ValueType::Array)
+            .param("item", ValueType::Any)
+            .variadic()
+            .returns(ValueType::Number)
+            .help("Append items to an array and return its new length"),
+        SymbolSignature::new("🔢", Capability::Core)
+            .param("value", ValueType::Any)
+            .returns(ValueType::Number)
+            .help("Convert a value to a number"),
+        SymbolSignature::new("🔤", Capability::Core)
+            .param("value", ValueType::Any)
+            .returns(ValueType::String)
+            .help("Convert a value to a string"),
+        SymbolSignature::new("⏰", Capability::Core)
+            .param("ms", ValueType::Number)
+            .returns(ValueType::Null)
+            .help("Sleep for the given number of milliseconds"),
+        SymbolSignature::new("⟰", Capability::FileRead)
+            .param("path", ValueType::String)
+            .optional("name", ValueType::String)
+            .returns(ValueType::Any)
+            .help("Import a module file (`.a.i` may be omitted) and return its first export, or the export called `name`"),
+        SymbolSignature::new("🔠", Capability::FileRead)
+            .param("dictionary", ValueType::Any)
+            .returns(ValueType::Number)
+            .help("Load a JSON file (or an object) of strings for `:key` references; returns the number of strings loaded"),
+        SymbolSignature::new("💾", Capability::FileWrite)
+            .param("name", ValueType::String)
+            .param("path", ValueType::String)
+            .returns(ValueType::Number)
+            .help("Save the strings for `:key` references, including those stored with `📝`, into the JSON file of strings for the dictionary `name`; returns the number of strings in the file"),
+        SymbolSignature::new("⎋", Capability::Core)
+            .param("value", ValueType::Any)
+            .returns(ValueType::Any)
+            .help("Encode a value as JSON, or decode a JSON string"),
+    ]
+}
+
+/// Install the global objects available to every script (`Date`, `JSON`, `Math`, ...)
+pub fn install_globals(env: &Env) {
+    let mut date = BTreeMap::new();
+    date.insert(
+        "now".to_string(),
+        Value::native("Date.now", |_| Ok(Value::Number(now_millis()))),
+    );
+    env.declare("Date", Value::object(date));
+
+    let mut json = BTreeMap::new();
+    json.insert(
+        "stringify".to_string(),
+        Value::native("JSON.stringify", |args| {
+            Ok(Value::Str(json_encode(&arg(&args, 0))))
+        }),
+    );
+    json.insert(
+        "parse".to_string(),
+        Value::native("JSON.parse", |args| json_decode(&arg(&args, 0).to_string())),
+    );
+    env.declare("JSON", Value::object(json));
+
+    let mut math = BTreeMap::new();
+    type MathFn = fn(f64) -> f64;
+    let unary: [(&str, MathFn); 6] = [
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("round", f64::round),
+        ("abs", f64::abs),
+        ("sqrt", f64::sqrt),
+        ("trunc", f64::trunc),
+    ];
+    for (name, op) in unary {
+        math.insert(
+            name.to_string(),
+            Value::native(&format!("Math.{}", name), move |args| {
+                Ok(Value::Number(op(arg(&args, 0).to_number())))
+            }),
+        );
+    }
+    math.insert(
+        "pow".to_string(),
+        Value::native("Math.pow", |args| {
+            Ok(Value::Number(
+                arg(&args, 0).to_number().powf(arg(&args, 1).to_number()),
+            ))
+        }),
+    );
+    math.insert(
+        "max".to_string(),
+        Value::native("Math.max", |args| {
+            Ok(Value::Number(
+                args.iter()
+                    .map(Value::to_number)
+                    .fold(f64::NEG_INFINITY, f64::max),
+            ))
+        }),
+    );
+    math.insert(
+        "min".to_string(),
+        Value::native("Math.min", |args| {
+            Ok(Value::Number(
+                args.iter()
+                    .map(Value::to_number)
+                    .fold(f64::INFINITY, f64::min),
+            ))
+        }),
+    );
+    math.insert("PI".to_string(), Value::Number(std::f64::consts::PI));
+    env.declare("Math", Value::object(math));
+
+    let mut object = BTreeMap::new();
+    object.insert(
+        "keys".to_string(),
+        Value::native("Object.keys", |args| match arg(&args, 0) {
+            Value::Object(props) => {
+                let keys = props
+                    .lock()
+                    .unwrap()
+                    .keys()
+                    .cloned()
+                    .map(Value::Str)
+                    .collect();
+                Ok(Value::array(keys))
+            }
+            _ => Ok(Value::array(Vec::new())),
+        }),
+    );
+    object.insert(
+        "values".to_string(),
+        Value::native("Object.values", |args| match arg(&args, 0) {
+            Value::Object(props) => {
+                let values = props.lock().unwrap().values().cloned().collect();
+                Ok(Value::array(values))
+            }
+            _ => Ok(Value::array(Vec::new())),
+        }),
+    );
+    env.declare("Object", Value::object(object));
+
+    env.declare(
+        "parseInt",
+        Value::native("parseInt", |args| {
+            let text = arg(&args, 0).to_string();
+            let digits: String = text
+                .trim()
+                .chars()
+                .enumerate()
+                .take_while(|(i, c)| c.is_ascii_digit() || (*i == 0 && (*c == '-' || *c == '+')))
+                .map(|(_, c)| c)
+                .collect();
+            Ok(Value::Number(digits.parse::<f64>().unwrap_or(f64::NAN)))
+        }),
+    );
+    env.declare(
+        "parseFloat",
+        Value::native("parseFloat", |args| {
+            Ok(Value::Number(arg(&args, 0).to_number()))
+        }),
+    );
+    env.declare(
+        "String",
+        Value::native("String", |args| Ok(Value::Str(arg(&args, 0).to_string()))),
+    );
+    env.declare(
+        "Number",
+        Value::native("Number", |args| {
+            Ok(Value::Number(arg(&args, 0).to_number()))
+        }),
+    );
+}
+
+/// Construct a built-in object with `new`, returning `None` for unknown names
+pub fn construct(name: &str, args: &[Value]) -> Option<Result<Value>> {
+    match name {
+        "Date" => {
+            let millis = match args.first() {
+                Some(value) => value.to_number(),
+                None => now_millis(),
+            };
+            Some(Ok(date_object(millis)))
+        }
+        "Error" => {
+            let mut props = BTreeMap::new();
+            props.insert("message".to_string(), Value::Str(arg(args, 0).to_string()));
+            Some(Ok(Value::object(props)))
+        }
+        "Object" => Some(Ok(Value::object(BTreeMap::new()))),
+        "Array" => Some(Ok(Value::array(Vec::new()))),
+        _ => None,
+    }
+}
+
+/// Read a property of a primitive, array or object value
+pub fn get_property(target: &Value, name: &str) -> Value {
+    match target {
+        Value::Object(props) => props
+            .lock()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or(Value::Null),
+        Value::Array(items) if name == "length" => {
+            Value::Number(items.lock().unwrap().len() as f64)
+        }
+        Value::Str(s) if name == "length" => Value::Number(s.chars().count() as f64),
+        Value::Bytes(data) if name == "length" => Value::Number(data.len() as f64),
+        Value::Regex(re) => match name {
+            "source" => Value::Str(re.pattern.clone()),
+            "flags" => Value::Str(re.flags.clone()),
+            _ => Value::Null,
+        },
+        _ => Value::Null,
+    }
+}
+
+/// Read `target[index]`
+pub fn get_index(target: &Value, index: &Value) -> Value {
+    match (target, index) {
+        (Value::Array(items), Value::Number(n)) => {
+            let items = items.lock().unwrap();
+            to_index(*n)
+                .and_then(|i| items.get(i).cloned())
+                .unwrap_or(Value::Null)
+        }
+        (Value::Str(s), Value::Number(n)) => to_index(*n)
+            .and_then(|i| s.chars().nth(i))
+            .map(|c| Value::Str(c.to_string()))
+            .unwrap_or(Value::Null),
+        (Value::Bytes(data), Value::Number(n)) => to_index(*n)
+            .and_then(|i| data.get(i))
+            .map(|byte| Value::Number(*byte as f64))
+            .unwrap_or(Value::Null),
+        (target, index) => get_property(target, &index.to_string()),
+    }
+}
+
+/// Write `target[index] = value`
+pub fn set_index(target: &Value, index: &Value, value: Value) -> Result<()> {
+    match (target, index) {
+        (Value::Array(items), Value::Number(n)) => {
+            let i =
+                to_index(*n).ok_or_else(|| anyhow!("Invalid array index {}", format_number(*n)))?;
+            let mut items = items.lock().unwrap();
+            if i >= items.len() {
+                if i >= MAX_ARRAY_LENGTH {
+                    return Err(anyhow!(
+                        "Array index {} is too large, arrays can grow to {} items by assignment",
+                        format_number(*n),
+                        MAX_ARRAY_LENGTH
+                    ));
+                }
+                items.resize(i + 1, Value::Null);
+            }
+            items[i] = value;
+            Ok(())
+        }
+        (Value::Object(props), index) => {
+            props.lock().unwrap().insert(index.to_string(), value);
+            Ok(())
+        }
+        (target, _) => Err(anyhow!(
+            "Cannot set index on a value of type {}",
+            target.type_name()
+        )),
+    }
+}
+
+/// Call a built-in method that does not take callbacks
+///
+/// Returns `None` when the receiver has no method with that name.
+pub fn call_method(receiver: &Value, name: &str, args: &[Value]) -> Option<Result<Value>> {
+    match receiver {
+        Value::Str(s) => string_method(s, name, args),
+        Value::Array(items) => array_method(items, name, args),
+        Value::Number(n) => match name {
+            "toFixed" => {
+                let digits = arg(args, 0).to_number().max(0.0) as usize;
+                Some(Ok(Value::Str(format!("{:.*}", digits, n))))
+            }
+            "toString" => Some(Ok(Value::Str(format_number(*n)))),
+            _ => None,
+        },
+        Value::Bytes(data) => match name {
+            "text" => Some(Ok(Value::Str(String::from_utf8_lossy(data).into_owned()))),
+            _ => None,
+        },
+        Value::Regex(re) => match name {
+            "test" => Some(Ok(Value::Bool(
+                re.regex.is_match(&arg(args, 0).to_string()),
+            ))),
+            _ => None,
+        },
+        _ => match name {
+            "toString" => Some(Ok(Value::Str(receiver.to_string()))),
+            _ => None,
+        },
+    }
+}
+
+fn string_method(s: &str, name: &str, args: &[Value]) -> Option<Result<Value>> {
+    let text = |i: usize| arg(args, i).to_string();
+
+    let result = match name {
+        "toUpperCase" => Value::Str(s.to_uppercase()),
+        "toLowerCase" => Value::Str(s.to_lowercase()),
+        "trim" => Value::Str(s.trim().to_string()),
+        "toString" => Value::Str(s.to_string()),
+        "includes" => Value::Bool(s.contains(&text(0))),
+        "startsWith" => Value::Bool(s.starts_with(&text(0))),
+        "endsWith" => Value::Bool(s.ends_with(&text(0))),
+        "indexOf" => Value::Number(
+            s.find(&text(0))
+                .map(|byte| s[..byte].chars().count() as f64)
+                .unwrap_or(-1.0),
+        ),
+        "repeat" => Value::Str(s.repeat(arg(args, 0).to_number().max(0.0) as usize)),
+        "charAt" => get_index(&Value::Str(s.to_string()), &arg(args, 0)),
+        "substring" | "slice" => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = slice_bounds(chars.len(), args, name == "slice");
+            Value::Str(chars[start..end].iter().collect())
+        }
+        "split" => {
+            let parts: Vec<Value> = match args.first() {
+                None => vec![Value::Str(s.to_string())],
+                Some(Value::Regex(re)) => re.regex.split(s).map(Value::from).collect(),
+                Some(sep) => {
+                    let sep = sep.to_string();
+                    if sep.is_empty() {
+                        s.chars().map(|c| Value::Str(c.to_string())).collect()
+                    } else {
+                        s.split(sep.as_str()).map(Value::from).collect()
+                    }
+                }
+            };
+            Value::array(parts)
+        }
+        "match" => {
+            let re = match args.first() {
+                Some(Value::Regex(re)) => Arc::clone(re),
+                Some(other) => match RegexValue::new(&regex::escape(&other.to_string()), "") {
+                    Ok(re) => Arc::new(re),
+                    Err(e) => return Some(Err(e)),
+                },
+                None => return Some(Ok(Value::Null)),
+            };
+            regex_match(&re, s)
+        }
+        "replace" | "replaceAll" => {
+            let replacement = text(1);
+            match args.first() {
+                Some(Value::Regex(re)) => {
+                    let replacement = replacement.replace("$&", "${0}");
+                    if re.is_global() || name == "replaceAll" {
+                        Value::Str(re.regex.replace_all(s, replacement.as_str()).into_owned())
+                    } else {
+                        Value::Str(re.regex.replace(s, replacement.as_str()).into_owned())
+                    }
+                }
+                _ => {
+                    let pattern = text(0);
+                    if name == "replaceAll" {
+                        Value::Str(s.replace(&pattern, &replacement))
+                    } else {
+                        Value::Str(s.replacen(&pattern, &replacement, 1))
+                    }
+                }
+            }
+        }
+        _ => return None,
+    };
+
+    Some(Ok(result))
+}
+
+fn array_method(items: &crate::value::Array, name: &str, args: &[Value]) -> Option<Result<Value>> {
+    let result = match name {
+        "push" => {
+            let mut items = items.lock().unwrap();
+            items.extend(args.iter().cloned());
+            Value::Number(items.len() as f64)
+        }
+        "pop" => items.lock().unwrap().pop().unwrap_or(Value::Null),
+        "shift" => {
+            let mut items = items.lock().unwrap();
+            if items.is_empty() {
+                Value::Null
+            } else {
+                items.remove(0)
+            }
+        }
+        "unshift" => {
+            let mut items = items.lock().unwrap();
+            for (i, value) in args.iter().enumerate() {
+                items.insert(i, value.clone());
+            }
+            Value::Number(items.len() as f64)
+        }
+        "join" => {
+            let sep = match args.first() {
+                Some(sep) => sep.to_string(),
+                None => ",".to_string(),
+            };
+            let items = items.lock().unwrap();
+            Value::Str(
+                items
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(&sep),
+            )
+        }
+        "includes" => {
+            let needle = arg(args, 0);
+            Value::Bool(items.lock().unwrap().iter().any(|v| v.strict_eq(&needle)))
+        }
+        "indexOf" => {
+            let needle = arg(args, 0);
+            let items = items.lock().unwrap();
+            Value::Number(
+                items
+                    .iter()
+                    .position(|v| v.strict_eq(&needle))
+                    .map(|i| i as f64)
+                    .unwrap_or(-1.0),
+            )
+        }
+        "slice" => {
+            let items = items.lock().unwrap();
+            let (start, end) = slice_bounds(items.len(), args, true);
+            Value::array(items[start..end].to_vec())
+        }
+        "concat" => {
+            let mut result = items.lock().unwrap().clone();
+            for value in args {
+                match value {
+                    Value::Array(other) => result.extend(other.lock().unwrap().iter().cloned()),
+                    other => result.push(other.clone()),
+                }
+            }
+            Value::array(result)
+        }
+        "reverse" => {
+            items.lock().unwrap().reverse();
+            Value::Array(Arc::clone(items))
+        }
+        "toString" => Value::Str(Value::Array(Arc::clone(items)).to_string()),
+        _ => return None,
+    };
+
+    Some(Ok(result))
+}
+
+/// Apply a regex to a string the way `String.prototype.match` does
+pub fn regex_match(re: &RegexValue, s: &str) -> Value {
+    if re.is_global() {
+        let matches: Vec<Value> = re
+            .regex
+            .find_iter(s)
+            .map(|m| Value::from(m.as_str()))
+            .collect();
+        if matches.is_empty() {
+            Value::Null
+        } else {
+            Value::array(matches)
+        }
+    } else {
+        match re.regex.captures(s) {
+            Some(caps) => Value::array(
+                caps.iter()
+                    .map(|m| m.map(|m| Value::from(m.as_str())).unwrap_or(Value::Null))
+                    .collect(),
+            ),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Encode a value as compact JSON text
+pub fn json_encode(value: &Value) -> String {
+    value.to_json().to_string()
+}
+
+/// Decode JSON text into a value
+pub fn json_decode(text: &str) -> Result<Value> {
+    let json: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| anyhow!("Invalid JSON: {}", e))?;
+    Ok(Value::from_json(&json))
+}
+
+/// Argument `i`, or `null` when it was not supplied
+pub fn arg(args: &[Value], i: usize) -> Value {
+    args.get(i).cloned().unwrap_or(Value::Null)
+}
+
+fn to_index(n: f64) -> Option<usize> {
+    if n >= 0.0 && n.fract() == 0.0 {
+        Some(n as usize)
+    } else {
+        None
+    }
+}
+
+/// Resolve `(start, end)` arguments of `slice`/`substring` against a length
+fn slice_bounds(len: usize, args: &[Value], allow_negative: bool) -> (usize, usize) {
+    let resolve = |value: Option<&Value>, default: usize| -> usize {
+        match value {
+            None | Some(Value::Null) => default,
+            Some(value) => {
+                let n = value.to_number();
+                if n.is_nan() {
+                    0
+                } else if n < 0.0 {
+                    if allow_negative {
+                        len.saturating_sub((-n) as usize)
+                    } else {
+                        0
+                    }
+                } else {
+                    (n as usize).min(len)
+                }
+            }
+        }
+    };
+
+    let start = resolve(args.first(), 0);
+    let end = resolve(args.get(1), len);
+    (start.min(end), end)
+}
+
+fn now_millis() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as f64)
+        .unwrap_or(0.0)
+}
+
+/// Build the object returned by `new Date()`
+fn date_object(millis: f64) -> Value {
+    let mut props = BTreeMap::new();
+    props.insert(
+        "getTime".to_string(),
+        Value::native("Date.getTime", move |_| Ok(Value::Number(millis))),
+    );
+    props.insert(
+        "toISOString".to_string(),
+        Value::native("Date.toISOString", move |_| {
+            Ok(Value::Str(iso_8601(millis)))
+        }),
+    );
+    Value::object(props)
+}
+
+/// Format a Unix timestamp in milliseconds as an ISO 8601 UTC string
+fn iso_8601(millis: f64) -> String {
+    let total_ms = millis as i64;
+    let days = total_ms.div_euclid(86_400_000);
+    let ms_of_day = total_ms.rem_euclid(86_400_000);
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
@@ -0,0 +1,1111 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::ast::*;
+use crate::modules;
+use crate::value::Value;
+
+/// Index into the span table of a chunk
+pub(crate) type SpanId = u32;
+
+/// Arguments a call or literal takes from the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Args {
+    /// The top `n` values, first argument deepest
+    Count(u32),
+
+    /// A single list built with `List`, `Push` and `Extend`, used when an
+    /// argument is spread
+    List,
+}
+
+/// How deeply nested the VM is inside one chunk
+///
+/// Leaving a loop, block expression or `÷` body early unwinds to the depth
+/// recorded where it started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Depth {
+    /// Block scopes entered
+    pub scopes: u32,
+
+    /// `÷` handlers active
+    pub handlers: u32,
+
+    /// Statements running, each remembering the one it interrupted
+    pub statements: u32,
+
+    /// `∀` loops running
+    pub iterators: u32,
+}
+
+/// A VM instruction
+///
+/// Jump targets are instruction indices in the same chunk; names, constants
+/// and spans are indices into the chunk's tables.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Op {
+    // ----- values -----
+    /// Push a constant
+    Constant(u32),
+
+    /// Push a new regular expression built from a pattern and flags
+    Regex {
+        regex: u32,
+        span: SpanId,
+    },
+
+    /// Push the value of a variable
+    Load {
+        name: u32,
+        span: SpanId,
+    },
+
+    /// Push the `typeof` of a variable, `"undefined"` if it is not declared
+    TypeofName(u32),
+
+    /// Push a string loaded with `🔠`
+    StringRef {
+        key: u32,
+        span: SpanId,
+    },
+
+    /// Push a closure over the current scope
+    Closure(u32),
+
+    /// Build an array from arguments
+    Array(Args),
+
+    /// Build an object from the top values, one per key of a key list
+    Object(u32),
+
+    /// Join the top values into a template string
+    Template(u32),
+
+    Pop,
+    Swap,
+
+    // ----- operators -----
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+
+    /// Replace the top value by the result of `++` / `--` and, above it,
+    /// the value to store
+    Update {
+        op: UpdateOp,
+        prefix: bool,
+    },
+
+    /// Jump keeping the top value if it decides a logical operator, pop it otherwise
+    ShortCircuit {
+        op: LogicalOp,
+        target: u32,
+    },
+
+    // ----- variables and properties -----
+    /// Pop a value into a new variable of the current scope
+    Declare(u32),
+
+    /// Assign the top value to an existing variable, keeping it
+    Store {
+        name: u32,
+        span: SpanId,
+    },
+
+    /// Replace an object by one of its properties
+    GetMember {
+        name: u32,
+        optional: bool,
+        span: SpanId,
+    },
+
+    /// Pop an object and set a property to the value below it
+    SetMember {
+        name: u32,
+        span: SpanId,
+    },
+
+    /// Check the value being indexed, jumping with `null` for `?.[`
+    IndexGuard {
+        optional: bool,
+        target: u32,
+        span: SpanId,
+    },
+
+    /// Replace an object and an index by the indexed value
+    GetIndex,
+
+    /// Pop an object and an index and store the value below them
+    SetIndex {
+        span: SpanId,
+    },
+
+    // ----- argument lists -----
+    /// Push an empty argument list
+    List,
+
+    /// Pop a value onto the argument list below it
+    Push,
+
+    /// Pop a value and append its elements to the argument list below it
+    Extend {
+        span: SpanId,
+    },
+
+    // ----- calls -----
+    /// Call the value below the arguments
+    Call {
+        args: Args,
+        span: SpanId,
+    },
+
+    /// Check the receiver of a method call, jumping with `null` for `?.`
+    MethodGuard {
+        name: u32,
+        optional: bool,
+        target: u32,
+        span: SpanId,
+    },
+
+    /// Call a method of the value below the arguments
+    CallMethod {
+        name: u32,
+        args: Args,
+        span: SpanId,
+    },
+
+    /// Jump keeping the top value if it is `null`
+    JumpIfNull(u32),
+
+    /// Call a registered symbol
+    CallSymbol {
+        symbol: u32,
+        args: Args,
+        span: SpanId,
+    },
+
+    /// Run a built-in constructor on the arguments and jump, if `name` is one
+    Construct {
+        name: u32,
+        args: Args,
+        target: u32,
+        span: SpanId,
+    },
+
+    /// Pop a function and call it with the arguments below it, for `new`
+    New {
+        args: Args,
+        span: SpanId,
+    },
+
+    // ----- statements -----
+    /// Start a statement, pausing in the debugger if it wants to
+    Statement(SpanId),
+
+    /// Start an expression statement of a block expression, which the
+    /// debugger does not stop at
+    ExprStatement(SpanId),
+
+    /// Start a top-level statement of a script or module, which nothing
+    /// returns to
+    TopStatement(SpanId),
+
+    /// Go back to the statement the current one interrupted
+    EndStatement,
+
+    Print(Args),
+
+    /// Pop a value into the exports of the module
+    Export(u32),
+
+    /// Finish the chunk with the top value
+    Return,
+
+    /// Fail once the execution time limit has passed
+    CheckDeadline(SpanId),
+
+    // ----- control flow -----
+    Jump(u32),
+
+    /// Pop a condition and jump if it is falsy
+    JumpIfFalse(u32),
+
+    PushScope,
+    PopScope,
+
+    /// Bind the next argument to a parameter; when the parameter has a
+    /// default, jump over the code computing it unless it is needed
+    Param {
+        name: u32,
+        rest: bool,
+        default: Option<u32>,
+    },
+
+    /// Enter the body of the function the chunk was called for
+    EnterFunction,
+
+    /// Pop a collection and start iterating over it
+    IterStart(SpanId),
+
+    /// Bind the next item and key as arguments, or jump when done
+    IterNext {
+        target: u32,
+        span: SpanId,
+    },
+
+    /// Call the function on top with the next item and key, or jump when done
+    IterCall {
+        target: u32,
+        span: SpanId,
+    },
+
+    /// Stop the innermost iteration
+    IterEnd,
+
+    /// Handle errors at `target` until the matching `PopTry`
+    PushTry(u32),
+
+    PopTry,
+
+    /// Leave everything entered below `depth`
+    Unwind(Depth),
+
+    /// Unwind to `depth` and fail, for loop control that leaves its context
+    Escape {
+        message: u32,
+        span: SpanId,
+        depth: Depth,
+    },
+
+    /// Fail with a runtime error
+    Fail {
+        message: u32,
+        span: SpanId,
+    },
+}
+
+/// Compiled code of a script, module or function body
+#[derive(Debug, Default)]
+pub(crate) struct Chunk {
+    pub code: Vec<Op>,
+
+    /// Innermost expression each instruction belongs to, where its errors
+    /// are reported; `None` for instructions of the statement level
+    pub at: Vec<Option<SpanId>>,
+
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+
+    /// Variable, property and symbol names, and error messages
+    pub names: Vec<String>,
+
+    /// Functions created by `Closure`
+    pub functions: Vec<Arc<FunctionDecl>>,
+
+    /// Pattern and flags of regular expression literals
+    pub regexes: Vec<(String, String)>,
+
+    /// Keys of object literals
+    pub keys: Vec<Vec<String>>,
+}
+
+/// Compile the top level of a script, whose value is that of the last
+/// expression statement
+pub(crate) fn compile_program(program: &Program) -> Chunk {
+    let mut compiler = Compiler::new(Context::TopLevel { stmt: program.span });
+
+    // The value of the last expression statement
+    compiler.constant(Value::Null);
+    compiler.hoist(&program.body);
+    for stmt in &program.body {
+        compiler.enter_top_level(stmt.span);
+        if let StmtKind::Expr(expr) = &stmt.kind {
+            let span = compiler.span(stmt.span);
+            compiler.emit(Op::TopStatement(span));
+            compiler.expr(expr);
+            compiler.emit(Op::Swap);
+            compiler.emit(Op::Pop);
+        } else {
+            compiler.stmt(stmt);
+        }
+    }
+    compiler.emit(Op::Return);
+
+    compiler.chunk
+}
+
+/// Compile the top level of a module, where each top-level `⟼` adds an export
+pub(crate) fn compile_module(program: &Program) -> Chunk {
+    let mut compiler = Compiler::new(Context::TopLevel { stmt: program.span });
+
+    compiler.hoist(&program.body);
+    for stmt in &program.body {
+        compiler.enter_top_level(stmt.span);
+        if let StmtKind::Export(expr) = &stmt.kind {
+            let span = compiler.span(stmt.span);
+            compiler.emit(Op::TopStatement(span));
+            compiler.expr(expr);
+            let name = match &expr.kind {
+                ExprKind::Ident(name) => name.as_str(),
+                _ => modules::DEFAULT_EXPORT,
+            };
+            let name = compiler.name(name);
+            compiler.emit(Op::Export(name));
+        } else {
+            compiler.stmt(stmt);
+        }
+    }
+    compiler.constant(Value::Null);
+    compiler.emit(Op::Return);
+
+    compiler.chunk
+}
+
+/// Compile a function: binding its parameters, then its body
+pub(crate) fn compile_function(decl: &FunctionDecl) -> Chunk {
+    let mut compiler = Compiler::new(Context::Function { span: decl.span });
+
+    compiler.params(&decl.params);
+    compiler.emit(Op::EnterFunction);
+    compiler.body(&decl.body.stmts);
+    compiler.constant(Value::Null);
+    compiler.emit(Op::Return);
+
+    compiler.chunk
+}
+
+/// Construct that `↩`, `↵` and `continue` leave
+enum Context {
+    /// Top level of a script or module, at the statement being compiled
+    TopLevel {
+        stmt: Span,
+    },
+
+    Function {
+        span: Span,
+    },
+
+    While {
+        depth: Depth,
+        start: u32,
+        breaks: Vec<usize>,
+    },
+
+    /// A `∀` loop with an inline body, where `↩` ends the iteration
+    ForEach {
+        depth: Depth,
+        next: u32,
+        breaks: Vec<usize>,
+    },
+
+    /// A block used as an expression, at the statement being compiled
+    BlockExpr {
+        depth: Depth,
+        stmt: Span,
+        exits: Vec<usize>,
+    },
+}
+
+struct Compiler {
+    chunk: Chunk,
+
+    /// Indices of the names already in the chunk
+    names: HashMap<String, u32>,
+
+    /// Nesting at the instruction being emitted
+    depth: Depth,
+
+    /// Spans of the expressions being compiled, innermost last
+    exprs: Vec<SpanId>,
+
+    contexts: Vec<Context>,
+}
+
+impl Compiler {
+    fn new(context: Context) -> Self {
+        Self {
+            chunk: Chunk::default(),
+            names: HashMap::new(),
+            depth: Depth::default(),
+            exprs: Vec::new(),
+            contexts: vec![context],
+        }
+    }
+
+    // ----- emitting -----
+
+    fn emit(&mut self, op: Op) -> usize {
+        match op {
+            Op::PushScope => self.depth.scopes += 1,
+            Op::PopScope => self.depth.scopes -= 1,
+            Op::PushTry(_) => self.depth.handlers += 1,
+            Op::PopTry => self.depth.handlers -= 1,
+            Op::Statement(_) | Op::ExprStatement(_) => self.depth.statements += 1,
+            Op::EndStatement => self.depth.statements -= 1,
+            Op::IterStart(_) => self.depth.iterators += 1,
+            Op::IterEnd => self.depth.iterators -= 1,
+            _ => {}
+        }
+
+        self.chunk.code.push(op);
+        self.chunk.at.push(self.exprs.last().copied());
+        self.chunk.code.len() - 1
+    }
+
+    /// Index of the next instruction
+    fn here(&self) -> u32 {
+        self.chunk.code.len() as u32
+    }
+
+    /// Point the jump at `index` to the next instruction
+    fn patch(&mut self, index: usize) {
+        let here = self.here();
+        match &mut self.chunk.code[index] {
+            Op::Jump(target)
+            | Op::JumpIfFalse(target)
+            | Op::JumpIfNull(target)
+            | Op::PushTry(target)
+            | Op::ShortCircuit { target, .. }
+            | Op::IndexGuard { target, .. }
+            | Op::MethodGuard { target, .. }
+            | Op::Construct { target, .. }
+            | Op::IterNext { target, .. }
+            | Op::IterCall { target, .. }
+            | Op::Param {
+                default: Some(target),
+                ..
+            } => *target = here,
+            op => unreachable!("{:?} has no jump target", op),
+        }
+    }
+
+    fn span(&mut self, span: Span) -> SpanId {
+        self.chunk.spans.push(span);
+        (self.chunk.spans.len() - 1) as SpanId
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        if let Some(&index) = self.names.get(name) {
+            return index;
+        }
+
+        let index = self.chunk.names.len() as u32;
+        self.chunk.names.push(name.to_string());
+        self.names.insert(name.to_string(), index);
+        index
+    }
+
+    fn constant(&mut self, value: Value) {
+        self.chunk.constants.push(value);
+        let index = (self.chunk.constants.len() - 1) as u32;
+        self.emit(Op::Constant(index));
+    }
+
+    fn closure(&mut self, decl: &Arc<FunctionDecl>) {
+        self.chunk.functions.push(Arc::clone(decl));
+        let index = (self.chunk.functions.len() - 1) as u32;
+        self.emit(Op::Closure(index));
+    }
+
+    fn fail(&mut self, span: Span, message: &str) {
+        let message = self.name(message);
+        let span = self.span(span);
+        self.emit(Op::Fail { message, span });
+    }
+
+    /// Record the top-level statement loop control errors are reported at
+    fn enter_top_level(&mut self, span: Span) {
+        if let Some(Context::TopLevel { stmt }) = self.contexts.first_mut() {
+            *stmt = span;
+        }
+    }
+
+    // ----- statements -----
+
+    /// Declare every `ƒ` function of a statement list before running it,
+    /// like the tree walker's `hoist`
+    fn hoist(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            if let StmtKind::Function(decl) = &stmt.kind {
+                if let Some(name) = &decl.name {
+                    self.closure(decl);
+                    let name = self.name(&name.name);
+                    self.emit(Op::Declare(name));
+                }
+            }
+        }
+    }
+
+    fn body(&mut self, stmts: &[Stmt]) {
+        self.hoist(stmts);
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.emit(Op::PushScope);
+        self.body(&block.stmts);
+        self.emit(Op::PopScope);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        let span = self.span(stmt.span);
+        self.emit(Op::Statement(span));
+        self.stmt_kind(stmt);
+        self.emit(Op::EndStatement);
+    }
+
+    fn stmt_kind(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { name, init } => {
+                match init {
+                    Some(init) => self.expr(init),
+                    None => self.constant(Value::Null),
+                }
+                let name = self.name(&name.name);
+                self.emit(Op::Declare(name));
+            }
+            // Functions are declared when their block is entered
+            StmtKind::Function(_) => {}
+            StmtKind::Expr(expr) => {
+                self.expr(expr);
+                self.emit(Op::Pop);
+            }
+            StmtKind::Print(args) => {
+                let args = self.args(args);
+                self.emit(Op::Print(args));
+            }
+            StmtKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                let skip_then = self.emit(Op::JumpIfFalse(0));
+                self.block(then_branch);
+                match else_branch {
+                    Some(else_branch) => {
+                        let skip_else = self.emit(Op::Jump(0));
+                        self.patch(skip_then);
+                        self.stmt(else_branch);
+                        self.patch(skip_else);
+                    }
+                    None => self.patch(skip_then),
+                }
+            }
+            StmtKind::While { cond, body } => {
+                let start = self.here();
+                let span = self.span(stmt.span);
+                self.emit(Op::CheckDeadline(span));
+                self.expr(cond);
+                let exit = self.emit(Op::JumpIfFalse(0));
+
+                self.contexts.push(Context::While {
+                    depth: self.depth,
+                    start,
+                    breaks: Vec::new(),
+                });
+                self.block(body);
+                self.emit(Op::Jump(start));
+
+                self.patch(exit);
+                if let Some(Context::While { breaks, .. }) = self.contexts.pop() {
+                    for index in breaks {
+                        self.patch(index);
+                    }
+                }
+            }
+            StmtKind::ForEach { iterable, body } => self.foreach(iterable, body),
+            StmtKind::Return(value) => {
+                match value {
+                    Some(value) => self.expr(value),
+                    None => self.constant(Value::Null),
+                }
+                self.return_value();
+            }
+            StmtKind::Export(value) => {
+                self.expr(value);
+                self.return_value();
+            }
+            StmtKind::Break => self.loop_control(true),
+            StmtKind::Continue => self.loop_control(false),
+            StmtKind::Try {
+                body,
+                param,
+                handler,
+            } => {
+                let try_start = self.emit(Op::PushTry(0));
+                self.block(body);
+                self.emit(Op::PopTry);
+                let skip_handler = self.emit(Op::Jump(0));
+
+                // The VM jumps here with the error value on the stack
+                self.patch(try_start);
+                self.emit(Op::PushScope);
+                match param {
+                    Some(param) => {
+                        let name = self.name(&param.name);
+                        self.emit(Op::Declare(name));
+                    }
+                    None => {
+                        self.emit(Op::Pop);
+                    }
+                }
+                self.body(&handler.stmts);
+                self.emit(Op::PopScope);
+                self.patch(skip_handler);
+            }
+            StmtKind::Block(block) => self.block(block),
+        }
+    }
+
+    fn foreach(&mut self, iterable: &Expr, body: &ForEachBody) {
+        self.expr(iterable);
+        let span = self.span(iterable.span);
+        self.emit(Op::IterStart(span));
+
+        match body {
+            ForEachBody::Inline { params, body } => {
+                let next = self.here();
+                let done = self.emit(Op::IterNext { target: 0, span });
+
+                self.contexts.push(Context::ForEach {
+                    depth: self.depth,
+                    next,
+                    breaks: Vec::new(),
+                });
+                self.emit(Op::PushScope);
+                self.params(params);
+                self.body(&body.stmts);
+                self.emit(Op::PopScope);
+                self.emit(Op::Jump(next));
+
+                self.patch(done);
+                if let Some(Context::ForEach { breaks, .. }) = self.contexts.pop() {
+                    for index in breaks {
+                        self.patch(index);
+                    }
+                }
+                self.emit(Op::IterEnd);
+            }
+            ForEachBody::Callback(callback) => {
+                self.expr(callback);
+                let next = self.here();
+                let done = self.emit(Op::IterCall { target: 0, span });
+                self.emit(Op::Jump(next));
+                self.patch(done);
+                self.emit(Op::IterEnd);
+                self.emit(Op::Pop);
+            }
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            let name = self.name(&param.name.name);
+            match &param.default {
+                Some(default) if !param.rest => {
+                    let bind = self.emit(Op::Param {
+                        name,
+                        rest: false,
+                        default: Some(0),
+                    });
+                    self.expr(default);
+                    self.emit(Op::Declare(name));
+                    self.patch(bind);
+                }
+                _ => {
+                    self.emit(Op::Param {
+                        name,
+                        rest: param.rest,
+                        default: None,
+                    });
+                }
+            }
+        }
+    }
+
+    /// `↩` with its value on the stack: it finishes the chunk, a block
+    /// expression or, in an inline `∀` body, the iteration
+    fn return_value(&mut self) {
+        let target = self
+            .contexts
+            .iter()
+            .rposition(|context| !matches!(context, Context::While { .. }));
+
+        match target.map(|index| &self.contexts[index]) {
+            Some(&Context::ForEach { depth, next, .. }) => {
+                self.emit(Op::Pop);
+                self.emit(Op::Unwind(depth));
+                self.emit(Op::Jump(next));
+            }
+            Some(&Context::BlockExpr { depth, .. }) => {
+                // The value replaces the one of the last expression statement
+                self.emit(Op::Unwind(depth));
+                self.emit(Op::Swap);
+                self.emit(Op::Pop);
+                let exit = self.emit(Op::Jump(0));
+                if let Some(Context::BlockExpr { exits, .. }) =
+                    target.map(|index| &mut self.contexts[index])
+                {
+                    exits.push(exit);
+                }
+            }
+            _ => {
+                self.emit(Op::Return);
+            }
+        }
+    }
+
+    /// `↵` (`is_break`) or `continue`
+    fn loop_control(&mut self, is_break: bool) {
+        let index = self.contexts.len() - 1;
+        let (message, span, depth) = match self.contexts[index] {
+            Context::While { depth, start, .. } => {
+                self.emit(Op::Unwind(depth));
+                self.jump_out(index, is_break, start);
+                return;
+            }
+            Context::ForEach { depth, next, .. } => {
+                self.emit(Op::Unwind(depth));
+                self.jump_out(index, is_break, next);
+                return;
+            }
+            Context::BlockExpr { depth, stmt, .. } => (
+                "Loop control is not allowed inside a block expression",
+                stmt,
+                depth,
+            ),
+            Context::Function { span } => (
+                "Loop control escaped from a function body",
+                span,
+                Depth::default(),
+            ),
+            Context::TopLevel { stmt } if is_break => {
+                ("'↵' used outside of a loop", stmt, Depth::default())
+            }
+            Context::TopLevel { stmt } => {
+                ("continue used outside of a loop", stmt, Depth::default())
+            }
+        };
+
+        let message = self.name(message);
+        let span = self.span(span);
+        self.emit(Op::Escape {
+            message,
+            span,
+            depth,
+        });
+    }
+
+    /// Jump to the start of the loop in context `index` or, for a break, past its end
+    fn jump_out(&mut self, index: usize, is_break: bool, start: u32) {
+        if !is_break {
+            self.emit(Op::Jump(start));
+            return;
+        }
+
+        let exit = self.emit(Op::Jump(0));
+        if let Context::While { breaks, .. } | Context::ForEach { breaks, .. } =
+            &mut self.contexts[index]
+        {
+            breaks.push(exit);
+        }
+    }
+
+    // ----- expressions -----
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = self.span(expr.span);
+        self.exprs.push(span);
+        self.expr_kind(expr, span);
+        self.exprs.pop();
+    }
+
+    /// Compile arguments or array elements, spreading `...` ones
+    fn args(&mut self, args: &[Expr]) -> Args {
+        if !args
+            .iter()
+            .any(|arg| matches!(arg.kind, ExprKind::Spread(_)))
+        {
+            for arg in args {
+                self.expr(arg);
+            }
+            return Args::Count(args.len() as u32);
+        }
+
+        self.emit(Op::List);
+        for arg in args {
+            if let ExprKind::Spread(inner) = &arg.kind {
+                self.expr(inner);
+                let span = self.span(arg.span);
+                self.emit(Op::Extend { span });
+            } else {
+                self.expr(arg);
+                self.emit(Op::Push);
+            }
+        }
+        Args::List
+    }
+
+    fn expr_kind(&mut self, expr: &Expr, span: SpanId) {
+        match &expr.kind {
+            ExprKind::Number(n) => self.constant(Value::Number(*n)),
+            ExprKind::Str(s) => self.constant(Value::Str(s.clone())),
+            ExprKind::Bool(b) => self.constant(Value::Bool(*b)),
+            ExprKind::Null => self.constant(Value::Null),
+            ExprKind::Template(parts) => {
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(text) => self.constant(Value::Str(text.clone())),
+                        TemplatePart::Expr(expr) => self.expr(expr),
+                    }
+                }
+                self.emit(Op::Template(parts.len() as u32));
+            }
+            ExprKind::Regex { pattern, flags } => {
+                self.chunk.regexes.push((pattern.clone(), flags.clone()));
+                let regex = (self.chunk.regexes.len() - 1) as u32;
+                self.emit(Op::Regex { regex, span });
+            }
+            ExprKind::Ident(name) => {
+                let name = self.name(name);
+                self.emit(Op::Load { name, span });
+            }
+            ExprKind::StringRef(key) => {
+                let key = self.name(key);
+                self.emit(Op::StringRef { key, span });
+            }
+            ExprKind::Array(elements) => {
+                let args = self.args(elements);
+                self.emit(Op::Array(args));
+            }
+            ExprKind::Object(props) => {
+                for prop in props {
+                    self.expr(&prop.value);
+                }
+                self.chunk
+                    .keys
+                    .push(props.iter().map(|prop| prop.key.clone()).collect());
+                let keys = (self.chunk.keys.len() - 1) as u32;
+                self.emit(Op::Object(keys));
+            }
+            ExprKind::Lambda(decl) => self.closure(decl),
+            ExprKind::Unary { op, operand } => match (&operand.kind, op) {
+                (ExprKind::Ident(name), UnaryOp::Typeof) => {
+                    let name = self.name(name);
+                    self.emit(Op::TypeofName(name));
+                }
+                _ => {
+                    self.expr(operand);
+                    self.emit(Op::Unary(*op));
+                }
+            },
+            ExprKind::Binary { op, left, right } => {
+                self.expr(left);
+                self.expr(right);
+                self.emit(Op::Binary(*op));
+            }
+            ExprKind::Logical { op, left, right } => {
+                self.expr(left);
+                let skip = self.emit(Op::ShortCircuit { op: *op, target: 0 });
+                self.expr(right);
+                self.patch(skip);
+            }
+            ExprKind::Assign { op, target, value } => {
+                self.expr(value);
+                if let Some(binary) = op.binary() {
+                    self.expr(target);
+                    self.emit(Op::Swap);
+                    self.emit(Op::Binary(binary));
+                }
+                self.assign(target);
+            }
+            ExprKind::Update { op, prefix, target } => {
+                self.expr(target);
+                self.emit(Op::Update {
+                    op: *op,
+                    prefix: *prefix,
+                });
+                self.assign(target);
+                self.emit(Op::Pop);
+            }
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(cond);
+                let skip_then = self.emit(Op::JumpIfFalse(0));
+                self.expr(then_branch);
+                let skip_else = self.emit(Op::Jump(0));
+                self.patch(skip_then);
+                self.expr(else_branch);
+                self.patch(skip_else);
+            }
+            ExprKind::Member {
+                object,
+                property,
+                optional,
+            } => {
+                self.expr(object);
+                let name = self.name(&property.name);
+                let span = self.span(property.span);
+                self.emit(Op::GetMember {
+                    name,
+                    optional: *optional,
+                    span,
+                });
+            }
+            ExprKind::Index {
+                object,
+                index,
+                optional,
+            } => {
+                self.expr(object);
+                let guard = self.emit(Op::IndexGuard {
+                    optional: *optional,
+                    target: 0,
+                    span,
+                });
+                self.expr(index);
+                self.emit(Op::GetIndex);
+                self.patch(guard);
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                optional,
+            } => self.call(callee, args, *optional, span),
+            ExprKind::SymbolCall { symbol, args } => {
+                let args = self.args(args);
+                let symbol = self.name(symbol);
+                self.emit(Op::CallSymbol { symbol, args, span });
+            }
+            ExprKind::New { callee, args } => {
+                let args = self.args(args);
+                let construct = match &callee.kind {
+                    ExprKind::Ident(name) => {
+                        let name = self.name(name);
+                        Some(self.emit(Op::Construct {
+                            name,
+                            args,
+                            target: 0,
+                            span,
+                        }))
+                    }
+                    _ => None,
+                };
+                self.expr(callee);
+                self.emit(Op::New { args, span });
+                if let Some(construct) = construct {
+                    self.patch(construct);
+                }
+            }
+            ExprKind::Spread(_) => {
+                self.fail(expr.span, "Spread is only allowed in arguments and arrays")
+            }
+            ExprKind::Block(block) => self.block_expr(block),
+        }
+    }
+
+    fn call(&mut self, callee: &Expr, args: &[Expr], optional: bool, span: SpanId) {
+        // Method calls need the receiver, so they are resolved before the callee is evaluated
+        if let ExprKind::Member {
+            object,
+            property,
+            optional: optional_member,
+        } = &callee.kind
+        {
+            self.expr(object);
+            let name = self.name(&property.name);
+            let property_span = self.span(property.span);
+            let guard = self.emit(Op::MethodGuard {
+                name,
+                optional: *optional_member || optional,
+                target: 0,
+                span: property_span,
+            });
+            let args = self.args(args);
+            self.emit(Op::CallMethod {
+                name,
+                args,
+                span: property_span,
+            });
+            self.patch(guard);
+            return;
+        }
+
+        self.expr(callee);
+        let skip = optional.then(|| self.emit(Op::JumpIfNull(0)));
+        let args = self.args(args);
+        self.emit(Op::Call { args, span });
+        if let Some(skip) = skip {
+            self.patch(skip);
+        }
+    }
+
+    /// Store the value on top of the stack in an assignment target, keeping it
+    fn assign(&mut self, target: &Expr) {
+        match &target.kind {
+            ExprKind::Ident(name) => {
+                let name = self.name(name);
+                let span = self.span(target.span);
+                self.emit(Op::Store { name, span });
+            }
+            ExprKind::Member {
+                object, property, ..
+            } => {
+                self.expr(object);
+                let name = self.name(&property.name);
+                let span = self.span(property.span);
+                self.emit(Op::SetMember { name, span });
+            }
+            ExprKind::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+                let span = self.span(target.span);
+                self.emit(Op::SetIndex { span });
+            }
+            _ => self.fail(target.span, "Invalid assignment target"),
+        }
+    }
+
+    /// A block used as an expression; its value is the value of the last
+    /// expression statement or of a `↩` inside it
+    fn block_expr(&mut self, block: &Block) {
+        self.constant(Value::Null);
+        self.contexts.push(Context::BlockExpr {
+            depth: self.depth,
+            stmt: block.span,
+            exits: Vec::new(),
+        });
+
+        self.emit(Op::PushScope);
+        self.hoist(&block.stmts);
+        for stmt in &block.stmts {
+            if let Some(Context::BlockExpr { stmt: current, .. }) = self.contexts.last_mut() {
+                *current = stmt.span;
+            }
+
+            if let StmtKind::Expr(expr) = &stmt.kind {
+                let span = self.span(stmt.span);
+                self.emit(Op::ExprStatement(span));
+                self.expr(expr);
+                self.emit(Op::EndStatement);
+            } else {
+                self.stmt(stmt);
+                self.constant(Value::Null);
+            }
+            self.emit(Op::Swap);
+            self.emit(Op::Pop);
+        }
+        self.emit(Op::PopScope);
+
+        if let Some(Context::BlockExpr { exits, .. }) = self.contexts.pop() {
+            for index in exits {
+                self.patch(index);
+            }
+        }
+    }
+}
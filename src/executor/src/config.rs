@@ -1,6 +1,5 @@
-use std::path::PathBuf;
-
 /// Configuration for the executor module
+#[derive(Clone)]
 pub struct Config {
     /// Maximum execution time in milliseconds
     pub max_execution_time_ms: u64,
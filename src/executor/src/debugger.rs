@@ -0,0 +1,326 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::ast::Span;
+use crate::report::SymbolInvocation;
+use crate::value::Value;
+
+/// A debugger shared between the caller and the interpreter running the script
+pub type SharedDebugger = Arc<Mutex<dyn Debugger>>;
+
+/// Number of symbol calls shown in a pause
+pub(crate) const RECENT_CALLS: usize = 5;
+
+/// Front end of the step debugger
+///
+/// The interpreter decides when to stop, from the breakpoints and the
+/// stepping mode, and calls `paused` before running the statement it stopped
+/// at. The debugger answers with what to do next; an `Evaluate` request is
+/// answered through `evaluated`, after which `paused` is called again for
+/// the same statement.
+pub trait Debugger: Send {
+    /// Execution stopped before a statement
+    fn paused(&mut self, pause: &Pause) -> DebugAction;
+
+    /// Result of a `DebugAction::Evaluate` request
+    fn evaluated(&mut self, expression: &str, result: Result<Value, String>);
+}
+
+/// What to do after a pause
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugAction {
+    /// Run until the next breakpoint
+    Continue,
+
+    /// Stop at the next statement, entering function calls
+    StepIn,
+
+    /// Stop at the next statement of the current function
+    StepOver,
+
+    /// Stop after the current function returns
+    StepOut,
+
+    /// Evaluate code in the scope of the paused statement
+    Evaluate(String),
+
+    /// Stop at statements on a line
+    AddBreakpoint(usize),
+
+    /// Remove the breakpoint on a line
+    RemoveBreakpoint(usize),
+
+    /// Abort the script
+    Stop,
+}
+
+/// Why execution stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// Before the first statement of the script
+    Entry,
+
+    /// At a line breakpoint
+    Breakpoint,
+
+    /// After a step
+    Step,
+}
+
+/// A function call on the interpreter's call stack
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    /// Name of the function, `<lambda>` for anonymous functions
+    pub function: String,
+
+    /// Location of the call
+    pub call_span: Span,
+}
+
+/// Execution state at a pause
+#[derive(Debug, Clone)]
+pub struct Pause {
+    pub reason: PauseReason,
+
+    /// The statement about to run
+    pub span: Span,
+
+    /// Source of the line the statement starts on
+    pub source_line: String,
+
+    /// Active function calls, outermost first
+    pub call_stack: Vec<StackFrame>,
+
+    /// Variables of the enclosing blocks up to the function boundary, by name
+    pub locals: Vec<(String, Value)>,
+
+    /// Variables declared by the script at the top level, by name
+    pub globals: Vec<(String, Value)>,
+
+    /// The most recent symbol calls, oldest first
+    pub recent_calls: Vec<SymbolInvocation>,
+
+    /// Lines with a breakpoint
+    pub breakpoints: BTreeSet<usize>,
+}
+
+/// How far execution runs before the next pause
+#[derive(Debug, Clone, Copy)]
+enum StepMode {
+    Run,
+    StepIn,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+/// Debugger state kept by the interpreter
+pub(crate) struct DebugSession {
+    pub debugger: SharedDebugger,
+    pub breakpoints: BTreeSet<usize>,
+    mode: StepMode,
+    started: bool,
+}
+
+impl DebugSession {
+    /// Start a session that pauses before the first statement
+    pub fn new(debugger: SharedDebugger) -> Self {
+        Self {
+            debugger,
+            breakpoints: BTreeSet::new(),
+            mode: StepMode::StepIn,
+            started: false,
+        }
+    }
+
+    /// Whether to pause before a statement on `line` at call `depth`
+    pub fn should_pause(&self, line: usize, depth: usize) -> Option<PauseReason> {
+        let step = match self.mode {
+            StepMode::Run => false,
+            StepMode::StepIn => true,
+            StepMode::StepOver(from) => depth <= from,
+            StepMode::StepOut(from) => depth < from,
+        };
+
+        if !self.started {
+            Some(PauseReason::Entry)
+        } else if step {
+            Some(PauseReason::Step)
+        } else if self.breakpoints.contains(&line) {
+            Some(PauseReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+
+    /// Apply a resuming action taken at call `depth`
+    pub fn resume(&mut self, action: &DebugAction, depth: usize) {
+        self.started = true;
+        self.mode = match action {
+            DebugAction::StepIn => StepMode::StepIn,
+            DebugAction::StepOver => StepMode::StepOver(depth),
+            DebugAction::StepOut => StepMode::StepOut(depth),
+            _ => StepMode::Run,
+        };
+    }
+}
+
+/// Interactive debugger reading commands from the terminal
+pub struct TerminalDebugger {
+    /// Don't repeat the location when pausing again at the same statement,
+    /// e.g. after an evaluation
+    quiet: bool,
+}
+
+impl TerminalDebugger {
+    /// Create a debugger using stdin and stdout
+    pub fn new() -> Self {
+        Self { quiet: false }
+    }
+
+    fn print_help() {
+        println!("Commands:");
+        println!("  c, continue        Run until the next breakpoint");
+        println!("  s, step            Step to the next statement, entering calls");
+        println!("  n, next            Step over calls to the next statement");
+        println!("  o, out             Run until the current function returns");
+        println!("  b, break <line>    Set a breakpoint");
+        println!("  d, delete <line>   Remove a breakpoint");
+        println!("  l, locals          Show local variables");
+        println!("  g, globals         Show global variables");
+        println!("  bt, stack          Show the call stack");
+        println!("  calls              Show the last symbol calls and their results");
+        println!("  p, print <code>    Evaluate code in the current frame");
+        println!("  q, quit            Stop the script");
+    }
+
+    fn print_location(pause: &Pause) {
+        let reason = match pause.reason {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step => "step",
+        };
+        println!("Paused at line {} ({})", pause.span.line, reason);
+        println!("{:>5} | {}", pause.span.line, pause.source_line);
+    }
+
+    fn print_variables(variables: &[(String, Value)]) {
+        if variables.is_empty() {
+            println!("  (none)");
+        }
+        for (name, value) in variables {
+            println!("  {} = {:?}", name, value);
+        }
+    }
+
+    fn print_stack(pause: &Pause) {
+        println!("  at line {} (current statement)", pause.span.line);
+        for frame in pause.call_stack.iter().rev() {
+            println!(
+                "  in {} called at line {}",
+                frame.function, frame.call_span.line
+            );
+        }
+    }
+
+    fn print_calls(pause: &Pause) {
+        if pause.recent_calls.is_empty() {
+            println!("  (no symbol calls yet)");
+        }
+        for call in &pause.recent_calls {
+            let args: Vec<String> = call.args.iter().map(|v| format!("{:?}", v)).collect();
+            let outcome = match (&call.result, &call.error) {
+                (_, Some(error)) => format!("failed: {}", error),
+                (Some(result), None) => format!("{:?}", result),
+                (None, None) => "null".to_string(),
+            };
+            println!(
+                "  line {}: {}({}) -> {}",
+                call.span.line,
+                call.symbol,
+                args.join(", "),
+                outcome
+            );
+        }
+    }
+}
+
+impl Default for TerminalDebugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debugger for TerminalDebugger {
+    fn paused(&mut self, pause: &Pause) -> DebugAction {
+        if !std::mem::take(&mut self.quiet) {
+            Self::print_location(pause);
+        }
+
+        let stdin = std::io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = std::io::stdout().flush();
+
+            let mut input = String::new();
+            match stdin.lock().read_line(&mut input) {
+                // Without a terminal there is nobody to ask, so let the script finish
+                Ok(0) | Err(_) => return DebugAction::Continue,
+                Ok(_) => {}
+            }
+
+            let input = input.trim();
+            let (command, rest) = input.split_once(' ').unwrap_or((input, ""));
+            let rest = rest.trim();
+            let line = || rest.parse::<usize>().ok();
+
+            match command {
+                "c" | "continue" => return DebugAction::Continue,
+                "s" | "step" => return DebugAction::StepIn,
+                "n" | "next" => return DebugAction::StepOver,
+                "o" | "out" => return DebugAction::StepOut,
+                "q" | "quit" => return DebugAction::Stop,
+                "b" | "break" => match line() {
+                    Some(line) => {
+                        println!("Breakpoint set at line {}", line);
+                        self.quiet = true;
+                        return DebugAction::AddBreakpoint(line);
+                    }
+                    None if rest.is_empty() => {
+                        let lines: Vec<String> =
+                            pause.breakpoints.iter().map(|l| l.to_string()).collect();
+                        println!("Breakpoints: {}", lines.join(", "));
+                    }
+                    None => println!("Expected a line number"),
+                },
+                "d" | "delete" => match line() {
+                    Some(line) => {
+                        println!("Breakpoint removed from line {}", line);
+                        self.quiet = true;
+                        return DebugAction::RemoveBreakpoint(line);
+                    }
+                    None => println!("Expected a line number"),
+                },
+                "l" | "locals" => Self::print_variables(&pause.locals),
+                "g" | "globals" => Self::print_variables(&pause.globals),
+                "bt" | "stack" => Self::print_stack(pause),
+                "calls" => Self::print_calls(pause),
+                "p" | "print" if !rest.is_empty() => {
+                    return DebugAction::Evaluate(rest.to_string())
+                }
+                "where" => Self::print_location(pause),
+                "h" | "help" | "?" => Self::print_help(),
+                "" => {}
+                other => println!("Unknown command '{}', type 'help' for a list", other),
+            }
+        }
+    }
+
+    fn evaluated(&mut self, _expression: &str, result: Result<Value, String>) {
+        match result {
+            Ok(value) => println!("{:?}", value),
+            Err(message) => println!("Error: {}", message),
+        }
+        self.quiet = true;
+    }
+}
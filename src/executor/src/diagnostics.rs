@@ -0,0 +1,438 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ast::Span;
+use crate::error::{did_you_mean, Error};
+use crate::parser::{self, ParseError};
+
+/// Stable identifiers for the kinds of diagnostics the executor reports
+pub mod codes {
+    /// Code could not be parsed
+    pub const SYNTAX_ERROR: &str = "E0001";
+
+    /// Code failed while running
+    pub const RUNTIME_ERROR: &str = "E0002";
+
+    /// Call to a symbol that is not registered
+    pub const UNKNOWN_SYMBOL: &str = "E0003";
+
+    /// Call to a symbol whose capability is not allowed
+    pub const PERMISSION_DENIED: &str = "E0004";
+
+    /// Script ran longer than `max_execution_time_ms`
+    pub const TIMEOUT: &str = "E0005";
+
+    /// Script used more than `max_memory_bytes`
+    pub const MEMORY_LIMIT: &str = "E0006";
+
+    /// Use of a variable that is not declared
+    pub const UNDEFINED_VARIABLE: &str = "E0007";
+
+    /// Symbol call with the wrong number of arguments
+    pub const WRONG_ARITY: &str = "E0008";
+
+    /// `:key` reference to a string that is not in any loaded dictionary
+    pub const UNKNOWN_STRING_KEY: &str = "E0010";
+
+    /// Replayed script made a different symbol call than the trace
+    pub const REPLAY_DIVERGENCE: &str = "E0009";
+
+    /// Statement that can never run
+    pub const UNREACHABLE_CODE: &str = "W0001";
+
+    /// `main` function that is declared but never called
+    pub const MISSING_MAIN_CALL: &str = "W0002";
+
+    /// `📖` file read of a key the script stores in memory, which should be
+    /// read with `📚`
+    pub const MEMORY_READ_AS_FILE: &str = "W0003";
+
+    /// Any other executor failure
+    pub const INTERNAL_ERROR: &str = "E0000";
+}
+
+/// Width of a tab when rendering source lines
+const TAB_WIDTH: usize = 4;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// A problem in Anarchy-Inference code, tied to the place it occurred
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Stable identifier such as `E0001`
+    pub code: String,
+
+    pub message: String,
+
+    /// File the code came from, if any
+    pub file: Option<String>,
+
+    /// Location of the offending code, `None` if it is not known
+    pub span: Option<Span>,
+
+    /// Text shown next to the carets
+    pub label: Option<String>,
+
+    /// Additional hints shown below the snippet
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic
+    pub fn new(
+        severity: Severity,
+        code: &str,
+        message: impl Into<String>,
+        span: Option<Span>,
+    ) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            file: None,
+            span,
+            label: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Create an error diagnostic
+    pub fn error(code: &str, message: impl Into<String>, span: Option<Span>) -> Self {
+        Self::new(Severity::Error, code, message, span)
+    }
+
+    /// Create a warning diagnostic
+    pub fn warning(code: &str, message: impl Into<String>, span: Option<Span>) -> Self {
+        Self::new(Severity::Warning, code, message, span)
+    }
+
+    /// Create a diagnostic for an executor error
+    ///
+    /// The location is taken from `span` and removed from the message.
+    pub fn from_error(err: &anyhow::Error, span: Option<Span>) -> Self {
+        let (code, message) = describe(err);
+        Self::error(code, message, span)
+    }
+
+    /// Set the file the code came from
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Set the text shown next to the carets
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Add a note
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Render the diagnostic the way rustc does, with the offending source
+    /// line and carets under the span
+    ///
+    /// ```text
+    /// error[E0003]: Unknown symbol '📒'
+    ///  --> task.a.i:2:5
+    ///   |
+    /// 2 |     📒("notes.txt")
+    ///   |     ^^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+
+        let snippet = self.span.and_then(|span| Snippet::new(source, span));
+        let gutter = snippet
+            .as_ref()
+            .map(|snippet| snippet.line.to_string().len())
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        match (&self.span, &snippet) {
+            (Some(span), Some(snippet)) => {
+                let file = self.file.as_deref().unwrap_or("<script>");
+                out.push_str(&format!(
+                    "{}--> {}:{}:{}\n",
+                    pad, file, span.line, span.column
+                ));
+                out.push_str(&format!("{} |\n", pad));
+                out.push_str(&format!("{} | {}\n", snippet.line, snippet.text));
+
+                let mut marker = format!(
+                    "{}{}",
+                    " ".repeat(snippet.caret_offset),
+                    "^".repeat(snippet.caret_width)
+                );
+                if let Some(label) = &self.label {
+                    marker.push(' ');
+                    marker.push_str(label);
+                }
+                out.push_str(&format!("{} | {}\n", pad, marker));
+            }
+            _ => {
+                if let Some(file) = &self.file {
+                    out.push_str(&format!("{}--> {}\n", pad, file));
+                }
+            }
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!("{} = note: {}\n", pad, note));
+        }
+
+        out
+    }
+
+    /// The diagnostic as JSON, including its rendered form
+    pub fn to_json(&self, source: &str) -> serde_json::Value {
+        let mut json = serde_json::to_value(self).unwrap_or(serde_json::Value::Null);
+        if let serde_json::Value::Object(map) = &mut json {
+            map.insert(
+                "rendered".to_string(),
+                serde_json::Value::String(self.render(source)),
+            );
+        }
+        json
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(err: ParseError) -> Self {
+        Diagnostic::error(codes::SYNTAX_ERROR, err.message, Some(err.span))
+    }
+}
+
+/// Render several diagnostics, separated by blank lines
+pub fn render_all(diagnostics: &[Diagnostic], source: &str) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(source))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Diagnostics for syntax errors in `code`, empty if it parses
+pub fn check_syntax(code: &str) -> Vec<Diagnostic> {
+    if code.trim().is_empty() {
+        return vec![Diagnostic::error(codes::SYNTAX_ERROR, "Empty code", None)];
+    }
+
+    match parser::parse_spanned(code) {
+        Ok(_) => Vec::new(),
+        Err(err) => vec![err.into()],
+    }
+}
+
+/// The code and the location-free message of an executor error
+pub(crate) fn describe(err: &anyhow::Error) -> (&'static str, String) {
+    match err.downcast_ref::<Error>() {
+        Some(Error::CodeParsingError(message)) => (codes::SYNTAX_ERROR, strip_location(message)),
+        Some(Error::CodeExecutionError(message)) => (codes::RUNTIME_ERROR, strip_location(message)),
+        Some(Error::UnknownSymbol(message)) => (
+            codes::UNKNOWN_SYMBOL,
+            format!("Unknown symbol {}", strip_location(message)),
+        ),
+        Some(Error::SymbolFailed {
+            symbol, message, ..
+        }) => (
+            codes::RUNTIME_ERROR,
+            format!("Symbol '{}' failed: {}", symbol, message),
+        ),
+        Some(Error::UnknownStringKey {
+            key, suggestions, ..
+        }) => (
+            codes::UNKNOWN_STRING_KEY,
+            format!("Unknown string key ':{}'{}", key, did_you_mean(suggestions)),
+        ),
+        Some(Error::PermissionDenied {
+            symbol, capability, ..
+        }) => (
+            codes::PERMISSION_DENIED,
+            format!(
+                "'{}' requires the '{}' capability, which is not allowed",
+                symbol, capability
+            ),
+        ),
+        Some(Error::ExecutionTimeout { limit_ms, .. }) => (
+            codes::TIMEOUT,
+            format!("Execution timed out after {} ms", limit_ms),
+        ),
+        Some(Error::MemoryLimitExceeded {
+            limit, statement, ..
+        }) => (
+            codes::MEMORY_LIMIT,
+            format!("Memory limit of {} bytes exceeded: {}", limit, statement),
+        ),
+        Some(Error::ReplayDivergence {
+            index,
+            expected,
+            actual,
+            ..
+        }) => (
+            codes::REPLAY_DIVERGENCE,
+            format!(
+                "Replay diverged after {} recorded calls: expected {}, got {}",
+                index, expected, actual
+            ),
+        ),
+        Some(Error::DebuggerStopped { .. }) => (
+            codes::RUNTIME_ERROR,
+            "Execution stopped by the debugger".to_string(),
+        ),
+        _ => (codes::INTERNAL_ERROR, err.to_string()),
+    }
+}
+
+/// The (at most three) `candidates` closest to `name`, if they are close
+/// enough to be a misspelling of it
+pub(crate) fn suggestions<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let limit = (name.chars().count() / 3).max(1);
+    let mut close: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    close.sort();
+
+    let best = close.first().map_or(0, |(distance, _)| *distance);
+    close
+        .into_iter()
+        .take_while(|(distance, _)| *distance == best)
+        .take(3)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Remove the `at line L, column C` suffix runtime and parse errors end with
+fn strip_location(message: &str) -> String {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let location = LOCATION
+        .get_or_init(|| Regex::new(r" at line \d+, column \d+$").expect("valid location pattern"));
+    location.replace(message, "").into_owned()
+}
+
+/// The source line a span starts on, with the caret position measured in
+/// terminal columns
+struct Snippet {
+    line: usize,
+    text: String,
+    caret_offset: usize,
+    caret_width: usize,
+}
+
+impl Snippet {
+    fn new(source: &str, span: Span) -> Option<Self> {
+        if span.start > source.len() || !source.is_char_boundary(span.start) {
+            return None;
+        }
+
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+        let marked_end = span.end.clamp(span.start, line_end);
+        if !source.is_char_boundary(marked_end) {
+            return None;
+        }
+
+        let line_text = &source[line_start..line_end];
+        let prefix = &source[line_start..span.start];
+        let marked = &source[span.start..marked_end];
+
+        Some(Self {
+            line: span.line,
+            text: expand_tabs(line_text.trim_end_matches('\r')),
+            caret_offset: display_width(prefix),
+            caret_width: display_width(marked).max(1),
+        })
+    }
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// Columns `text` occupies in a terminal; emoji are two columns wide
+fn display_width(text: &str) -> usize {
+    expand_tabs(text).width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The marker line of `source` rendered with a caret under `marked`
+    fn marker(source: &str, marked: &str) -> String {
+        let start = source.find(marked).unwrap();
+        let span = Span::new(start, start + marked.len(), 1, 1);
+        let rendered =
+            Diagnostic::error(codes::UNKNOWN_SYMBOL, "Unknown symbol", Some(span)).render(source);
+        rendered.lines().nth(4).unwrap().to_string()
+    }
+
+    #[test]
+    fn carets_count_emoji_as_two_columns() {
+        let source = r#"ι x = 📖("a"); 📒("b");"#;
+
+        // `ι x = ` takes 6 columns, `📖` 2 and `("a"); ` 7
+        assert_eq!(marker(source, "📒"), format!("  | {}^^", " ".repeat(15)));
+        assert_eq!(marker(source, "x"), "  |   ^");
+    }
+
+    #[test]
+    fn carets_expand_tabs() {
+        let source = "\t\tι y = 📒(1);";
+
+        assert_eq!(
+            marker(source, "📒"),
+            format!("  | {}^^", " ".repeat(2 * TAB_WIDTH + 6))
+        );
+        assert_eq!(marker(source, "ι"), format!("  | {}^", " ".repeat(8)));
+    }
+}
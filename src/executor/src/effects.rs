@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast::Span;
+use crate::builtins;
+use crate::registry::{Capability, SymbolSignature, ValueType};
+use crate::value::Value;
+
+/// A side effect a script would have had, recorded during a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct Effect {
+    /// The symbol that was called, e.g. `✍`
+    pub symbol: String,
+
+    /// Capability the symbol requires
+    pub capability: Capability,
+
+    /// Arguments the symbol was called with
+    pub args: Vec<Value>,
+
+    /// What the call would have done, e.g. "Run shell command `ls -la`"
+    pub description: String,
+
+    /// Location of the call
+    pub span: Span,
+}
+
+impl Effect {
+    /// Record a call to a symbol with side effects
+    pub(crate) fn new(signature: &SymbolSignature, args: Vec<Value>, span: Span) -> Self {
+        Self {
+            symbol: signature.name.clone(),
+            capability: signature.capability,
+            description: describe(&signature.name, &args),
+            args,
+            span,
+        }
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: [{}] {}",
+            self.span.line, self.capability, self.description
+        )
+    }
+}
+
+/// What a call would do, in words
+fn describe(symbol: &str, args: &[Value]) -> String {
+    let arg = |i: usize| args.get(i).map(|v| v.to_string()).unwrap_or_default();
+
+    match symbol {
+        "✍" => format!("Write {} bytes to file {}", arg(1).len(), arg(0)),
+        "✂" => format!("Delete {}", arg(0)),
+        "⧉" => format!("Copy {} to {}", arg(0), arg(1)),
+        "↷" => format!("Move {} to {}", arg(0), arg(1)),
+        "!" => format!("Run shell command `{}`", arg(0)),
+        "↗" => format!("Send an HTTP GET request to {}", arg(0)),
+        "↓" => format!("Send an HTTP POST request to {}", arg(0)),
+        "~" => {
+            let option = |name: &str| match args.first() {
+                Some(options) => builtins::get_property(options, name),
+                None => Value::Null,
+            };
+            let method = match option("method") {
+                Value::Null => "GET".to_string(),
+                method => method.to_string().to_uppercase(),
+            };
+            format!("Send an HTTP {} request to {}", method, option("url"))
+        }
+        "🌐" => format!("Open {} in the browser", arg(0)),
+        "🖱" => format!("Click {}", arg(1)),
+        "⌨" => format!("Type \"{}\" into {}", arg(2), arg(1)),
+        "🧠" => "Run JavaScript in the page".to_string(),
+        "❌" => "Close the browser".to_string(),
+        "📝" => format!("Store memory entry {}", arg(0)),
+        "🗑" => format!("Delete memory entry {}", arg(0)),
+        "💾" => format!("Save string dictionary {} to {}", arg(0), arg(1)),
+        _ => format!(
+            "Call {}({})",
+            symbol,
+            args.iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Whether a dry run replaces calls to a symbol by a stub
+///
+/// Symbols with side effects are always stubbed, and so are the other
+/// browser symbols: opening a page is a side effect, so during a dry run
+/// there is never a page for them to read from.
+pub(crate) fn is_stubbed(signature: &SymbolSignature, stub_reads: bool) -> bool {
+    signature.side_effects || stub_reads || signature.capability == Capability::Browser
+}
+
+/// Value returned in place of a symbol's result during a dry run
+///
+/// Shell commands and HTTP requests return objects shaped like the real
+/// results, so scripts can go on to read `.o` or `.b`; other symbols return
+/// an empty value of their declared return type.
+pub(crate) fn stub(signature: &SymbolSignature) -> Value {
+    let fields = |fields: &[(&str, Value)]| {
+        Value::object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    };
+
+    match signature.name.as_str() {
+        "!" => fields(&[
+            ("o", Value::string("")),
+            ("e", Value::string("")),
+            ("c", Value::Number(0.0)),
+        ]),
+        "↗" | "↓" | "~" => fields(&[
+            ("s", Value::Number(200.0)),
+            ("h", Value::object(BTreeMap::new())),
+            ("b", Value::string("")),
+        ]),
+        _ => match signature.return_type {
+            ValueType::String => Value::string(""),
+            ValueType::Number => Value::Number(0.0),
+            ValueType::Bool => Value::Bool(true),
+            ValueType::Array => Value::array(Vec::new()),
+            ValueType::Object => Value::object(BTreeMap::new()),
+            ValueType::Bytes => Value::bytes(Vec::new()),
+            ValueType::Any | ValueType::Null => Value::Null,
+        },
+    }
+}
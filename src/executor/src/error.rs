@@ -1,5 +1,8 @@
 use thiserror::Error;
 
+use crate::registry::Capability;
+use crate::value::Value;
+
 /// Errors that can occur in the executor module
 #[derive(Error, Debug)]
 pub enum Error {
@@ -15,6 +18,73 @@ pub enum Error {
     #[error("Symbol registration error: {0}")]
     SymbolRegistrationError(String),
     
+    /// Symbol argument of the wrong type
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    
+    /// HTTP request that got no usable response
+    #[error("Network error: {0}")]
+    NetworkError(String),
+    
+    /// HTTP request that took longer than its timeout
+    #[error("Timeout error: {0}")]
+    TimeoutError(String),
+    
+    /// Call to a symbol that is not registered
+    #[error("Unknown symbol: {0}")]
+    UnknownSymbol(String),
+    
+    /// `:key` reference to a string no loaded dictionary contains
+    #[error("Unknown string key ':{key}' at line {line}, column {column}{}", did_you_mean(.suggestions))]
+    UnknownStringKey {
+        key: String,
+        suggestions: Vec<String>,
+        line: usize,
+        column: usize,
+    },
+    
+    /// A symbol's handler returned an error
+    #[error("Symbol '{symbol}' failed: {message} at line {line}, column {column}")]
+    SymbolFailed {
+        symbol: String,
+        args: Vec<Value>,
+        /// What went wrong, see [`kind`]
+        kind: String,
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    
+    /// Call to a symbol whose capability is not allowed
+    #[error("Permission denied at line {line}, column {column}: '{symbol}' requires the '{capability}' capability, which is not allowed")]
+    PermissionDenied {
+        symbol: String,
+        capability: Capability,
+        line: usize,
+        column: usize,
+    },
+    
+    /// Script exceeded `max_execution_time_ms`
+    #[error("Execution timed out after {limit_ms} ms at line {line}")]
+    ExecutionTimeout { limit_ms: u64, line: usize },
+    
+    /// Script values outgrew `max_memory_bytes`
+    #[error("Memory limit of {limit} bytes exceeded at line {line}: {statement}")]
+    MemoryLimitExceeded { limit: usize, line: usize, statement: String },
+    
+    /// Script aborted from the debugger
+    #[error("Execution stopped by the debugger at line {line}")]
+    DebuggerStopped { line: usize },
+    
+    /// Script made a different symbol call than the trace it is replayed from
+    #[error("Replay diverged after {index} recorded calls at line {line}: expected {expected}, got {actual}")]
+    ReplayDivergence {
+        index: usize,
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+    
     /// Error during code parsing
     #[error("Code parsing error: {0}")]
     CodeParsingError(String),
@@ -35,3 +105,98 @@ pub enum Error {
     #[error("File wait error: {0}")]
     FileWaitError(String),
 }
+
+/// `, did you mean ':a' or ':b'?` for the keys in `suggestions`
+pub(crate) fn did_you_mean(suggestions: &[String]) -> String {
+    let keys: Vec<String> = suggestions.iter().map(|key| format!("':{}'", key)).collect();
+    match keys.split_last() {
+        None => String::new(),
+        Some((last, [])) => format!(", did you mean {}?", last),
+        Some((last, rest)) => format!(", did you mean {} or {}?", rest.join(", "), last),
+    }
+}
+
+/// What kind of failure an error is, e.g. `PathNotAllowed` or `Timeout`
+///
+/// This is the name of the error's variant in the `Error` enum of the crate
+/// it comes from, without the `Error` suffix, so that scripts can tell
+/// failures apart in `÷` handlers. Errors of other types are `Unknown`.
+pub(crate) fn kind(err: &anyhow::Error) -> String {
+    let kind = if let Some(err) = err.downcast_ref::<Error>() {
+        executor_kind(err)
+    } else if let Some(err) = err.downcast_ref::<system::Error>() {
+        system_kind(err)
+    } else if let Some(err) = err.downcast_ref::<memory::Error>() {
+        memory_kind(err)
+    } else if let Some(err) = err.downcast_ref::<browser::Error>() {
+        browser_kind(err)
+    } else if err.downcast_ref::<std::io::Error>().is_some() {
+        "Io"
+    } else {
+        "Unknown"
+    };
+    kind.to_string()
+}
+
+fn executor_kind(err: &Error) -> &str {
+    match err {
+        Error::SandboxInitializationError(_) => "SandboxInitialization",
+        Error::CodeExecutionError(_) => "CodeExecution",
+        Error::SymbolRegistrationError(_) => "SymbolRegistration",
+        Error::InvalidArgument(_) => "InvalidArgument",
+        Error::NetworkError(_) => "Network",
+        Error::TimeoutError(_) => "Timeout",
+        Error::UnknownSymbol(_) => "UnknownSymbol",
+        Error::UnknownStringKey { .. } => "UnknownStringKey",
+        Error::SymbolFailed { kind, .. } => kind,
+        Error::PermissionDenied { .. } => "PermissionDenied",
+        Error::ExecutionTimeout { .. } => "ExecutionTimeout",
+        Error::MemoryLimitExceeded { .. } => "MemoryLimitExceeded",
+        Error::DebuggerStopped { .. } => "DebuggerStopped",
+        Error::ReplayDivergence { .. } => "ReplayDivergence",
+        Error::CodeParsingError(_) => "CodeParsing",
+        Error::SandboxShutdownError(_) => "SandboxShutdown",
+        Error::InputError(_) => "Input",
+        Error::OutputError(_) => "Output",
+        Error::FileWaitError(_) => "FileWait",
+    }
+}
+
+fn system_kind(err: &system::Error) -> &'static str {
+    match err {
+        system::Error::SandboxInitializationError(_) => "SandboxInitialization",
+        system::Error::FileSystemError(_) => "FileSystem",
+        system::Error::ShellExecutionError(_) => "ShellExecution",
+        system::Error::PermissionDenied(_) => "PermissionDenied",
+        system::Error::PathNotAllowed(_) => "PathNotAllowed",
+        system::Error::CommandNotAllowed(_) => "CommandNotAllowed",
+        system::Error::TimeoutError(_) => "Timeout",
+        system::Error::Unknown(_) => "Unknown",
+    }
+}
+
+fn memory_kind(err: &memory::Error) -> &'static str {
+    match err {
+        memory::Error::DatabaseInitializationError(_) => "DatabaseInitialization",
+        memory::Error::DatabaseConnectionError(_) => "DatabaseConnection",
+        memory::Error::QueryExecutionError(_) => "QueryExecution",
+        memory::Error::KeyNotFoundError(_) => "KeyNotFound",
+        memory::Error::VectorStoreError(_) => "VectorStore",
+        memory::Error::SemanticSearchError(_) => "SemanticSearch",
+        memory::Error::FileOperationError(_) => "FileOperation",
+        memory::Error::SerializationError(_) => "Serialization",
+        memory::Error::UnsupportedOperationError(_) => "UnsupportedOperation",
+    }
+}
+
+fn browser_kind(err: &browser::Error) -> &'static str {
+    match err {
+        browser::Error::BrowserInitializationError(_) => "BrowserInitialization",
+        browser::Error::NoPageOpen(_) => "NoPageOpen",
+        browser::Error::NavigationError(_) => "Navigation",
+        browser::Error::ElementInteractionError(_) => "ElementInteraction",
+        browser::Error::JavaScriptError(_) => "JavaScript",
+        browser::Error::TimeoutError(_) => "Timeout",
+        browser::Error::Unknown(_) => "Unknown",
+    }
+}
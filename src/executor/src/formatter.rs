@@ -0,0 +1,660 @@
+use anyhow::Result;
+
+use crate::ast::*;
+use crate::error::Error;
+use crate::lexer::{is_ident_continue, is_ident_start};
+use crate::parser;
+
+/// Indentation added for each nested block
+const INDENT: &str = "    ";
+
+/// Longest object or array literal kept on a single line
+const MAX_INLINE_WIDTH: usize = 60;
+
+/// Words the lexer turns into keywords, which must be quoted as object keys
+const KEYWORDS: [&str; 12] = [
+    "if",
+    "else",
+    "while",
+    "break",
+    "continue",
+    "return",
+    "true",
+    "false",
+    "null",
+    "undefined",
+    "new",
+    "typeof",
+];
+
+/// Format a parsed program in the canonical style
+///
+/// Statements use the glyph keywords (`ι`, `↪`, `↩`, ...), blocks are
+/// indented by four spaces and runs of blank lines collapse into one.
+/// Comments are kept: a comment inside an expression is moved in front of
+/// the statement containing it. `source` must be the code the program was
+/// parsed from. Formatting is idempotent.
+pub fn format_program(program: &Program, source: &str) -> String {
+    let mut formatter = Formatter::new(source, &program.comments);
+    formatter.block_body(&program.body, 0, source.len());
+    formatter.out
+}
+
+/// Parse and format code
+///
+/// Fails with `Error::CodeParsingError` if the code does not parse; use
+/// `check_syntax` for diagnostics pointing at the problem.
+pub fn format_code(code: &str) -> Result<String> {
+    let parsed = parser::parse_spanned(code).map_err(Error::from)?;
+    Ok(format_program(&parsed.program, code))
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: &'a [Comment],
+
+    /// Which comments have been written
+    emitted: Vec<bool>,
+
+    /// Nesting depth of the block being written
+    depth: usize,
+
+    /// Lines written so far
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, comments: &'a [Comment]) -> Self {
+        Self {
+            source,
+            comments,
+            emitted: vec![false; comments.len()],
+            depth: 0,
+            out: String::new(),
+        }
+    }
+
+    fn indent(&self) -> String {
+        INDENT.repeat(self.depth)
+    }
+
+    fn line(&mut self, text: &str) {
+        let indent = self.indent();
+        self.out.push_str(&indent);
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Whether the source has a blank line between two byte offsets
+    fn blank_between(&self, from: usize, to: usize) -> bool {
+        self.source
+            .get(from..to)
+            .is_some_and(|text| text.matches('\n').count() >= 2)
+    }
+
+    /// Mark the unwritten comments starting in `from..to` as written and return them
+    fn take_comments(&mut self, from: usize, to: usize) -> Vec<&'a Comment> {
+        let comments = self.comments;
+        let mut taken = Vec::new();
+
+        for (i, comment) in comments.iter().enumerate() {
+            if !self.emitted[i] && comment.span.start >= from && comment.span.start < to {
+                self.emitted[i] = true;
+                taken.push(comment);
+            }
+        }
+
+        taken
+    }
+
+    /// Whether any unwritten comment starts in `from..to`
+    fn has_comments(&self, from: usize, to: usize) -> bool {
+        self.comments
+            .iter()
+            .zip(&self.emitted)
+            .any(|(comment, emitted)| {
+                !emitted && comment.span.start >= from && comment.span.start < to
+            })
+    }
+
+    /// Write the statements of a block spanning `start..end` with their comments
+    fn block_body(&mut self, stmts: &[Stmt], start: usize, end: usize) {
+        let mut last_end = start;
+        let mut first = true;
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            for comment in self.take_comments(start, stmt.span.start) {
+                if !first && self.blank_between(last_end, comment.span.start) {
+                    self.out.push('\n');
+                }
+                self.line(&comment.text);
+                last_end = comment.span.end;
+                first = false;
+            }
+
+            let text = self.stmt(stmt);
+
+            if !first && self.blank_between(last_end, stmt.span.start) {
+                self.out.push('\n');
+            }
+            for comment in self.take_comments(stmt.span.start, stmt.span.end) {
+                self.line(&comment.text);
+            }
+            self.line(&text);
+            last_end = stmt.span.end;
+            first = false;
+
+            // A comment after the statement on the same line stays there
+            let next = stmts.get(i + 1).map_or(end, |next| next.span.start);
+            let trailing = self
+                .comments
+                .iter()
+                .enumerate()
+                .find(|(i, comment)| !self.emitted[*i] && comment.span.start >= stmt.span.end)
+                .filter(|(_, comment)| comment.trailing && comment.span.start < next);
+
+            if let Some((index, comment)) = trailing {
+                self.emitted[index] = true;
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+                self.out.push('\n');
+                last_end = comment.span.end;
+            }
+        }
+
+        for comment in self.take_comments(start, end) {
+            if !first && self.blank_between(last_end, comment.span.start) {
+                self.out.push('\n');
+            }
+            self.line(&comment.text);
+            last_end = comment.span.end;
+            first = false;
+        }
+    }
+
+    /// Format a block as `{ ... }`, the closing brace at the current indentation
+    fn block(&mut self, block: &Block) -> String {
+        let (start, end) = (block.span.start, block.span.end);
+        if block.stmts.is_empty() && !self.has_comments(start, end) {
+            return "{}".to_string();
+        }
+
+        let outer = std::mem::take(&mut self.out);
+        self.depth += 1;
+        self.block_body(&block.stmts, start, end);
+        self.depth -= 1;
+        let body = std::mem::replace(&mut self.out, outer);
+
+        format!("{{\n{}{}}}", body, self.indent())
+    }
+
+    // ----- statements -----
+
+    /// Format a statement without leading indentation or trailing newline
+    fn stmt(&mut self, stmt: &Stmt) -> String {
+        match &stmt.kind {
+            StmtKind::Let { name, init } => match init {
+                Some(init) => format!("ι {} = {};", name.name, self.expr(init)),
+                None => format!("ι {};", name.name),
+            },
+            StmtKind::Function(decl) => self.function(decl),
+            StmtKind::Expr(expr) => {
+                let text = self.expr(expr);
+                let is_shell =
+                    matches!(&expr.kind, ExprKind::SymbolCall { symbol, .. } if symbol == "!");
+
+                // Keep the statement from reading as a block, a declaration or a shell call
+                if text.starts_with('{')
+                    || text.starts_with('ƒ')
+                    || (text.starts_with("!(") && !is_shell)
+                {
+                    format!("({});", text)
+                } else {
+                    format!("{};", text)
+                }
+            }
+            StmtKind::Print(args) => format!("⌽({});", self.list(args)),
+            StmtKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let mut text = format!("↪({}) {}", self.expr(cond), self.block(then_branch));
+                if let Some(else_branch) = else_branch {
+                    let rest = match &else_branch.kind {
+                        StmtKind::Block(block) => self.block(block),
+                        _ => self.stmt(else_branch),
+                    };
+                    text.push_str(" ↛ ");
+                    text.push_str(&rest);
+                }
+                text
+            }
+            StmtKind::While { cond, body } => {
+                format!("↻({}) {}", self.expr(cond), self.block(body))
+            }
+            StmtKind::ForEach { iterable, body } => {
+                let iterable = self.expr(iterable);
+                let body = match body {
+                    ForEachBody::Inline { params, body } => {
+                        format!("λ({}) {}", self.params(params), self.block(body))
+                    }
+                    ForEachBody::Callback(callback) => self.expr(callback),
+                };
+                format!("∀({}, {});", iterable, body)
+            }
+            StmtKind::Return(Some(value)) => format!("↩ {};", self.expr(value)),
+            StmtKind::Return(None) => "↩;".to_string(),
+            StmtKind::Export(value) => format!("⟼({});", self.expr(value)),
+            StmtKind::Break => "↵;".to_string(),
+            StmtKind::Continue => "↻;".to_string(),
+            StmtKind::Try {
+                body,
+                param,
+                handler,
+            } => {
+                let body = self.block(body);
+                let param = param
+                    .as_ref()
+                    .map(|param| format!("({})", param.name))
+                    .unwrap_or_default();
+                format!("↺ {} ⚠{} {}", body, param, self.block(handler))
+            }
+            StmtKind::Block(block) => self.block(block),
+        }
+    }
+
+    fn function(&mut self, decl: &FunctionDecl) -> String {
+        let params = self.params(&decl.params);
+        let body = self.block(&decl.body);
+
+        match &decl.name {
+            Some(name) => format!("ƒ{}({}) {}", name.name, params, body),
+            None => format!("λ({}) {}", params, body),
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) -> String {
+        params
+            .iter()
+            .map(|param| {
+                let mut text = String::new();
+                if param.rest {
+                    text.push_str("...");
+                }
+                if let Some(sigil) = param.sigil {
+                    text.push(sigil);
+                }
+                text.push_str(&param.name.name);
+                if let Some(default) = &param.default {
+                    text.push_str(" = ");
+                    text.push_str(&self.operand(default, Precedence::Conditional));
+                }
+                text
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // ----- expressions -----
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        self.operand(expr, Precedence::Assignment)
+    }
+
+    /// Format an expression, parenthesized if it binds looser than `min`
+    fn operand(&mut self, expr: &Expr, min: Precedence) -> String {
+        let text = self.expr_kind(expr);
+        if precedence(expr) < min {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr]) -> String {
+        exprs
+            .iter()
+            .map(|expr| self.expr(expr))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn expr_kind(&mut self, expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Number(n) => format_number(*n),
+            ExprKind::Str(s) => quote(s),
+            ExprKind::Bool(b) => b.to_string(),
+            ExprKind::Null => "null".to_string(),
+            ExprKind::Template(parts) => {
+                let mut text = String::from("`");
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(s) => text.push_str(&escape_template(s)),
+                        TemplatePart::Expr(expr) => {
+                            text.push_str("${");
+                            text.push_str(&self.expr(expr));
+                            text.push('}');
+                        }
+                    }
+                }
+                text.push('`');
+                text
+            }
+            ExprKind::Regex { pattern, flags } => format!("/{}/{}", pattern, flags),
+            ExprKind::Ident(name) => name.clone(),
+            ExprKind::StringRef(key) => format!(":{}", key),
+            ExprKind::Array(elements) => {
+                let elements: Vec<String> =
+                    self.nested(|f| elements.iter().map(|e| f.expr(e)).collect());
+                self.collection('[', ']', elements)
+            }
+            ExprKind::Object(properties) => {
+                let properties: Vec<String> = self.nested(|f| {
+                    properties
+                        .iter()
+                        .map(|property| {
+                            let key = object_key(&property.key);
+                            if property.shorthand {
+                                key
+                            } else {
+                                format!("{}: {}", key, f.expr(&property.value))
+                            }
+                        })
+                        .collect()
+                });
+                self.collection('{', '}', properties)
+            }
+            ExprKind::Lambda(decl) => self.function(decl),
+            ExprKind::Unary { op, operand } => {
+                let mut inner = self.operand(operand, Precedence::Prefix);
+
+                // `!("a" + b)` would read as a shell call
+                if *op == UnaryOp::Not
+                    && inner.starts_with('(')
+                    && matches!(inner[1..].chars().next(), Some('"' | '`'))
+                {
+                    inner = format!("({})", inner);
+                }
+
+                // `- -x` must not become `--x`
+                let sign = op.as_str();
+                if (sign == "-" || sign == "+") && inner.starts_with(sign) {
+                    format!("{} {}", sign, inner)
+                } else {
+                    format!("{}{}", sign, inner)
+                }
+            }
+            ExprKind::Binary { op, left, right } => {
+                let level = binary_precedence(*op);
+                format!(
+                    "{} {} {}",
+                    self.operand(left, level),
+                    op.as_str(),
+                    self.operand(right, level.next())
+                )
+            }
+            ExprKind::Logical { op, left, right } => {
+                let level = logical_precedence(*op);
+                format!(
+                    "{} {} {}",
+                    self.operand(left, level),
+                    op.as_str(),
+                    self.operand(right, level.next())
+                )
+            }
+            ExprKind::Assign { op, target, value } => format!(
+                "{} {} {}",
+                self.operand(target, Precedence::Postfix),
+                op.as_str(),
+                self.expr(value)
+            ),
+            ExprKind::Update { op, prefix, target } => {
+                let sign = match op {
+                    UpdateOp::Increment => "++",
+                    UpdateOp::Decrement => "--",
+                };
+                let target = self.operand(target, Precedence::Postfix);
+                if *prefix {
+                    format!("{}{}", sign, target)
+                } else {
+                    format!("{}{}", target, sign)
+                }
+            }
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => format!(
+                "{} ? {} : {}",
+                self.operand(cond, Precedence::Or),
+                self.expr(then_branch),
+                self.expr(else_branch)
+            ),
+            ExprKind::Member {
+                object,
+                property,
+                optional,
+            } => format!(
+                "{}{}{}",
+                self.operand(object, Precedence::Postfix),
+                if *optional { "?." } else { "." },
+                property.name
+            ),
+            ExprKind::Index {
+                object,
+                index,
+                optional,
+            } => format!(
+                "{}{}[{}]",
+                self.operand(object, Precedence::Postfix),
+                if *optional { "?." } else { "" },
+                self.expr(index)
+            ),
+            ExprKind::Call {
+                callee,
+                args,
+                optional,
+            } => format!(
+                "{}{}({})",
+                self.operand(callee, Precedence::Postfix),
+                if *optional { "?." } else { "" },
+                self.list(args)
+            ),
+            ExprKind::SymbolCall { symbol, args } => format!("{}({})", symbol, self.list(args)),
+            ExprKind::New { callee, args } => {
+                let callee_text = self.expr(callee);
+                let callee_text = if is_member_chain(callee) {
+                    callee_text
+                } else {
+                    format!("({})", callee_text)
+                };
+                format!("new {}({})", callee_text, self.list(args))
+            }
+            ExprKind::Spread(inner) => format!("...{}", self.expr(inner)),
+            ExprKind::Block(block) => self.block(block),
+        }
+    }
+
+    /// Format items one level deeper, as the lines of a multi-line literal
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> Vec<String>) -> Vec<String> {
+        self.depth += 1;
+        let items = f(self);
+        self.depth -= 1;
+        items
+    }
+
+    /// Lay out array elements or object properties, on one line if they fit
+    fn collection(&self, open: char, close: char, items: Vec<String>) -> String {
+        if items.is_empty() {
+            return format!("{}{}", open, close);
+        }
+
+        let joined = items.join(", ");
+        let inline = if open == '{' {
+            format!("{{ {} }}", joined)
+        } else {
+            format!("[{}]", joined)
+        };
+
+        if !inline.contains('\n') && inline.chars().count() <= MAX_INLINE_WIDTH {
+            return inline;
+        }
+
+        let inner = INDENT.repeat(self.depth + 1);
+        let lines: Vec<String> = items
+            .iter()
+            .map(|item| format!("{}{}", inner, item))
+            .collect();
+        format!(
+            "{}\n{}\n{}{}",
+            open,
+            lines.join(",\n"),
+            self.indent(),
+            close
+        )
+    }
+}
+
+/// Binding strength of expressions, loosest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Assignment,
+    Conditional,
+    Or,
+    Nullish,
+    And,
+    Equality,
+    Comparison,
+    Additive,
+    Multiplicative,
+    Prefix,
+    Postfix,
+    Primary,
+}
+
+impl Precedence {
+    /// The next tighter level, required of right operands
+    fn next(self) -> Self {
+        match self {
+            Precedence::Assignment => Precedence::Conditional,
+            Precedence::Conditional => Precedence::Or,
+            Precedence::Or => Precedence::Nullish,
+            Precedence::Nullish => Precedence::And,
+            Precedence::And => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Additive,
+            Precedence::Additive => Precedence::Multiplicative,
+            Precedence::Multiplicative => Precedence::Prefix,
+            Precedence::Prefix => Precedence::Postfix,
+            Precedence::Postfix | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
+
+fn precedence(expr: &Expr) -> Precedence {
+    match &expr.kind {
+        ExprKind::Assign { .. } | ExprKind::Spread(_) => Precedence::Assignment,
+        ExprKind::Conditional { .. } => Precedence::Conditional,
+        ExprKind::Logical { op, .. } => logical_precedence(*op),
+        ExprKind::Binary { op, .. } => binary_precedence(*op),
+        ExprKind::Unary { .. } | ExprKind::Update { prefix: true, .. } => Precedence::Prefix,
+        ExprKind::Update { prefix: false, .. }
+        | ExprKind::Member { .. }
+        | ExprKind::Index { .. }
+        | ExprKind::Call { .. } => Precedence::Postfix,
+        _ => Precedence::Primary,
+    }
+}
+
+fn binary_precedence(op: BinaryOp) -> Precedence {
+    match op {
+        BinaryOp::Eq | BinaryOp::NotEq | BinaryOp::StrictEq | BinaryOp::StrictNotEq => {
+            Precedence::Equality
+        }
+        BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => Precedence::Comparison,
+        BinaryOp::Add | BinaryOp::Sub => Precedence::Additive,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => Precedence::Multiplicative,
+    }
+}
+
+fn logical_precedence(op: LogicalOp) -> Precedence {
+    match op {
+        LogicalOp::Or => Precedence::Or,
+        LogicalOp::Nullish => Precedence::Nullish,
+        LogicalOp::And => Precedence::And,
+    }
+}
+
+/// Whether `new` can take the expression as its callee without parentheses
+fn is_member_chain(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Ident(_) => true,
+        ExprKind::Member {
+            object,
+            optional: false,
+            ..
+        } => is_member_chain(object),
+        _ => false,
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Write a string literal in double quotes
+fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Escape the literal text of a template string; line breaks stay as written
+fn escape_template(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => out.push_str("\\`"),
+            '\\' => out.push_str("\\\\"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() && c != '\n' && c != '\t' => {
+                out.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write an object key, quoted unless it is a plain identifier
+fn object_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_ident = chars.next().is_some_and(is_ident_start)
+        && chars.all(is_ident_continue)
+        && !KEYWORDS.contains(&key);
+
+    if is_ident {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
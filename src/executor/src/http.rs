@@ -0,0 +1,312 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::value::Value;
+
+/// Time a request may take when the script does not set `timeout`
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Largest response body accepted when the script does not set `max_bytes`
+const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
+
+/// Redirects followed before a request fails
+const MAX_REDIRECTS: usize = 10;
+
+/// HTTP client behind the network symbols
+///
+/// Connections are pooled across calls. Redirects are a per-request choice,
+/// and reqwest fixes the redirect policy per client, so there is one client
+/// that follows them and one that does not.
+pub struct HttpClient {
+    following: reqwest::Client,
+    direct: reqwest::Client,
+}
+
+/// A request as described by a script
+///
+/// `↗(url, options)`, `↓(url, body, options)` and `~(options)` all build one
+/// of these from an options object with the fields below.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+
+    /// `headers: { name: value }`
+    pub headers: Vec<(String, String)>,
+
+    /// `query: { name: value }`, appended to the URL
+    pub query: Vec<(String, String)>,
+
+    pub body: Option<HttpBody>,
+
+    /// `timeout`, in milliseconds
+    pub timeout_ms: u64,
+
+    /// `max_bytes`, the largest response body to accept
+    pub max_bytes: usize,
+
+    /// `redirects: false` returns redirect responses instead of following them
+    pub follow_redirects: bool,
+}
+
+/// Body of a request
+#[derive(Debug, Clone)]
+pub enum HttpBody {
+    /// `json: value`, sent as `application/json`
+    Json(serde_json::Value),
+
+    /// `form: { name: value }`, sent as `application/x-www-form-urlencoded`
+    Form(Vec<(String, String)>),
+
+    /// `body: text` or bytes, sent as is
+    Raw(Vec<u8>),
+}
+
+impl HttpRequest {
+    /// A request with the default limits and no headers, query or body
+    pub fn new(method: &str, url: &str) -> Self {
+        Self {
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            headers: Vec::new(),
+            query: Vec::new(),
+            body: None,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            max_bytes: DEFAULT_MAX_BYTES,
+            follow_redirects: true,
+        }
+    }
+
+    /// Build a request from an options object
+    ///
+    /// `method` and `url` may be given as defaults for options that do not
+    /// set them, e.g. `↗` defaults the method to GET.
+    pub fn from_options(options: &Value, method: Option<&str>, url: Option<&str>) -> Result<Self> {
+        let props = match options {
+            Value::Object(props) => props.lock().unwrap().clone(),
+            Value::Null => BTreeMap::new(),
+            other => {
+                return Err(invalid(format!(
+                    "request options must be an object, got {}",
+                    other.type_name()
+                )))
+            }
+        };
+
+        let method = match props.get("method") {
+            Some(Value::Str(method)) => method.clone(),
+            Some(other) => {
+                return Err(invalid(format!(
+                    "method must be a string, got {}",
+                    other.type_name()
+                )))
+            }
+            None => method.unwrap_or("GET").to_string(),
+        };
+        let url = match (props.get("url"), url) {
+            (Some(Value::Str(url)), _) => url.clone(),
+            (Some(other), _) => {
+                return Err(invalid(format!(
+                    "url must be a string, got {}",
+                    other.type_name()
+                )))
+            }
+            (None, Some(url)) => url.to_string(),
+            (None, None) => return Err(invalid("request options need a url".to_string())),
+        };
+
+        let mut request = Self::new(&method, &url);
+        for (key, value) in &props {
+            match key.as_str() {
+                "method" | "url" => {}
+                "headers" => request.headers = pairs("headers", value)?,
+                "query" => request.query = pairs("query", value)?,
+                "json" => request.body = Some(HttpBody::Json(value.to_json())),
+                "form" => request.body = Some(HttpBody::Form(pairs("form", value)?)),
+                "body" => request.body = Some(raw_body(value)?),
+                "timeout" => request.timeout_ms = limit("timeout", value)? as u64,
+                "max_bytes" => request.max_bytes = limit("max_bytes", value)? as usize,
+                "redirects" => request.follow_redirects = value.is_truthy(),
+                other => return Err(invalid(format!("unknown request option '{}'", other))),
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+impl HttpClient {
+    pub fn new() -> Result<Self> {
+        let following = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+            .build()?;
+        let direct = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { following, direct })
+    }
+
+    /// Send a request, returning the response as `{ s: status, h: headers, b: body }`
+    ///
+    /// Responses with error statuses are returned like any other; only
+    /// requests that get no response at all fail. Header names are lower
+    /// case, repeated headers are joined with `, `. The body is a string if
+    /// it is valid UTF-8 and bytes otherwise.
+    pub async fn send(&self, request: HttpRequest) -> Result<Value> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|_| invalid(format!("invalid HTTP method '{}'", request.method)))?;
+        let client = if request.follow_redirects {
+            &self.following
+        } else {
+            &self.direct
+        };
+
+        let mut builder = client
+            .request(method, &request.url)
+            .timeout(Duration::from_millis(request.timeout_ms));
+        if !request.query.is_empty() {
+            builder = builder.query(&request.query);
+        }
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder = match request.body {
+            Some(HttpBody::Json(json)) => builder
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(json.to_string()),
+            Some(HttpBody::Form(fields)) => builder.form(&fields),
+            Some(HttpBody::Raw(data)) => builder.body(data),
+            None => builder,
+        };
+
+        let mut response = builder
+            .send()
+            .await
+            .map_err(|e| request_error(&request.url, e))?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| length > request.max_bytes as u64)
+        {
+            return Err(too_large(&request.url, request.max_bytes));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| request_error(&request.url, e))?
+        {
+            if body.len() + chunk.len() > request.max_bytes {
+                return Err(too_large(&request.url, request.max_bytes));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let mut headers: BTreeMap<String, Value> = BTreeMap::new();
+        for (name, value) in response.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            match headers.get_mut(name.as_str()) {
+                Some(Value::Str(existing)) => {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+                _ => {
+                    headers.insert(name.as_str().to_string(), Value::Str(value));
+                }
+            }
+        }
+
+        let body = match String::from_utf8(body) {
+            Ok(text) => Value::Str(text),
+            Err(err) => Value::bytes(err.into_bytes()),
+        };
+
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "s".to_string(),
+            Value::Number(response.status().as_u16() as f64),
+        );
+        fields.insert("h".to_string(), Value::object(headers));
+        fields.insert("b".to_string(), body);
+        Ok(Value::object(fields))
+    }
+}
+
+/// A request body given as a string or bytes; other values are sent as JSON
+pub fn raw_body(value: &Value) -> Result<HttpBody> {
+    match value {
+        Value::Str(text) => Ok(HttpBody::Raw(text.clone().into_bytes())),
+        Value::Bytes(data) => Ok(HttpBody::Raw(data.as_ref().clone())),
+        Value::Array(_) | Value::Object(_) | Value::Number(_) | Value::Bool(_) => {
+            Ok(HttpBody::Json(value.to_json()))
+        }
+        other => Err(invalid(format!(
+            "cannot send a value of type {} as a request body",
+            other.type_name()
+        ))),
+    }
+}
+
+/// The properties of an object option as name/value pairs of strings
+fn pairs(option: &str, value: &Value) -> Result<Vec<(String, String)>> {
+    match value {
+        Value::Object(props) => Ok(props
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect()),
+        Value::Null => Ok(Vec::new()),
+        other => Err(invalid(format!(
+            "{} must be an object, got {}",
+            option,
+            other.type_name()
+        ))),
+    }
+}
+
+/// A non-negative number option
+fn limit(option: &str, value: &Value) -> Result<f64> {
+    match value {
+        Value::Number(n) if n.is_finite() && *n >= 0.0 => Ok(*n),
+        other => Err(invalid(format!(
+            "{} must be a non-negative number, got {}",
+            option, other
+        ))),
+    }
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    Error::InvalidArgument(message).into()
+}
+
+fn too_large(url: &str, max_bytes: usize) -> anyhow::Error {
+    Error::NetworkError(format!(
+        "response from {} is larger than {} bytes",
+        url, max_bytes
+    ))
+    .into()
+}
+
+/// Map a failed request to `TimeoutError` or `NetworkError`
+fn request_error(url: &str, err: reqwest::Error) -> anyhow::Error {
+    if err.is_timeout() {
+        Error::TimeoutError(format!("request to {} timed out", url)).into()
+    } else if err.is_redirect() {
+        Error::NetworkError(format!("too many redirects from {}", url)).into()
+    } else {
+        Error::NetworkError(format!("request to {} failed: {}", url, root_cause(&err))).into()
+    }
+}
+
+/// The innermost error message, which says what actually went wrong
+/// (e.g. "Connection refused") rather than just "error sending request"
+fn root_cause(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut current = err;
+    while let Some(source) = current.source() {
+        current = source;
+    }
+    current.to_string()
+}
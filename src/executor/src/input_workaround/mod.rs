@@ -5,7 +5,9 @@ use std::fs;
 use std::path::Path;
 
 use crate::error::Error;
+use crate::registry::{Capability, SymbolSignature, ValueType};
 use crate::sandbox::Sandbox;
+use crate::value::Value;
 
 /// Input workaround module that provides alternative ways to get user input
 pub struct InputWorkaround {
@@ -54,7 +56,7 @@ impl InputWorkaround {
     }
     
     /// Wait for input file to appear
-    pub fn wait_for_input_file(&self, filename: &str, timeout_ms: u64) -> Result<bool> {
+    pub async fn wait_for_input_file(&self, filename: &str, timeout_ms: u64) -> Result<bool> {
         let file_path = Path::new(&self.file_watch_dir).join(filename);
         let start = std::time::Instant::now();
         let timeout = std::time::Duration::from_millis(timeout_ms);
@@ -63,7 +65,7 @@ impl InputWorkaround {
             if file_path.exists() {
                 return Ok(true);
             }
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
         
         Ok(false)
@@ -76,70 +78,83 @@ pub fn register_input_symbols(
     input_workaround: Arc<Mutex<InputWorkaround>>,
 ) -> Result<()> {
     // Register 📥 (get input from file)
-    sandbox.register_symbol("📥", move |args| {
-        if args.len() != 1 {
-            return Err(Error::SymbolRegistrationError(
-                "📥 requires exactly one argument (filename)".to_string()
-            ).into());
+    let input_workaround_clone = Arc::clone(&input_workaround);
+    let signature = SymbolSignature::new("📥", Capability::UserIo)
+        .param("filename", ValueType::String)
+        .returns(ValueType::String)
+        .help("Read user input from a file in the input directory");
+    sandbox.register_symbol(signature, move |args| {
+        let input_workaround = Arc::clone(&input_workaround_clone);
+        async move {
+            if args.len() != 1 {
+                return Err(Error::SymbolRegistrationError(
+                    "📥 requires exactly one argument (filename)".to_string()
+                ).into());
+            }
+            
+            let content = input_workaround.lock().await.get_input_from_file(&args[0].to_string())?;
+            
+            Ok(Value::Str(content))
         }
-        
-        let filename = args[0];
-        let input_clone = Arc::clone(&input_workaround);
-        
-        // This would be properly async in a real implementation
-        let content = tokio::runtime::Runtime::new()?.block_on(async {
-            let input_lock = input_clone.lock().await;
-            input_lock.get_input_from_file(filename)
-        })?;
-        
-        Ok(content)
     })?;
     
     // Register 📤 (write output to file)
     let input_workaround_clone = Arc::clone(&input_workaround);
-    sandbox.register_symbol("📤", move |args| {
-        if args.len() != 2 {
-            return Err(Error::SymbolRegistrationError(
-                "📤 requires exactly two arguments (filename, content)".to_string()
-            ).into());
+    let signature = SymbolSignature::new("📤", Capability::UserIo)
+        .param("filename", ValueType::String)
+        .param("content", ValueType::String)
+        .returns(ValueType::String)
+        .side_effects()
+        .help("Write output for the user to a file in the input directory");
+    sandbox.register_symbol(signature, move |args| {
+        let input_workaround = Arc::clone(&input_workaround_clone);
+        async move {
+            if args.len() != 2 {
+                return Err(Error::SymbolRegistrationError(
+                    "📤 requires exactly two arguments (filename, content)".to_string()
+                ).into());
+            }
+            
+            input_workaround
+                .lock()
+                .await
+                .write_output_to_file(&args[0].to_string(), &args[1].to_string())?;
+            
+            Ok(Value::string("Output written"))
         }
-        
-        let filename = args[0];
-        let content = args[1];
-        let input_clone = Arc::clone(&input_workaround_clone);
-        
-        // This would be properly async in a real implementation
-        tokio::runtime::Runtime::new()?.block_on(async {
-            let input_lock = input_clone.lock().await;
-            input_lock.write_output_to_file(filename, content)
-        })?;
-        
-        Ok("Output written".to_string())
     })?;
     
     // Register 📩 (wait for input file)
     let input_workaround_clone = Arc::clone(&input_workaround);
-    sandbox.register_symbol("📩", move |args| {
-        if args.len() != 2 {
-            return Err(Error::SymbolRegistrationError(
-                "📩 requires exactly two arguments (filename, timeout_ms)".to_string()
-            ).into());
+    let signature = SymbolSignature::new("📩", Capability::UserIo)
+        .param("filename", ValueType::String)
+        .param("timeout_ms", ValueType::Number)
+        .returns(ValueType::Bool)
+        .help("Wait until an input file appears or the timeout expires");
+    sandbox.register_symbol(signature, move |args| {
+        let input_workaround = Arc::clone(&input_workaround_clone);
+        async move {
+            if args.len() != 2 {
+                return Err(Error::SymbolRegistrationError(
+                    "📩 requires exactly two arguments (filename, timeout_ms)".to_string()
+                ).into());
+            }
+            
+            let timeout_ms = args[1].to_number();
+            if !timeout_ms.is_finite() || timeout_ms < 0.0 {
+                return Err(Error::SymbolRegistrationError(
+                    "Invalid timeout value".to_string()
+                ).into());
+            }
+            
+            let result = input_workaround
+                .lock()
+                .await
+                .wait_for_input_file(&args[0].to_string(), timeout_ms as u64)
+                .await?;
+            
+            Ok(Value::Bool(result))
         }
-        
-        let filename = args[0];
-        let timeout_ms = args[1].parse::<u64>().map_err(|_| {
-            Error::SymbolRegistrationError("Invalid timeout value".to_string())
-        })?;
-        
-        let input_clone = Arc::clone(&input_workaround_clone);
-        
-        // This would be properly async in a real implementation
-        let result = tokio::runtime::Runtime::new()?.block_on(async {
-            let input_lock = input_clone.lock().await;
-            input_lock.wait_for_input_file(filename, timeout_ms)
-        })?;
-        
-        Ok(format!("{}", result))
     })?;
     
     Ok(())
@@ -0,0 +1,1971 @@
+mod vm;
+
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::time::Instant;
+
+use crate::ast::*;
+use crate::builtins;
+use crate::config::Config;
+use crate::debugger::{self, DebugAction, DebugSession, Pause, StackFrame};
+use crate::diagnostics;
+use crate::effects::{self, Effect};
+use crate::error::{self, Error};
+use crate::meter::{self, MemoryMeter, Usage};
+use crate::modules::{self, Exports, ModuleState};
+use crate::options::{Engine, ExecutionOptions};
+use crate::parser::{self, ParsedCode};
+use crate::registry::{CapabilityProfile, SymbolImpl, SymbolRegistry, SymbolSignature};
+use crate::report::{ExecutionReport, ReportError, SymbolInvocation};
+use crate::trace::{Outcome, Replay, Trace, TracedCall};
+use crate::value::{Closure, Function, RegexValue, Value};
+
+/// A boxed future, used to make the recursive evaluator async
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A reference-counted scope
+pub type Env = Arc<Scope>;
+
+/// Maximum nesting of function calls before a script is aborted
+const MAX_CALL_DEPTH: usize = 200;
+
+/// A lexical scope holding variable bindings
+pub struct Scope {
+    vars: Mutex<HashMap<String, Value>>,
+    parent: Option<Env>,
+}
+
+impl Scope {
+    /// Create a scope without a parent
+    pub fn root() -> Env {
+        Arc::new(Self {
+            vars: Mutex::new(HashMap::new()),
+            parent: None,
+        })
+    }
+
+    /// Create a scope nested inside `parent`
+    pub fn child(parent: &Env) -> Env {
+        Arc::new(Self {
+            vars: Mutex::new(HashMap::new()),
+            parent: Some(Arc::clone(parent)),
+        })
+    }
+
+    /// Declare (or redeclare) a variable in this scope
+    pub fn declare(&self, name: &str, value: Value) {
+        self.vars.lock().unwrap().insert(name.to_string(), value);
+    }
+
+    /// Look a variable up in this scope or any enclosing one
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.vars.lock().unwrap().get(name) {
+            return Some(value.clone());
+        }
+
+        self.parent.as_ref().and_then(|parent| parent.lookup(name))
+    }
+
+    /// Assign to an existing variable, returning `false` if it is not declared
+    pub fn assign(&self, name: &str, value: Value) -> bool {
+        {
+            let mut vars = self.vars.lock().unwrap();
+            if let Some(slot) = vars.get_mut(name) {
+                *slot = value;
+                return true;
+            }
+        }
+
+        match &self.parent {
+            Some(parent) => parent.assign(name, value),
+            None => false,
+        }
+    }
+
+    /// The enclosing scope
+    pub fn parent(&self) -> Option<&Env> {
+        self.parent.as_ref()
+    }
+
+    /// Call `f` with every binding declared directly in this scope
+    pub fn visit_bindings(&self, mut f: impl FnMut(&str, &Value)) {
+        for (name, value) in self.vars.lock().unwrap().iter() {
+            f(name, value);
+        }
+    }
+
+    /// Drop every binding, breaking reference cycles between closures and
+    /// the scope they were declared in
+    pub fn clear(&self) {
+        let vars = std::mem::take(&mut *self.vars.lock().unwrap());
+        drop(vars);
+    }
+}
+
+/// How control leaves a statement
+enum Flow {
+    Normal,
+    Return(Value),
+    Break,
+    Continue,
+}
+
+/// Interpreter for Anarchy-Inference programs
+///
+/// Programs are evaluated by walking the syntax tree or, with
+/// `Engine::Bytecode`, compiled and run on the stack VM in `vm`. Both engines
+/// share the scopes, symbol dispatch and resource limits below.
+pub struct Interpreter {
+    symbols: SymbolRegistry,
+
+    /// Capabilities the script may use
+    capabilities: CapabilityProfile,
+
+    globals: Env,
+    output: Mutex<Vec<String>>,
+    call_depth: AtomicUsize,
+
+    /// When the script has to stop, `None` if it may run forever
+    deadline: Option<Instant>,
+
+    /// `max_execution_time_ms` the deadline was derived from
+    time_limit_ms: u64,
+
+    /// Accounts for the memory used by script values
+    memory: MemoryMeter,
+
+    /// Scopes of the blocks currently executing, the roots of memory measurements
+    frames: Mutex<Vec<Env>>,
+
+    /// Statement currently executing, reported when the memory limit is hit
+    statement: Mutex<Span>,
+
+    /// Source of the program being run
+    source: Mutex<String>,
+
+    /// Innermost expression that raised the error currently propagating
+    failed_at: Mutex<Option<Span>>,
+
+    /// Calls to registered symbols, in order
+    invocations: Mutex<Vec<SymbolInvocation>>,
+
+    /// Whether symbols with side effects are stubbed out and recorded
+    dry_run: bool,
+
+    /// Whether a dry run stubs out symbols without side effects as well
+    stub_reads: bool,
+
+    /// Side effects recorded during a dry run, in order
+    effects: Mutex<Vec<Effect>>,
+
+    /// Attached debugger, taken out while it has execution paused
+    debug: Mutex<Option<DebugSession>>,
+
+    /// Function calls currently executing, with the scope of their body
+    calls: Mutex<Vec<(StackFrame, Env)>>,
+
+    /// Names of the globals installed before the script starts
+    builtin_globals: Vec<String>,
+
+    /// Symbol calls recorded for the report's trace, `None` if not recording
+    recorded: Option<Mutex<Vec<TracedCall>>>,
+
+    /// Trace whose results answer symbol calls instead of their handlers
+    replay: Option<Replay>,
+
+    /// Strings loaded with `🔠`, referenced as `:key`
+    strings: Mutex<BTreeMap<String, String>>,
+
+    /// Directory relative paths in the script are resolved against
+    script_dir: Option<PathBuf>,
+
+    /// Directories searched for imported modules
+    library_path: Vec<PathBuf>,
+
+    /// Modules imported with `⟰`, by canonical path
+    modules: Mutex<HashMap<PathBuf, ModuleState>>,
+
+    /// Modules currently loading, innermost last
+    importing: Mutex<Vec<PathBuf>>,
+
+    /// Global scopes of the imported modules
+    module_scopes: Mutex<Vec<Env>>,
+
+    /// Engine running the program
+    engine: Engine,
+
+    /// Function bodies compiled for the VM
+    compiled: Mutex<vm::Compiled>,
+}
+
+/// Keeps a scope registered as executing until dropped
+struct FrameGuard<'a> {
+    frames: &'a Mutex<Vec<Env>>,
+}
+
+impl Drop for FrameGuard<'_> {
+    fn drop(&mut self) {
+        self.frames.lock().unwrap().pop();
+    }
+}
+
+/// Build a runtime error that points at `span`
+fn runtime_error(span: Span, message: impl std::fmt::Display) -> anyhow::Error {
+    Error::CodeExecutionError(format!(
+        "{} at line {}, column {}",
+        message, span.line, span.column
+    ))
+    .into()
+}
+
+/// Whether an error must not be handled by `÷` / `↺` blocks, because it
+/// signals that the script as a whole has to stop
+fn is_uncatchable(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<Error>(),
+        Some(
+            Error::ExecutionTimeout { .. }
+                | Error::MemoryLimitExceeded { .. }
+                | Error::DebuggerStopped { .. }
+                | Error::ReplayDivergence { .. }
+        )
+    )
+}
+
+/// Attach a location to errors coming from builtins that don't know one
+fn locate(span: Span, err: anyhow::Error) -> anyhow::Error {
+    if err.downcast_ref::<Error>().is_some() {
+        err
+    } else {
+        runtime_error(span, err)
+    }
+}
+
+impl Interpreter {
+    /// Create an interpreter that dispatches symbol calls to `symbols`
+    ///
+    /// The execution time limit starts counting when the interpreter is created.
+    /// A `max_execution_time_ms` of 0 disables it, and so does attaching a
+    /// debugger, since time spent paused would count against the limit.
+    pub fn new(symbols: SymbolRegistry, config: &Config, options: &ExecutionOptions) -> Self {
+        let globals = Scope::root();
+        builtins::install_globals(&globals);
+        let mut builtin_globals = Vec::new();
+        globals.visit_bindings(|name, _| builtin_globals.push(name.to_string()));
+
+        let time_limit_ms = config.max_execution_time_ms;
+        let deadline = (time_limit_ms > 0 && options.debugger.is_none())
+            .then(|| Instant::now() + std::time::Duration::from_millis(time_limit_ms));
+
+        let mut capabilities = CapabilityProfile::from_config(config);
+        if let Some(profile) = &options.capabilities {
+            capabilities = capabilities.intersect(profile);
+        }
+
+        Self {
+            symbols,
+            capabilities,
+            globals,
+            output: Mutex::new(Vec::new()),
+            call_depth: AtomicUsize::new(0),
+            deadline,
+            time_limit_ms,
+            memory: MemoryMeter::new(config.max_memory_bytes),
+            frames: Mutex::new(Vec::new()),
+            statement: Mutex::new(Span::default()),
+            source: Mutex::new(String::new()),
+            failed_at: Mutex::new(None),
+            invocations: Mutex::new(Vec::new()),
+            dry_run: options.dry_run,
+            stub_reads: options.stub_reads,
+            effects: Mutex::new(Vec::new()),
+            debug: Mutex::new(options.debugger.clone().map(DebugSession::new)),
+            calls: Mutex::new(Vec::new()),
+            builtin_globals,
+            recorded: options.record.then(|| Mutex::new(Vec::new())),
+            replay: options.replay.as_ref().map(Replay::new),
+            strings: Mutex::new(BTreeMap::new()),
+            script_dir: options.script_dir.clone(),
+            library_path: options.library_path.clone(),
+            modules: Mutex::new(HashMap::new()),
+            importing: Mutex::new(Vec::new()),
+            module_scopes: Mutex::new(Vec::new()),
+            engine: options.engine,
+            compiled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run a whole program
+    pub async fn run(&self, code: &ParsedCode) -> ExecutionReport {
+        *self.source.lock().unwrap() = code.raw_code.clone();
+        let started = std::time::Instant::now();
+
+        let mut result = match self.engine {
+            Engine::TreeWalker => self.run_program(&code.program).await,
+            Engine::Bytecode => self.run_compiled(&code.program).await,
+        };
+        if let (Ok(_), Some(replay)) = (&result, &self.replay) {
+            let line = self.statement.lock().unwrap().line;
+            if let Err(err) = replay.finish(line) {
+                result = Err(err);
+            }
+        }
+        self.frames.lock().unwrap().clear();
+        self.calls.lock().unwrap().clear();
+        self.globals.clear();
+        self.modules.lock().unwrap().clear();
+        for scope in self.module_scopes.lock().unwrap().drain(..) {
+            scope.clear();
+        }
+        self.compiled.lock().unwrap().clear();
+
+        let (value, error) = match result {
+            Ok(value) => (value, None),
+            Err(err) => {
+                let span = self
+                    .failed_at
+                    .lock()
+                    .unwrap()
+                    .or(Some(*self.statement.lock().unwrap()));
+                (Value::Null, Some(ReportError::new(&err, span)))
+            }
+        };
+
+        ExecutionReport {
+            output: std::mem::take(&mut *self.output.lock().unwrap()),
+            value,
+            error,
+            invocations: std::mem::take(&mut *self.invocations.lock().unwrap()),
+            effects: std::mem::take(&mut *self.effects.lock().unwrap()),
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            trace: self.recorded.as_ref().map(|calls| Trace {
+                calls: std::mem::take(&mut *calls.lock().unwrap()),
+                ..Trace::new(code.raw_code.clone())
+            }),
+        }
+    }
+
+    async fn run_program(&self, program: &Program) -> Result<Value> {
+        let env = Arc::clone(&self.globals);
+        self.hoist(&program.body, &env);
+
+        let mut last = Value::Null;
+        for stmt in &program.body {
+            if let StmtKind::Expr(expr) = &stmt.kind {
+                self.enter_statement(stmt.span);
+                self.debug_hook(stmt.span, &env).await?;
+                last = self.eval_expr(expr, &env).await?;
+                continue;
+            }
+
+            match self.exec_stmt(stmt, &env).await? {
+                Flow::Normal => {}
+                Flow::Return(value) => return Ok(value),
+                Flow::Break => return Err(runtime_error(stmt.span, "'↵' used outside of a loop")),
+                Flow::Continue => {
+                    return Err(runtime_error(stmt.span, "continue used outside of a loop"))
+                }
+            }
+        }
+
+        Ok(last)
+    }
+
+    /// Run the top level of an imported module, returning what it exported
+    ///
+    /// Unlike in the main script, `⟼` does not end a module: every `⟼` adds
+    /// an export, named after the identifier it exports.
+    async fn run_module(&self, program: &Program, env: &Env) -> Result<Vec<(String, Value)>> {
+        self.hoist(&program.body, env);
+
+        let mut exports = Vec::new();
+        for stmt in &program.body {
+            if let StmtKind::Export(expr) = &stmt.kind {
+                self.enter_statement(stmt.span);
+                self.debug_hook(stmt.span, env).await?;
+                let value = self.eval_expr(expr, env).await?;
+                let name = match &expr.kind {
+                    ExprKind::Ident(name) => name.clone(),
+                    _ => modules::DEFAULT_EXPORT.to_string(),
+                };
+                exports.push((name, value));
+                continue;
+            }
+
+            match self.exec_stmt(stmt, env).await? {
+                Flow::Normal => {}
+                Flow::Return(_) => break,
+                Flow::Break => return Err(runtime_error(stmt.span, "'↵' used outside of a loop")),
+                Flow::Continue => {
+                    return Err(runtime_error(stmt.span, "continue used outside of a loop"))
+                }
+            }
+        }
+
+        Ok(exports)
+    }
+
+    /// Find the file an import refers to, checking with `?` so that modules
+    /// outside the sandbox are not found
+    async fn resolve_module(&self, spec: &str, span: Span) -> Result<PathBuf> {
+        let importer_dir = match self.importing.lock().unwrap().last() {
+            Some(module) => module.parent().map(Path::to_path_buf),
+            None => self.script_dir.clone(),
+        };
+        let candidates = modules::candidates(spec, importer_dir.as_deref(), &self.library_path);
+        for candidate in &candidates {
+            if self.file_call("?", candidate, None, span).await?.is_truthy() {
+                return Ok(candidate.canonicalize()?);
+            }
+        }
+        Err(modules::not_found(spec, &candidates))
+    }
+
+    /// Load a module once per run, resolving `spec` relative to the file
+    /// that imports it
+    ///
+    /// Modules are found and read with `?` and `📖`, so the sandbox confines
+    /// them like any other file.
+    async fn import(&self, spec: &str, span: Span) -> Result<(PathBuf, Arc<Exports>)> {
+        let path = self.resolve_module(spec, span).await?;
+        let name = modules::display(&path);
+
+        match self.modules.lock().unwrap().get(&path) {
+            Some(ModuleState::Loaded(exports)) => return Ok((path, Arc::clone(exports))),
+            Some(ModuleState::Loading) => {
+                let importing = self.importing.lock().unwrap();
+                let start = importing.iter().position(|m| *m == path).unwrap_or(0);
+                let cycle: Vec<String> = importing[start..]
+                    .iter()
+                    .chain(std::iter::once(&path))
+                    .map(|module| modules::display(module))
+                    .collect();
+                return Err(anyhow::anyhow!("Import cycle: {}", cycle.join(" -> ")));
+            }
+            None => {}
+        }
+
+        let code = self.file_call("📖", &path, None, span).await?.to_string();
+        let parsed = parser::parse_spanned(&code).map_err(|e| {
+            anyhow::anyhow!(
+                "Syntax error in module {}:{}:{}: {}",
+                name,
+                e.span.line,
+                e.span.column,
+                e.message
+            )
+        })?;
+
+        self.modules
+            .lock()
+            .unwrap()
+            .insert(path.clone(), ModuleState::Loading);
+        self.importing.lock().unwrap().push(path.clone());
+        let outer_source = std::mem::replace(&mut *self.source.lock().unwrap(), code);
+
+        // Each module has globals of its own, so only exports leave it
+        let env = Scope::root();
+        builtins::install_globals(&env);
+        self.module_scopes.lock().unwrap().push(Arc::clone(&env));
+        let result = match self.engine {
+            Engine::TreeWalker => self.run_module(&parsed.program, &env).await,
+            Engine::Bytecode => self.run_compiled_module(&parsed.program, &env).await,
+        };
+
+        *self.source.lock().unwrap() = outer_source;
+        self.importing.lock().unwrap().pop();
+
+        match result {
+            Ok(exports) => {
+                let exports = Arc::new(Exports::new(exports));
+                self.modules
+                    .lock()
+                    .unwrap()
+                    .insert(path.clone(), ModuleState::Loaded(Arc::clone(&exports)));
+                Ok((path, exports))
+            }
+            Err(err) => {
+                self.modules.lock().unwrap().remove(&path);
+                if is_uncatchable(&err) {
+                    return Err(err);
+                }
+
+                // Report the error at the import, pointing into the module in the message
+                let span = self
+                    .failed_at
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .unwrap_or(*self.statement.lock().unwrap());
+                Err(anyhow::anyhow!(
+                    "Error in module {}:{}:{}: {}",
+                    name,
+                    span.line,
+                    span.column,
+                    diagnostics::describe(&err).1
+                ))
+            }
+        }
+    }
+
+    /// Declare every `ƒ` function of a statement list before running it,
+    /// so functions can be called before their definition
+    fn hoist(&self, stmts: &[Stmt], env: &Env) {
+        for stmt in stmts {
+            if let StmtKind::Function(decl) = &stmt.kind {
+                if let Some(name) = &decl.name {
+                    let closure = Closure {
+                        decl: Arc::clone(decl),
+                        env: Arc::clone(env),
+                    };
+                    env.declare(
+                        &name.name,
+                        Value::Function(Function::Closure(Arc::new(closure))),
+                    );
+                }
+            }
+        }
+    }
+
+    fn print(&self, values: &[Value]) {
+        let line = values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        log::debug!("⌽ {}", line);
+        self.output.lock().unwrap().push(line);
+    }
+
+    // ----- statements -----
+
+    fn exec_block<'a>(&'a self, block: &'a Block, env: &'a Env) -> BoxFuture<'a, Result<Flow>> {
+        Box::pin(async move {
+            let scope = Scope::child(env);
+            self.exec_stmts(&block.stmts, &scope).await
+        })
+    }
+
+    async fn exec_stmts(&self, stmts: &[Stmt], env: &Env) -> Result<Flow> {
+        let _frame = self.enter_frame(env);
+        self.hoist(stmts, env);
+
+        for stmt in stmts {
+            match self.exec_stmt(stmt, env).await? {
+                Flow::Normal => {}
+                other => return Ok(other),
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn exec_stmt<'a>(&'a self, stmt: &'a Stmt, env: &'a Env) -> BoxFuture<'a, Result<Flow>> {
+        Box::pin(async move {
+            let outer = self.enter_statement(stmt.span);
+            let flow = match self.debug_hook(stmt.span, env).await {
+                Ok(()) => self.exec_stmt_kind(stmt, env).await,
+                Err(err) => Err(err),
+            };
+            self.enter_statement(outer);
+            flow
+        })
+    }
+
+    async fn exec_stmt_kind(&self, stmt: &Stmt, env: &Env) -> Result<Flow> {
+        match &stmt.kind {
+            StmtKind::Let { name, init } => {
+                let value = match init {
+                    Some(init) => self.eval_expr(init, env).await?,
+                    None => Value::Null,
+                };
+                env.declare(&name.name, value);
+                Ok(Flow::Normal)
+            }
+            // Functions are declared when their block is entered
+            StmtKind::Function(_) => Ok(Flow::Normal),
+            StmtKind::Expr(expr) => {
+                self.eval_expr(expr, env).await?;
+                Ok(Flow::Normal)
+            }
+            StmtKind::Print(args) => {
+                let values = self.eval_args(args, env).await?;
+                self.print(&values);
+                Ok(Flow::Normal)
+            }
+            StmtKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval_expr(cond, env).await?.is_truthy() {
+                    self.exec_block(then_branch, env).await
+                } else if let Some(else_branch) = else_branch {
+                    self.exec_stmt(else_branch, env).await
+                } else {
+                    Ok(Flow::Normal)
+                }
+            }
+            StmtKind::While { cond, body } => {
+                loop {
+                    self.check_deadline(stmt.span)?;
+                    if !self.eval_expr(cond, env).await?.is_truthy() {
+                        break;
+                    }
+
+                    match self.exec_block(body, env).await? {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow @ Flow::Return(_) => return Ok(flow),
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            StmtKind::ForEach { iterable, body } => {
+                self.exec_foreach(iterable, body, env).await
+            }
+            StmtKind::Return(value) => {
+                let value = match value {
+                    Some(value) => self.eval_expr(value, env).await?,
+                    None => Value::Null,
+                };
+                Ok(Flow::Return(value))
+            }
+            StmtKind::Export(value) => Ok(Flow::Return(self.eval_expr(value, env).await?)),
+            StmtKind::Break => Ok(Flow::Break),
+            StmtKind::Continue => Ok(Flow::Continue),
+            StmtKind::Try {
+                body,
+                param,
+                handler,
+            } => match self.exec_block(body, env).await {
+                Ok(flow) => Ok(flow),
+                Err(err) if is_uncatchable(&err) => Err(err),
+                Err(err) => {
+                    let error = self.caught(&err);
+                    let scope = Scope::child(env);
+                    if let Some(param) = param {
+                        scope.declare(&param.name, error);
+                    }
+                    self.exec_stmts(&handler.stmts, &scope).await
+                }
+            },
+            StmtKind::Block(block) => self.exec_block(block, env).await,
+        }
+    }
+
+    async fn exec_foreach(&self, iterable: &Expr, body: &ForEachBody, env: &Env) -> Result<Flow> {
+        let collection = self.eval_expr(iterable, env).await?;
+        let items = iteration_items(&collection, iterable.span)?;
+
+        match body {
+            ForEachBody::Inline { params, body } => {
+                for (item, key) in items {
+                    self.check_deadline(iterable.span)?;
+                    let scope = Scope::child(env);
+                    self.bind_params(params, vec![item, key], &scope).await?;
+
+                    // The inline body behaves like the callback it is written
+                    // as: `↩` ends the current iteration, `↵` ends the loop
+                    match self.exec_stmts(&body.stmts, &scope).await? {
+                        Flow::Normal | Flow::Continue | Flow::Return(_) => {}
+                        Flow::Break => break,
+                    }
+                }
+            }
+            ForEachBody::Callback(callback) => {
+                let callback = self.eval_expr(callback, env).await?;
+                for (item, key) in items {
+                    self.call_value(&callback, vec![item, key], iterable.span)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    // ----- expressions -----
+
+    async fn eval_args(&self, args: &[Expr], env: &Env) -> Result<Vec<Value>> {
+        let mut values = Vec::with_capacity(args.len());
+
+        for arg in args {
+            if let ExprKind::Spread(inner) = &arg.kind {
+                let value = self.eval_expr(inner, env).await?;
+                spread_into(&mut values, &value, arg.span)?;
+            } else {
+                values.push(self.eval_expr(arg, env).await?);
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn eval_expr<'a>(&'a self, expr: &'a Expr, env: &'a Env) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(async move {
+            let result = self.eval_expr_kind(expr, env).await;
+            if result.is_err() {
+                self.failed_at.lock().unwrap().get_or_insert(expr.span);
+            }
+            result
+        })
+    }
+
+    async fn eval_expr_kind(&self, expr: &Expr, env: &Env) -> Result<Value> {
+        let span = expr.span;
+
+        match &expr.kind {
+            ExprKind::Number(n) => Ok(Value::Number(*n)),
+            ExprKind::Str(s) => Ok(Value::Str(s.clone())),
+            ExprKind::Bool(b) => Ok(Value::Bool(*b)),
+            ExprKind::Null => Ok(Value::Null),
+            ExprKind::Template(parts) => {
+                let mut result = String::new();
+                for part in parts {
+                    match part {
+                        TemplatePart::Text(text) => result.push_str(text),
+                        TemplatePart::Expr(expr) => {
+                            result.push_str(&self.eval_expr(expr, env).await?.to_string());
+                        }
+                    }
+                }
+                self.track(Value::Str(result))
+            }
+            ExprKind::Regex { pattern, flags } => {
+                let regex =
+                    RegexValue::new(pattern, flags).map_err(|e| runtime_error(span, e))?;
+                Ok(Value::Regex(Arc::new(regex)))
+            }
+            ExprKind::Ident(name) => env
+                .lookup(name)
+                .ok_or_else(|| runtime_error(span, format!("Undefined variable '{}'", name))),
+            ExprKind::StringRef(key) => self.string_ref(key, span),
+            ExprKind::Array(elements) => {
+                self.track(Value::array(self.eval_args(elements, env).await?))
+            }
+            ExprKind::Object(props) => {
+                let mut map = BTreeMap::new();
+                for prop in props {
+                    let value = self.eval_expr(&prop.value, env).await?;
+                    map.insert(prop.key.clone(), value);
+                }
+                self.track(Value::object(map))
+            }
+            ExprKind::Lambda(decl) => {
+                Ok(Value::Function(Function::Closure(Arc::new(Closure {
+                    decl: Arc::clone(decl),
+                    env: Arc::clone(env),
+                }))))
+            }
+            ExprKind::Unary { op, operand } => {
+                if *op == UnaryOp::Typeof {
+                    if let ExprKind::Ident(name) = &operand.kind {
+                        if env.lookup(name).is_none() {
+                            return Ok(Value::from("undefined"));
+                        }
+                    }
+                }
+
+                let value = self.eval_expr(operand, env).await?;
+                Ok(unary_op(*op, &value))
+            }
+            ExprKind::Binary { op, left, right } => {
+                let left = self.eval_expr(left, env).await?;
+                let right = self.eval_expr(right, env).await?;
+                self.track(binary_op(*op, &left, &right)?)
+            }
+            ExprKind::Logical { op, left, right } => {
+                let left_value = self.eval_expr(left, env).await?;
+                let short_circuit = match op {
+                    LogicalOp::And => !left_value.is_truthy(),
+                    LogicalOp::Or => left_value.is_truthy(),
+                    LogicalOp::Nullish => !left_value.is_null(),
+                };
+
+                if short_circuit {
+                    Ok(left_value)
+                } else {
+                    self.eval_expr(right, env).await
+                }
+            }
+            ExprKind::Assign { op, target, value } => {
+                let value = self.eval_expr(value, env).await?;
+                let value = match op.binary() {
+                    Some(binary) => {
+                        let current = self.eval_expr(target, env).await?;
+                        self.track(binary_op(binary, &current, &value)?)?
+                    }
+                    None => value,
+                };
+                self.assign(target, value.clone(), env).await?;
+                Ok(value)
+            }
+            ExprKind::Update { op, prefix, target } => {
+                let old = self.eval_expr(target, env).await?.to_number();
+                let new = match op {
+                    UpdateOp::Increment => old + 1.0,
+                    UpdateOp::Decrement => old - 1.0,
+                };
+                self.assign(target, Value::Number(new), env).await?;
+                Ok(Value::Number(if *prefix { new } else { old }))
+            }
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval_expr(cond, env).await?.is_truthy() {
+                    self.eval_expr(then_branch, env).await
+                } else {
+                    self.eval_expr(else_branch, env).await
+                }
+            }
+            ExprKind::Member {
+                object,
+                property,
+                optional,
+            } => {
+                let target = self.eval_expr(object, env).await?;
+                read_member(&target, &property.name, *optional, property.span)
+            }
+            ExprKind::Index {
+                object,
+                index,
+                optional,
+            } => {
+                let target = self.eval_expr(object, env).await?;
+                if target.is_null() {
+                    if *optional {
+                        return Ok(Value::Null);
+                    }
+                    return Err(runtime_error(span, "Cannot index into null"));
+                }
+                let index = self.eval_expr(index, env).await?;
+                Ok(builtins::get_index(&target, &index))
+            }
+            ExprKind::Call {
+                callee,
+                args,
+                optional,
+            } => {
+                let result = self.eval_call(callee, args, *optional, span, env).await?;
+                self.track(result)
+            }
+            ExprKind::SymbolCall { symbol, args } => {
+                let args = self.eval_args(args, env).await?;
+                let result = self.call_symbol(symbol, args, span).await?;
+                self.track_deep(result)
+            }
+            ExprKind::New { callee, args } => {
+                let args = self.eval_args(args, env).await?;
+                if let ExprKind::Ident(name) = &callee.kind {
+                    if let Some(result) = builtins::construct(name, &args) {
+                        return self.track(result.map_err(|e| locate(span, e))?);
+                    }
+                }
+
+                let callee = self.eval_expr(callee, env).await?;
+                self.call_value(&callee, args, span).await
+            }
+            ExprKind::Spread(_) => Err(runtime_error(
+                span,
+                "Spread is only allowed in arguments and arrays",
+            )),
+            ExprKind::Block(block) => self.eval_block_expr(block, env).await,
+        }
+    }
+
+    /// Evaluate a block used as an expression; its value is the value of
+    /// the last expression statement or of a `↩` inside it
+    async fn eval_block_expr(&self, block: &Block, env: &Env) -> Result<Value> {
+        let scope = Scope::child(env);
+        let _frame = self.enter_frame(&scope);
+        self.hoist(&block.stmts, &scope);
+
+        let mut last = Value::Null;
+        for stmt in &block.stmts {
+            if let StmtKind::Expr(expr) = &stmt.kind {
+                let outer = self.enter_statement(stmt.span);
+                last = self.eval_expr(expr, &scope).await?;
+                self.enter_statement(outer);
+                continue;
+            }
+
+            match self.exec_stmt(stmt, &scope).await? {
+                Flow::Normal => last = Value::Null,
+                Flow::Return(value) => return Ok(value),
+                Flow::Break | Flow::Continue => {
+                    return Err(runtime_error(
+                        stmt.span,
+                        "Loop control is not allowed inside a block expression",
+                    ))
+                }
+            }
+        }
+
+        Ok(last)
+    }
+
+    async fn assign(&self, target: &Expr, value: Value, env: &Env) -> Result<()> {
+        match &target.kind {
+            ExprKind::Ident(name) => {
+                if env.assign(name, value) {
+                    Ok(())
+                } else {
+                    Err(runtime_error(
+                        target.span,
+                        format!("Assignment to undeclared variable '{}'", name),
+                    ))
+                }
+            }
+            ExprKind::Member {
+                object, property, ..
+            } => {
+                let target_value = self.eval_expr(object, env).await?;
+                write_member(&target_value, &property.name, value, property.span)
+            }
+            ExprKind::Index { object, index, .. } => {
+                let target_value = self.eval_expr(object, env).await?;
+                let index = self.eval_expr(index, env).await?;
+                let growth = self.reserve_index(&target_value, &index)?;
+                builtins::set_index(&target_value, &index, value)
+                    .map_err(|e| runtime_error(target.span, e))?;
+                self.charge(growth)
+            }
+            _ => Err(runtime_error(target.span, "Invalid assignment target")),
+        }
+    }
+
+    // ----- calls -----
+
+    async fn eval_call(
+        &self,
+        callee: &Expr,
+        args: &[Expr],
+        optional: bool,
+        span: Span,
+        env: &Env,
+    ) -> Result<Value> {
+        // Method calls need the receiver, so they are resolved before the callee is evaluated
+        if let ExprKind::Member {
+            object,
+            property,
+            optional: optional_member,
+        } = &callee.kind
+        {
+            let receiver = self.eval_expr(object, env).await?;
+            if receiver.is_null() {
+                if *optional_member || optional {
+                    return Ok(Value::Null);
+                }
+                return Err(runtime_error(
+                    property.span,
+                    format!("Cannot call method '{}' on null", property.name),
+                ));
+            }
+
+            let args = self.eval_args(args, env).await?;
+            return self
+                .call_method(&receiver, &property.name, args, property.span)
+                .await;
+        }
+
+        let function = self.eval_expr(callee, env).await?;
+        if function.is_null() && optional {
+            return Ok(Value::Null);
+        }
+
+        let args = self.eval_args(args, env).await?;
+        self.call_value(&function, args, span).await
+    }
+
+    async fn call_method(
+        &self,
+        receiver: &Value,
+        name: &str,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value> {
+        if let Value::Object(props) = receiver {
+            let method = props.lock().unwrap().get(name).cloned();
+            return match method {
+                Some(method @ Value::Function(_)) => self.call_value(&method, args, span).await,
+                _ => Err(runtime_error(span, format!("'{}' is not a function", name))),
+            };
+        }
+
+        // Array methods that take callbacks
+        if let Value::Array(items) = receiver {
+            let callback = args.first().cloned().unwrap_or(Value::Null);
+            let snapshot = || items.lock().unwrap().clone();
+
+            match name {
+                "forEach" => {
+                    for (i, item) in snapshot().into_iter().enumerate() {
+                        self.call_value(&callback, vec![item, Value::Number(i as f64)], span)
+                            .await?;
+                    }
+                    return Ok(Value::Null);
+                }
+                "map" => {
+                    let mut result = Vec::new();
+                    for (i, item) in snapshot().into_iter().enumerate() {
+                        result.push(
+                            self.call_value(&callback, vec![item, Value::Number(i as f64)], span)
+                                .await?,
+                        );
+                    }
+                    return Ok(Value::array(result));
+                }
+                "filter" | "find" | "some" | "every" => {
+                    let mut kept = Vec::new();
+                    for (i, item) in snapshot().into_iter().enumerate() {
+                        let keep = self
+                            .call_value(
+                                &callback,
+                                vec![item.clone(), Value::Number(i as f64)],
+                                span,
+                            )
+                            .await?
+                            .is_truthy();
+                        match (name, keep) {
+                            ("find", true) => return Ok(item),
+                            ("some", true) => return Ok(Value::Bool(true)),
+                            ("every", false) => return Ok(Value::Bool(false)),
+                            ("filter", true) => kept.push(item),
+                            _ => {}
+                        }
+                    }
+                    return Ok(match name {
+                        "filter" => Value::array(kept),
+                        "find" => Value::Null,
+                        "some" => Value::Bool(false),
+                        _ => Value::Bool(true),
+                    });
+                }
+                "reduce" => {
+                    let mut items = snapshot().into_iter().enumerate();
+                    let mut acc = match args.get(1) {
+                        Some(initial) => initial.clone(),
+                        None => match items.next() {
+                            Some((_, first)) => first,
+                            None => {
+                                return Err(runtime_error(
+                                    span,
+                                    "reduce of empty array with no initial value",
+                                ))
+                            }
+                        },
+                    };
+                    for (i, item) in items {
+                        acc = self
+                            .call_value(&callback, vec![acc, item, Value::Number(i as f64)], span)
+                            .await?;
+                    }
+                    return Ok(acc);
+                }
+                _ => {}
+            }
+        }
+
+        // Refuse before allocating rather than after: a single `repeat`
+        // can ask for far more memory than the process has
+        if let (Value::Str(s), "repeat") = (receiver, name) {
+            let count = builtins::arg(&args, 0).to_number().max(0.0);
+            self.reserve((s.len() as f64 * count).min(usize::MAX as f64) as usize)?;
+        }
+
+        match builtins::call_method(receiver, name, &args) {
+            Some(result) => result.map_err(|e| locate(span, e)),
+            None => Err(runtime_error(
+                span,
+                format!(
+                    "Unknown method '{}' on a value of type {}",
+                    name,
+                    receiver.type_name()
+                ),
+            )),
+        }
+    }
+
+    /// Call a function value with already evaluated arguments
+    fn call_value<'a>(
+        &'a self,
+        function: &'a Value,
+        args: Vec<Value>,
+        span: Span,
+    ) -> BoxFuture<'a, Result<Value>> {
+        Box::pin(async move {
+            let function = match function {
+                Value::Function(function) => function,
+                other => {
+                    return Err(runtime_error(
+                        span,
+                        format!("Value of type {} is not callable", other.type_name()),
+                    ))
+                }
+            };
+
+            match function {
+                Function::Native(native) => {
+                    let result = (native.func)(args).map_err(|e| locate(span, e))?;
+                    self.track_deep(result)
+                }
+                Function::Closure(closure) => {
+                    self.check_deadline(span)?;
+                    if self.call_depth.fetch_add(1, Ordering::SeqCst) >= MAX_CALL_DEPTH {
+                        self.call_depth.fetch_sub(1, Ordering::SeqCst);
+                        return Err(runtime_error(span, "Maximum call depth exceeded"));
+                    }
+
+                    let result = match self.engine {
+                        Engine::TreeWalker => self.call_closure(closure, args, span).await,
+                        Engine::Bytecode => self.call_compiled(closure, args, span).await,
+                    };
+                    self.call_depth.fetch_sub(1, Ordering::SeqCst);
+                    result
+                }
+            }
+        })
+    }
+
+    async fn call_closure(&self, closure: &Closure, args: Vec<Value>, span: Span) -> Result<Value> {
+        let scope = Scope::child(&closure.env);
+        self.bind_params(&closure.decl.params, args, &scope).await?;
+
+        let frame = StackFrame {
+            function: closure
+                .decl
+                .name
+                .as_ref()
+                .map_or_else(|| "<lambda>".to_string(), |name| name.name.clone()),
+            call_span: span,
+        };
+        self.calls.lock().unwrap().push((frame, Arc::clone(&scope)));
+        let flow = self.exec_stmts(&closure.decl.body.stmts, &scope).await;
+        self.calls.lock().unwrap().pop();
+
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            Flow::Normal => Ok(Value::Null),
+            Flow::Break | Flow::Continue => Err(runtime_error(
+                closure.decl.span,
+                "Loop control escaped from a function body",
+            )),
+        }
+    }
+
+    async fn bind_params(&self, params: &[Param], args: Vec<Value>, scope: &Env) -> Result<()> {
+        let mut args = args.into_iter();
+
+        for param in params {
+            let value = if param.rest {
+                Value::array(args.by_ref().collect())
+            } else {
+                match (args.next(), &param.default) {
+                    (Some(value), _) if !value.is_null() || param.default.is_none() => value,
+                    (_, Some(default)) => self.eval_expr(default, scope).await?,
+                    (_, None) => Value::Null,
+                }
+            };
+            scope.declare(&param.name.name, value);
+        }
+
+        Ok(())
+    }
+
+    /// Dispatch a symbol call to a built-in or a registered handler
+    async fn call_symbol(&self, symbol: &str, args: Vec<Value>, span: Span) -> Result<Value> {
+        let registered = self.symbols.get(symbol).ok_or_else(|| {
+            anyhow::Error::from(Error::UnknownSymbol(format!(
+                "'{}' at line {}, column {}",
+                symbol, span.line, span.column
+            )))
+        })?;
+
+        let signature = &registered.signature;
+        if !signature.accepts(args.len()) {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "{} expects {}, got {} (usage: {})",
+                    signature.name,
+                    signature.arity_description(),
+                    args.len(),
+                    signature.usage()
+                ),
+            ));
+        }
+
+        self.check_capability(signature, span)?;
+        self.check_deadline(span)?;
+
+        let handler = match &registered.implementation {
+            SymbolImpl::Builtin => {
+                let call = self.call_intrinsic(&signature.name, &args, span);
+                return self
+                    .with_deadline(call, span)
+                    .await?
+                    .map_err(|e| locate(span, e));
+            }
+            SymbolImpl::Handler(handler) => Arc::clone(handler),
+        };
+
+        if self.dry_run && effects::is_stubbed(signature, self.stub_reads) {
+            let result = effects::stub(signature);
+            if signature.side_effects {
+                let effect = Effect::new(signature, args.clone(), span);
+                self.effects.lock().unwrap().push(effect);
+            }
+            self.define_string(&signature.name, &args)?;
+
+            self.invocations.lock().unwrap().push(SymbolInvocation {
+                symbol: signature.name.clone(),
+                capability: signature.capability,
+                args,
+                result: Some(result.clone()),
+                error: None,
+                span,
+                duration_ms: 0.0,
+            });
+            return Ok(result);
+        }
+
+        let started = std::time::Instant::now();
+        let outcome = match &self.replay {
+            Some(replay) => replay.next(signature, &args, span)?,
+            None => match self.with_deadline(handler(args.clone()), span).await {
+                Ok(Ok(result)) => Outcome::Returned(result),
+                Ok(Err(e)) => Outcome::Failed {
+                    kind: error::kind(&e),
+                    message: e.to_string(),
+                },
+                Err(_) => Outcome::TimedOut,
+            },
+        };
+
+        if let Some(recorded) = &self.recorded {
+            let (result, error, error_kind) = match &outcome {
+                Outcome::Returned(value) => (Some(value.clone()), None, None),
+                Outcome::Failed { kind, message } => {
+                    (None, Some(message.clone()), Some(kind.clone()))
+                }
+                Outcome::TimedOut => (None, None, None),
+            };
+            recorded.lock().unwrap().push(TracedCall {
+                symbol: signature.name.clone(),
+                args: args.clone(),
+                result,
+                error,
+                error_kind,
+                timed_out: matches!(outcome, Outcome::TimedOut),
+                line: span.line,
+            });
+        }
+
+        let result = match outcome {
+            Outcome::Returned(value) => Ok(value),
+            Outcome::Failed { kind, message } => Err(Error::SymbolFailed {
+                symbol: signature.name.clone(),
+                args: args.clone(),
+                kind,
+                message,
+                line: span.line,
+                column: span.column,
+            }
+            .into()),
+            Outcome::TimedOut => Err(self.timeout_error(span)),
+        };
+
+        self.invocations.lock().unwrap().push(SymbolInvocation {
+            symbol: signature.name.clone(),
+            capability: signature.capability,
+            args: args.clone(),
+            result: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            span,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+        });
+
+        let value = result?;
+        self.define_string(&signature.name, &args)?;
+        Ok(value)
+    }
+
+    /// Fail with `PermissionDenied` if the capability profile forbids `signature`
+    fn check_capability(&self, signature: &SymbolSignature, span: Span) -> Result<()> {
+        if self.capabilities.allows(signature.capability) {
+            return Ok(());
+        }
+
+        Err(Error::PermissionDenied {
+            symbol: signature.name.clone(),
+            capability: signature.capability,
+            line: span.line,
+            column: span.column,
+        }
+        .into())
+    }
+
+    /// Call the handler of the file symbol `symbol` on `path` (and
+    /// `contents` for writes) for a file the interpreter handles itself
+    ///
+    /// The system sandbox and the capability profile apply as they do to the
+    /// script's own calls, but the call is not reported, recorded or stubbed.
+    async fn file_call(
+        &self,
+        symbol: &str,
+        path: &Path,
+        contents: Option<String>,
+        span: Span,
+    ) -> Result<Value> {
+        let registered = self.symbols.get(symbol).ok_or_else(|| {
+            anyhow::Error::from(Error::UnknownSymbol(format!(
+                "'{}' is needed for {}",
+                symbol,
+                path.display()
+            )))
+        })?;
+        self.check_capability(&registered.signature, span)?;
+
+        let mut args = vec![Value::Str(path.display().to_string())];
+        args.extend(contents.map(Value::Str));
+        let result = match &registered.implementation {
+            SymbolImpl::Handler(handler) => handler(args.clone()).await,
+            SymbolImpl::Builtin => return Err(anyhow::anyhow!("'{}' has no handler", symbol)),
+        };
+        result.map_err(|e| {
+            Error::SymbolFailed {
+                symbol: registered.signature.name.clone(),
+                args,
+                kind: error::kind(&e),
+                message: e.to_string(),
+                line: span.line,
+                column: span.column,
+            }
+            .into()
+        })
+    }
+
+    /// Define the `:key` string for `📝(key, text)` calls storing a string,
+    /// which is how dictionary scripts build the dictionary `💾` saves
+    fn define_string(&self, symbol: &str, args: &[Value]) -> Result<()> {
+        if let ("📝", [Value::Str(key), Value::Str(text)]) = (symbol, args) {
+            self.charge(key.len() + text.len())?;
+            self.strings
+                .lock()
+                .unwrap()
+                .insert(key.clone(), text.clone());
+        }
+        Ok(())
+    }
+
+    /// Look up a string loaded with `🔠`
+    ///
+    /// A dry run that stubs reads loads no strings, so every key is an empty
+    /// string then.
+    fn string_ref(&self, key: &str, span: Span) -> Result<Value> {
+        let strings = self.strings.lock().unwrap();
+        match strings.get(key) {
+            Some(text) => Ok(Value::Str(text.clone())),
+            None if self.dry_run && self.stub_reads => Ok(Value::Str(String::new())),
+            None => Err(Error::UnknownStringKey {
+                key: key.to_string(),
+                suggestions: diagnostics::suggestions(key, strings.keys().map(String::as_str)),
+                line: span.line,
+                column: span.column,
+            }
+            .into()),
+        }
+    }
+
+    /// The value a `÷` handler receives for an error it catches
+    ///
+    /// An object with the error's `kind` and `message`, and for failed
+    /// symbol calls the `symbol` and its `args` (`null` otherwise).
+    fn caught(&self, err: &anyhow::Error) -> Value {
+        *self.failed_at.lock().unwrap() = None;
+        log::debug!("Caught error: {}", err);
+
+        let (message, symbol, args) = match err.downcast_ref::<Error>() {
+            Some(Error::SymbolFailed {
+                symbol,
+                args,
+                message,
+                ..
+            }) => (
+                message.clone(),
+                Value::Str(symbol.clone()),
+                Value::array(args.clone()),
+            ),
+            _ => (diagnostics::describe(err).1, Value::Null, Value::Null),
+        };
+
+        let mut fields = BTreeMap::new();
+        fields.insert("kind".to_string(), Value::Str(error::kind(err)));
+        fields.insert("message".to_string(), Value::Str(message));
+        fields.insert("symbol".to_string(), symbol);
+        fields.insert("args".to_string(), args);
+        Value::object(fields)
+    }
+
+    // ----- debugging -----
+
+    /// Pause before a statement if the attached debugger wants to stop there
+    ///
+    /// The session is taken out of the interpreter while paused, so code
+    /// evaluated on behalf of the debugger runs without stopping again.
+    async fn debug_hook(&self, span: Span, env: &Env) -> Result<()> {
+        let depth = self.calls.lock().unwrap().len();
+        let (mut session, reason) = {
+            let mut debug = self.debug.lock().unwrap();
+            let Some(reason) = debug
+                .as_ref()
+                .and_then(|session| session.should_pause(span.line, depth))
+            else {
+                return Ok(());
+            };
+            (debug.take().expect("debug session present"), reason)
+        };
+
+        let mut pause = self.pause(reason, span, env, &session);
+        let result = loop {
+            let action = session.debugger.lock().unwrap().paused(&pause);
+            match action {
+                DebugAction::Evaluate(code) => {
+                    let result = self.debug_evaluate(&code, env).await;
+                    session.debugger.lock().unwrap().evaluated(&code, result);
+                    // The evaluation may have changed variables or called symbols
+                    pause = self.pause(reason, span, env, &session);
+                }
+                DebugAction::AddBreakpoint(line) => {
+                    session.breakpoints.insert(line);
+                    pause.breakpoints = session.breakpoints.clone();
+                }
+                DebugAction::RemoveBreakpoint(line) => {
+                    session.breakpoints.remove(&line);
+                    pause.breakpoints = session.breakpoints.clone();
+                }
+                DebugAction::Stop => {
+                    break Err(Error::DebuggerStopped { line: span.line }.into());
+                }
+                action => {
+                    session.resume(&action, depth);
+                    break Ok(());
+                }
+            }
+        };
+
+        *self.debug.lock().unwrap() = Some(session);
+        result
+    }
+
+    /// Snapshot of the execution state for the debugger
+    fn pause(
+        &self,
+        reason: debugger::PauseReason,
+        span: Span,
+        env: &Env,
+        session: &DebugSession,
+    ) -> Pause {
+        let calls = self.calls.lock().unwrap();
+        let function_scope = calls.last().map(|(_, scope)| scope);
+
+        // Walk outwards from the current block, stopping at the function
+        // body or, at the top level, before the globals
+        let mut locals: Vec<(String, Value)> = Vec::new();
+        let mut scope = Some(env);
+        while let Some(current) = scope {
+            if Arc::ptr_eq(current, &self.globals) {
+                break;
+            }
+            current.visit_bindings(|name, value| {
+                if !locals.iter().any(|(seen, _)| seen == name) {
+                    locals.push((name.to_string(), value.clone()));
+                }
+            });
+            if function_scope.is_some_and(|function| Arc::ptr_eq(current, function)) {
+                break;
+            }
+            scope = current.parent();
+        }
+        locals.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut globals = Vec::new();
+        self.globals.visit_bindings(|name, value| {
+            if !self.builtin_globals.iter().any(|builtin| builtin == name) {
+                globals.push((name.to_string(), value.clone()));
+            }
+        });
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let invocations = self.invocations.lock().unwrap();
+        let recent = invocations.len().saturating_sub(debugger::RECENT_CALLS);
+
+        Pause {
+            reason,
+            span,
+            source_line: self
+                .source
+                .lock()
+                .unwrap()
+                .lines()
+                .nth(span.line.saturating_sub(1))
+                .unwrap_or_default()
+                .trim_end()
+                .to_string(),
+            call_stack: calls.iter().map(|(frame, _)| frame.clone()).collect(),
+            locals,
+            globals,
+            recent_calls: invocations[recent..].to_vec(),
+            breakpoints: session.breakpoints.clone(),
+        }
+    }
+
+    /// Run code typed into the debugger in the scope of the paused statement,
+    /// returning the value of the last expression
+    async fn debug_evaluate(&self, code: &str, env: &Env) -> std::result::Result<Value, String> {
+        let parsed = parser::parse_spanned(code).map_err(|err| err.message)?;
+        let statement = *self.statement.lock().unwrap();
+
+        let mut result = Ok(Value::Null);
+        for stmt in &parsed.program.body {
+            result = match &stmt.kind {
+                StmtKind::Expr(expr) => self.eval_expr(expr, env).await,
+                _ => self.exec_stmt(stmt, env).await.map(|_| Value::Null),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+
+        *self.statement.lock().unwrap() = statement;
+        *self.failed_at.lock().unwrap() = None;
+        result.map_err(|err| diagnostics::describe(&err).1)
+    }
+
+    /// Fail with `ExecutionTimeout` once the deadline has passed
+    fn check_deadline(&self, span: Span) -> Result<()> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(self.timeout_error(span)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Run a future, dropping it (and so cancelling the work it was doing)
+    /// if the deadline passes first
+    async fn with_deadline<F: Future>(&self, future: F, span: Span) -> Result<F::Output> {
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, future)
+                .await
+                .map_err(|_| self.timeout_error(span)),
+            None => Ok(future.await),
+        }
+    }
+
+    /// The deadline can pass in a loop deep inside a call, so the error is
+    /// reported where it was raised, not at the outermost expression it
+    /// propagates through
+    fn timeout_error(&self, span: Span) -> anyhow::Error {
+        *self.failed_at.lock().unwrap() = Some(span);
+        Error::ExecutionTimeout {
+            limit_ms: self.time_limit_ms,
+            line: span.line,
+        }
+        .into()
+    }
+
+    /// Register the scope of a block that starts executing
+    fn enter_frame(&self, scope: &Env) -> FrameGuard<'_> {
+        self.frames.lock().unwrap().push(Arc::clone(scope));
+        FrameGuard {
+            frames: &self.frames,
+        }
+    }
+
+    /// Record the statement that starts executing, returning the previous one
+    fn enter_statement(&self, span: Span) -> Span {
+        std::mem::replace(&mut *self.statement.lock().unwrap(), span)
+    }
+
+    /// Account for a value created by the current statement
+    fn track(&self, value: Value) -> Result<Value> {
+        if self.memory.charge(meter::shallow_size(&value)) {
+            self.settle(Some(&value))?;
+        }
+        Ok(value)
+    }
+
+    /// Account for a value created outside the interpreter, including
+    /// everything it contains
+    fn track_deep(&self, value: Value) -> Result<Value> {
+        if matches!(
+            value,
+            Value::Array(_) | Value::Object(_) | Value::Str(_) | Value::Bytes(_)
+        ) {
+            let mut usage = Usage::new();
+            usage.add_value(&value);
+            if self.memory.charge(usage.total()) {
+                self.settle(Some(&value))?;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Account for `bytes` allocated by the current statement
+    fn charge(&self, bytes: usize) -> Result<()> {
+        if self.memory.charge(bytes) {
+            self.settle(None)?;
+        }
+        Ok(())
+    }
+
+    /// Fail before allocating `bytes` if they would not fit in the quota
+    fn reserve(&self, bytes: usize) -> Result<()> {
+        if self.memory.would_exceed(bytes) {
+            self.settle(None)?;
+            if self.memory.would_exceed(bytes) {
+                return Err(self.memory_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail before `target[index] = ...` grows an array past the quota,
+    /// returning the bytes the growth takes to charge once it happened
+    fn reserve_index(&self, target: &Value, index: &Value) -> Result<usize> {
+        let growth = match (target, index) {
+            (Value::Array(items), Value::Number(n)) if *n >= 0.0 => {
+                let missing = n.floor() + 1.0 - items.lock().unwrap().len() as f64;
+                let bytes = missing.max(0.0) * std::mem::size_of::<Value>() as f64;
+                bytes.min(usize::MAX as f64) as usize
+            }
+            _ => 0,
+        };
+        self.reserve(growth)?;
+        Ok(growth)
+    }
+
+    /// Measure the memory reachable from the executing scopes and `pending`,
+    /// failing if it exceeds the quota
+    fn settle(&self, pending: Option<&Value>) -> Result<()> {
+        let mut usage = Usage::new();
+        usage.add_scope(&self.globals);
+        let modules = self.module_scopes.lock().unwrap().clone();
+        for scope in &modules {
+            usage.add_scope(scope);
+        }
+        let frames = self.frames.lock().unwrap().clone();
+        for frame in &frames {
+            usage.add_scope(frame);
+        }
+        if let Some(value) = pending {
+            usage.add_value(value);
+        }
+
+        log::debug!("Script memory usage: {} bytes", usage.total());
+        if self.memory.settle(usage.total()) {
+            Ok(())
+        } else {
+            Err(self.memory_error())
+        }
+    }
+
+    /// Reported at the statement that was executing, like a timeout
+    fn memory_error(&self) -> anyhow::Error {
+        let span = *self.statement.lock().unwrap();
+        *self.failed_at.lock().unwrap() = Some(span);
+        let source = self.source.lock().unwrap();
+        let statement = source
+            .get(span.start..span.end)
+            .and_then(|text| text.lines().next())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+
+        Error::MemoryLimitExceeded {
+            limit: self.memory.limit(),
+            line: span.line,
+            statement,
+        }
+        .into()
+    }
+
+    /// A path in the script, relative to the script's directory
+    fn script_path(&self, path: &str) -> PathBuf {
+        match &self.script_dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+
+    /// Read a JSON object of strings, relative to the script's directory
+    ///
+    /// The file is read with `📖`, and a dry run that stubs reads loads nothing.
+    async fn read_dictionary(&self, path: &str, span: Span) -> Result<BTreeMap<String, Value>> {
+        let path = self.script_path(path);
+        if self.dry_run && self.stub_reads {
+            return Ok(BTreeMap::new());
+        }
+        let json = self.file_call("📖", &path, None, span).await?.to_string();
+
+        match builtins::json_decode(&json) {
+            Ok(Value::Object(props)) => Ok(props.lock().unwrap().clone()),
+            Ok(other) => Err(anyhow::anyhow!(
+                "Dictionary {} must be a JSON object, got {}",
+                path.display(),
+                other.type_name()
+            )),
+            Err(e) => Err(anyhow::anyhow!("{} in dictionary {}", e, path.display())),
+        }
+    }
+
+    /// Symbols implemented by the interpreter itself because they work on
+    /// script values rather than on strings
+    async fn call_intrinsic(&self, symbol: &str, args: &[Value], span: Span) -> Result<Value> {
+        let arg = |i| builtins::arg(args, i);
+
+        match symbol {
+            // ＋(array, item): append to an array
+            "＋" => match arg(0) {
+                Value::Array(items) => {
+                    let added = std::mem::size_of_val(&args[1..]);
+                    let length = {
+                        let mut items = items.lock().unwrap();
+                        items.extend(args[1..].iter().cloned());
+                        items.len()
+                    };
+                    self.charge(added)?;
+                    Ok(Value::Number(length as f64))
+                }
+                other => Err(anyhow::anyhow!(
+                    "＋ expects an array, got {}",
+                    other.type_name()
+                )),
+            },
+            // 🔢(value): convert to a number
+            "🔢" => Ok(Value::Number(arg(0).to_number())),
+            // 🔤(value): convert to a string
+            "🔤" => Ok(Value::Str(arg(0).to_string())),
+            // ⏰(ms): sleep
+            "⏰" => {
+                let millis = arg(0).to_number().max(0.0) as u64;
+                tokio::time::sleep(std::time::Duration::from_millis(millis)).await;
+                Ok(Value::Null)
+            }
+            // ⟰(path, name?): import a module's main or named export
+            "⟰" => {
+                let (path, exports) = self.import(&arg(0).to_string(), span).await?;
+                match arg(1) {
+                    Value::Null => Ok(exports.main()),
+                    name => exports.get(&path, &name.to_string()),
+                }
+            }
+            // 🔠(path or object): load strings for `:key` references
+            "🔠" => {
+                let dictionary = match arg(0) {
+                    Value::Str(path) => self.read_dictionary(&path, span).await?,
+                    Value::Object(props) => props.lock().unwrap().clone(),
+                    other => {
+                        return Err(anyhow::anyhow!(
+                            "🔠 expects a file path or an object, got {}",
+                            other.type_name()
+                        ))
+                    }
+                };
+
+                let mut loaded = BTreeMap::new();
+                for (key, value) in dictionary {
+                    let text = match value {
+                        Value::Str(text) => text,
+                        value @ (Value::Number(_) | Value::Bool(_)) => value.to_string(),
+                        other => {
+                            return Err(anyhow::anyhow!(
+                                "String '{}' has a value of type {}, expected a string",
+                                key,
+                                other.type_name()
+                            ))
+                        }
+                    };
+                    self.charge(key.len() + text.len())?;
+                    loaded.insert(key, text);
+                }
+
+                let count = loaded.len();
+                self.strings.lock().unwrap().extend(loaded);
+                Ok(Value::Number(count as f64))
+            }
+            // 💾(name, path): save the strings into a dictionary file, keeping
+            // the strings it has for other keys
+            "💾" => {
+                let file = arg(1).to_string();
+                let path = self.script_path(&file);
+                if self.dry_run {
+                    if let Some(registered) = self.symbols.get(symbol) {
+                        let effect = Effect::new(&registered.signature, args.to_vec(), span);
+                        self.effects.lock().unwrap().push(effect);
+                    }
+                    return Ok(Value::Number(self.strings.lock().unwrap().len() as f64));
+                }
+
+                let mut dictionary = BTreeMap::new();
+                if self.file_call("?", &path, None, span).await?.is_truthy() {
+                    dictionary = self.read_dictionary(&file, span).await?;
+                }
+                for (key, text) in self.strings.lock().unwrap().iter() {
+                    dictionary.insert(key.clone(), Value::Str(text.clone()));
+                }
+
+                let count = dictionary.len();
+                let json = serde_json::to_string_pretty(&Value::object(dictionary).to_json())?;
+                self.file_call("✍", &path, Some(json), span).await?;
+                Ok(Value::Number(count as f64))
+            }
+            // ⎋(value): encode a value as JSON, or decode a JSON string
+            "⎋" => match arg(0) {
+                Value::Str(text) => builtins::json_decode(&text),
+                other => Ok(Value::Str(builtins::json_encode(&other))),
+            },
+            other => Err(anyhow::anyhow!("'{}' has no built-in implementation", other)),
+        }
+    }
+}
+
+/// Apply a unary operator to a value
+fn unary_op(op: UnaryOp, value: &Value) -> Value {
+    match op {
+        UnaryOp::Not => Value::Bool(!value.is_truthy()),
+        UnaryOp::Neg => Value::Number(-value.to_number()),
+        UnaryOp::Plus => Value::Number(value.to_number()),
+        UnaryOp::Typeof => Value::from(value.type_name()),
+    }
+}
+
+/// Apply a binary operator to two values
+fn binary_op(op: BinaryOp, left: &Value, right: &Value) -> Result<Value> {
+    Ok(match op {
+        BinaryOp::Add => match (left, right) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::Str(_), _)
+            | (_, Value::Str(_))
+            | (Value::Array(_), _)
+            | (Value::Object(_), _) => Value::Str(format!("{}{}", left, right)),
+            _ => Value::Number(left.to_number() + right.to_number()),
+        },
+        BinaryOp::Sub => Value::Number(left.to_number() - right.to_number()),
+        BinaryOp::Mul => Value::Number(left.to_number() * right.to_number()),
+        BinaryOp::Div => Value::Number(left.to_number() / right.to_number()),
+        BinaryOp::Rem => Value::Number(left.to_number() % right.to_number()),
+        BinaryOp::Eq => Value::Bool(left.loose_eq(right)),
+        BinaryOp::NotEq => Value::Bool(!left.loose_eq(right)),
+        BinaryOp::StrictEq => Value::Bool(left.strict_eq(right)),
+        BinaryOp::StrictNotEq => Value::Bool(!left.strict_eq(right)),
+        BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+            let ordering = match (left, right) {
+                (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+                _ => left.to_number().partial_cmp(&right.to_number()),
+            };
+
+            Value::Bool(match ordering {
+                None => false,
+                Some(ordering) => match op {
+                    BinaryOp::Lt => ordering.is_lt(),
+                    BinaryOp::LtEq => ordering.is_le(),
+                    BinaryOp::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+            })
+        }
+    })
+}
+
+/// Read `target.property`, or `target?.property` if `optional`
+fn read_member(target: &Value, property: &str, optional: bool, span: Span) -> Result<Value> {
+    if !target.is_null() {
+        Ok(builtins::get_property(target, property))
+    } else if optional {
+        Ok(Value::Null)
+    } else {
+        Err(runtime_error(
+            span,
+            format!("Cannot read property '{}' of null", property),
+        ))
+    }
+}
+
+/// Assign to `target.property`
+fn write_member(target: &Value, property: &str, value: Value, span: Span) -> Result<()> {
+    match target {
+        Value::Object(props) => {
+            props.lock().unwrap().insert(property.to_string(), value);
+            Ok(())
+        }
+        other => Err(runtime_error(
+            span,
+            format!(
+                "Cannot set property '{}' on a value of type {}",
+                property,
+                other.type_name()
+            ),
+        )),
+    }
+}
+
+/// Append the elements of a `...spread` argument
+fn spread_into(values: &mut Vec<Value>, spread: &Value, span: Span) -> Result<()> {
+    match spread {
+        Value::Array(items) => values.extend(items.lock().unwrap().iter().cloned()),
+        Value::Null => {}
+        other => {
+            return Err(runtime_error(
+                span,
+                format!("Cannot spread a value of type {}", other.type_name()),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// The items of a `∀` loop with their keys: indices for arrays, strings
+/// and bytes, property names for objects
+fn iteration_items(collection: &Value, span: Span) -> Result<Vec<(Value, Value)>> {
+    Ok(match collection {
+        Value::Array(items) => items
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.clone(), Value::Number(i as f64)))
+            .collect(),
+        Value::Object(props) => props
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (v.clone(), Value::Str(k.clone())))
+            .collect(),
+        Value::Str(s) => s
+            .chars()
+            .enumerate()
+            .map(|(i, c)| (Value::Str(c.to_string()), Value::Number(i as f64)))
+            .collect(),
+        Value::Bytes(data) => data
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| (Value::Number(*byte as f64), Value::Number(i as f64)))
+            .collect(),
+        Value::Null => Vec::new(),
+        other => {
+            return Err(runtime_error(
+                span,
+                format!("Cannot iterate over a value of type {}", other.type_name()),
+            ))
+        }
+    })
+}
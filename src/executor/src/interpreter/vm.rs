@@ -0,0 +1,629 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{
+    binary_op, is_uncatchable, iteration_items, locate, read_member, runtime_error, spread_into,
+    unary_op, write_member, BoxFuture, Env, Interpreter, Scope,
+};
+use crate::ast::{FunctionDecl, LogicalOp, Program, Span, UpdateOp};
+use crate::builtins;
+use crate::bytecode::{self, Args, Chunk, Depth, Op};
+use crate::debugger::StackFrame;
+use crate::value::{Closure, Function, RegexValue, Value};
+
+/// Compiled function bodies by address of their declaration, which each
+/// entry keeps alive so the address is not reused
+pub(super) type Compiled = HashMap<usize, (Arc<FunctionDecl>, Arc<Chunk>)>;
+
+/// What a chunk left behind when it finished
+struct Finished {
+    value: Value,
+
+    /// Values exported by a module
+    exports: Vec<(String, Value)>,
+}
+
+/// An active `÷` handler
+struct Handler {
+    /// First instruction of the handler
+    target: u32,
+
+    /// Nesting when the `÷` was entered
+    depth: Depth,
+
+    /// Stack height when the `÷` was entered
+    stack: usize,
+}
+
+/// Execution state of a running chunk
+struct Frame {
+    ip: usize,
+    stack: Vec<Value>,
+    env: Env,
+
+    /// Scopes left when entering the block scopes, innermost last
+    outer: Vec<Env>,
+
+    handlers: Vec<Handler>,
+
+    /// Statements interrupted by the running ones, innermost last
+    statements: Vec<Span>,
+
+    /// Items left in the running `∀` loops
+    iterators: Vec<std::vec::IntoIter<(Value, Value)>>,
+
+    /// Arguments not bound to parameters yet
+    args: std::vec::IntoIter<Value>,
+
+    /// The call to enter when the parameters are bound
+    call: Option<StackFrame>,
+
+    exports: Vec<(String, Value)>,
+}
+
+impl Frame {
+    fn depth(&self) -> Depth {
+        Depth {
+            scopes: self.outer.len() as u32,
+            handlers: self.handlers.len() as u32,
+            statements: self.statements.len() as u32,
+            iterators: self.iterators.len() as u32,
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("VM stack underflow")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("VM stack underflow")
+    }
+
+    /// Take the arguments of a call off the stack
+    fn pop_args(&mut self, args: Args) -> Vec<Value> {
+        match args {
+            Args::Count(count) => {
+                let start = self.stack.len() - count as usize;
+                self.stack.split_off(start)
+            }
+            Args::List => match self.pop() {
+                Value::Array(items) => std::mem::take(&mut *items.lock().unwrap()),
+                _ => unreachable!("argument list is not an array"),
+            },
+        }
+    }
+
+    /// The arguments of a call, left on the stack
+    fn peek_args(&self, args: Args) -> Vec<Value> {
+        match args {
+            Args::Count(count) => self.stack[self.stack.len() - count as usize..].to_vec(),
+            Args::List => match self.peek() {
+                Value::Array(items) => items.lock().unwrap().clone(),
+                _ => unreachable!("argument list is not an array"),
+            },
+        }
+    }
+}
+
+impl Interpreter {
+    /// Run a program on the VM
+    pub(super) async fn run_compiled(&self, program: &Program) -> Result<Value> {
+        let chunk = bytecode::compile_program(program);
+        let env = Arc::clone(&self.globals);
+        let finished = self.execute(&chunk, env, Vec::new(), None).await?;
+        Ok(finished.value)
+    }
+
+    /// Run the top level of a module on the VM, returning what it exported
+    pub(super) async fn run_compiled_module(
+        &self,
+        program: &Program,
+        env: &Env,
+    ) -> Result<Vec<(String, Value)>> {
+        let chunk = bytecode::compile_module(program);
+        let finished = self
+            .execute(&chunk, Arc::clone(env), Vec::new(), None)
+            .await?;
+        Ok(finished.exports)
+    }
+
+    /// Call a script function on the VM
+    pub(super) async fn call_compiled(
+        &self,
+        closure: &Closure,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value> {
+        let chunk = self.compile(&closure.decl);
+        let frame = StackFrame {
+            function: closure
+                .decl
+                .name
+                .as_ref()
+                .map_or_else(|| "<lambda>".to_string(), |name| name.name.clone()),
+            call_span: span,
+        };
+
+        let scope = Scope::child(&closure.env);
+        let finished = self.execute(&chunk, scope, args, Some(frame)).await?;
+        Ok(finished.value)
+    }
+
+    /// The compiled body of a function, compiling it on its first call
+    fn compile(&self, decl: &Arc<FunctionDecl>) -> Arc<Chunk> {
+        let key = Arc::as_ptr(decl) as usize;
+        if let Some((_, chunk)) = self.compiled.lock().unwrap().get(&key) {
+            return Arc::clone(chunk);
+        }
+
+        let chunk = Arc::new(bytecode::compile_function(decl));
+        self.compiled
+            .lock()
+            .unwrap()
+            .insert(key, (Arc::clone(decl), Arc::clone(&chunk)));
+        chunk
+    }
+
+    /// Run a chunk in `env` until it returns or fails
+    fn execute<'a>(
+        &'a self,
+        chunk: &'a Chunk,
+        env: Env,
+        args: Vec<Value>,
+        call: Option<StackFrame>,
+    ) -> BoxFuture<'a, Result<Finished>> {
+        Box::pin(async move {
+            let mut frame = Frame {
+                ip: 0,
+                stack: Vec::new(),
+                env,
+                outer: Vec::new(),
+                handlers: Vec::new(),
+                statements: Vec::new(),
+                iterators: Vec::new(),
+                args: args.into_iter(),
+                call,
+                exports: Vec::new(),
+            };
+            let frames = self.frames.lock().unwrap().len();
+            let calls = self.calls.lock().unwrap().len();
+
+            let result = loop {
+                let op = chunk.code[frame.ip];
+                frame.ip += 1;
+
+                let err = match self.step(chunk, op, &mut frame).await {
+                    Ok(None) => continue,
+                    Ok(Some(value)) => break Ok(value),
+                    Err(err) => err,
+                };
+
+                if let Some(at) = chunk.at[frame.ip - 1] {
+                    self.failed_at
+                        .lock()
+                        .unwrap()
+                        .get_or_insert(chunk.spans[at as usize]);
+                }
+                if is_uncatchable(&err) {
+                    break Err(err);
+                }
+                let Some(handler) = frame.handlers.pop() else {
+                    break Err(err);
+                };
+
+                self.unwind(&mut frame, handler.depth);
+                frame.stack.truncate(handler.stack);
+                frame.stack.push(self.caught(&err));
+                frame.ip = handler.target as usize;
+            };
+
+            // Leave whatever the chunk was in the middle of
+            if let Some(&outer) = frame.statements.first() {
+                self.enter_statement(outer);
+            }
+            self.frames.lock().unwrap().truncate(frames);
+            self.calls.lock().unwrap().truncate(calls);
+
+            result.map(|value| Finished {
+                value,
+                exports: frame.exports,
+            })
+        })
+    }
+
+    /// Leave the scopes, handlers, statements and loops entered below `depth`
+    fn unwind(&self, frame: &mut Frame, depth: Depth) {
+        let scopes = depth.scopes as usize;
+        if frame.outer.len() > scopes {
+            let mut frames = self.frames.lock().unwrap();
+            while frame.outer.len() > scopes {
+                frame.env = frame.outer.pop().expect("scope to leave");
+                frames.pop();
+            }
+        }
+
+        frame.handlers.truncate(depth.handlers as usize);
+
+        let statements = depth.statements as usize;
+        if frame.statements.len() > statements {
+            self.enter_statement(frame.statements[statements]);
+            frame.statements.truncate(statements);
+        }
+
+        frame.iterators.truncate(depth.iterators as usize);
+    }
+
+    /// Execute one instruction, returning the chunk's value if it finished
+    async fn step(&self, chunk: &Chunk, op: Op, frame: &mut Frame) -> Result<Option<Value>> {
+        let name = |index: u32| chunk.names[index as usize].as_str();
+        let span = |index: u32| chunk.spans[index as usize];
+
+        match op {
+            // ----- values -----
+            Op::Constant(index) => frame.stack.push(chunk.constants[index as usize].clone()),
+            Op::Regex { regex, span: at } => {
+                let (pattern, flags) = &chunk.regexes[regex as usize];
+                let regex =
+                    RegexValue::new(pattern, flags).map_err(|e| runtime_error(span(at), e))?;
+                frame.stack.push(Value::Regex(Arc::new(regex)));
+            }
+            Op::Load {
+                name: index,
+                span: at,
+            } => {
+                let value = frame.env.lookup(name(index)).ok_or_else(|| {
+                    runtime_error(span(at), format!("Undefined variable '{}'", name(index)))
+                })?;
+                frame.stack.push(value);
+            }
+            Op::TypeofName(index) => {
+                let type_name = match frame.env.lookup(name(index)) {
+                    Some(value) => value.type_name(),
+                    None => "undefined",
+                };
+                frame.stack.push(Value::from(type_name));
+            }
+            Op::StringRef { key, span: at } => {
+                let value = self.string_ref(name(key), span(at))?;
+                frame.stack.push(value);
+            }
+            Op::Closure(index) => {
+                let closure = Closure {
+                    decl: Arc::clone(&chunk.functions[index as usize]),
+                    env: Arc::clone(&frame.env),
+                };
+                frame
+                    .stack
+                    .push(Value::Function(Function::Closure(Arc::new(closure))));
+            }
+            Op::Array(args) => {
+                let items = frame.pop_args(args);
+                frame.stack.push(self.track(Value::array(items))?);
+            }
+            Op::Object(keys) => {
+                let keys = &chunk.keys[keys as usize];
+                let values = frame.pop_args(Args::Count(keys.len() as u32));
+                let map = keys.iter().cloned().zip(values).collect();
+                frame.stack.push(self.track(Value::object(map))?);
+            }
+            Op::Template(count) => {
+                let parts = frame.pop_args(Args::Count(count));
+                let text: String = parts.iter().map(|part| part.to_string()).collect();
+                frame.stack.push(self.track(Value::Str(text))?);
+            }
+            Op::Pop => {
+                frame.pop();
+            }
+            Op::Swap => {
+                let len = frame.stack.len();
+                frame.stack.swap(len - 1, len - 2);
+            }
+
+            // ----- operators -----
+            Op::Unary(op) => {
+                let value = frame.pop();
+                frame.stack.push(unary_op(op, &value));
+            }
+            Op::Binary(op) => {
+                let right = frame.pop();
+                let left = frame.pop();
+                frame.stack.push(self.track(binary_op(op, &left, &right)?)?);
+            }
+            Op::Update { op, prefix } => {
+                let old = frame.pop().to_number();
+                let new = match op {
+                    UpdateOp::Increment => old + 1.0,
+                    UpdateOp::Decrement => old - 1.0,
+                };
+                frame
+                    .stack
+                    .push(Value::Number(if prefix { new } else { old }));
+                frame.stack.push(Value::Number(new));
+            }
+            Op::ShortCircuit { op, target } => {
+                let value = frame.peek();
+                let short_circuit = match op {
+                    LogicalOp::And => !value.is_truthy(),
+                    LogicalOp::Or => value.is_truthy(),
+                    LogicalOp::Nullish => !value.is_null(),
+                };
+
+                if short_circuit {
+                    frame.ip = target as usize;
+                } else {
+                    frame.pop();
+                }
+            }
+
+            // ----- variables and properties -----
+            Op::Declare(index) => {
+                let value = frame.pop();
+                frame.env.declare(name(index), value);
+            }
+            Op::Store {
+                name: index,
+                span: at,
+            } => {
+                let value = frame.peek().clone();
+                if !frame.env.assign(name(index), value) {
+                    return Err(runtime_error(
+                        span(at),
+                        format!("Assignment to undeclared variable '{}'", name(index)),
+                    ));
+                }
+            }
+            Op::GetMember {
+                name: index,
+                optional,
+                span: at,
+            } => {
+                let target = frame.pop();
+                let value = read_member(&target, name(index), optional, span(at))?;
+                frame.stack.push(value);
+            }
+            Op::SetMember {
+                name: index,
+                span: at,
+            } => {
+                let target = frame.pop();
+                write_member(&target, name(index), frame.peek().clone(), span(at))?;
+            }
+            Op::IndexGuard {
+                optional,
+                target,
+                span: at,
+            } => {
+                if frame.peek().is_null() {
+                    if !optional {
+                        return Err(runtime_error(span(at), "Cannot index into null"));
+                    }
+                    frame.ip = target as usize;
+                }
+            }
+            Op::GetIndex => {
+                let index = frame.pop();
+                let target = frame.pop();
+                frame.stack.push(builtins::get_index(&target, &index));
+            }
+            Op::SetIndex { span: at } => {
+                let index = frame.pop();
+                let target = frame.pop();
+                let growth = self.reserve_index(&target, &index)?;
+                builtins::set_index(&target, &index, frame.peek().clone())
+                    .map_err(|e| runtime_error(span(at), e))?;
+                self.charge(growth)?;
+            }
+
+            // ----- argument lists -----
+            Op::List => frame.stack.push(Value::array(Vec::new())),
+            Op::Push => {
+                let value = frame.pop();
+                if let Value::Array(items) = frame.peek() {
+                    items.lock().unwrap().push(value);
+                }
+            }
+            Op::Extend { span: at } => {
+                let value = frame.pop();
+                if let Value::Array(items) = frame.peek() {
+                    spread_into(&mut items.lock().unwrap(), &value, span(at))?;
+                }
+            }
+
+            // ----- calls -----
+            Op::Call { args, span: at } => {
+                let args = frame.pop_args(args);
+                let function = frame.pop();
+                let result = self.call_value(&function, args, span(at)).await?;
+                frame.stack.push(self.track(result)?);
+            }
+            Op::MethodGuard {
+                name: index,
+                optional,
+                target,
+                span: at,
+            } => {
+                if frame.peek().is_null() {
+                    if !optional {
+                        return Err(runtime_error(
+                            span(at),
+                            format!("Cannot call method '{}' on null", name(index)),
+                        ));
+                    }
+                    frame.ip = target as usize;
+                }
+            }
+            Op::CallMethod {
+                name: index,
+                args,
+                span: at,
+            } => {
+                let args = frame.pop_args(args);
+                let receiver = frame.pop();
+                let result = self
+                    .call_method(&receiver, name(index), args, span(at))
+                    .await?;
+                frame.stack.push(self.track(result)?);
+            }
+            Op::JumpIfNull(target) => {
+                if frame.peek().is_null() {
+                    frame.ip = target as usize;
+                }
+            }
+            Op::CallSymbol {
+                symbol,
+                args,
+                span: at,
+            } => {
+                let args = frame.pop_args(args);
+                let result = self.call_symbol(name(symbol), args, span(at)).await?;
+                frame.stack.push(self.track_deep(result)?);
+            }
+            Op::Construct {
+                name: index,
+                args,
+                target,
+                span: at,
+            } => {
+                if let Some(result) = builtins::construct(name(index), &frame.peek_args(args)) {
+                    frame.pop_args(args);
+                    frame
+                        .stack
+                        .push(self.track(result.map_err(|e| locate(span(at), e))?)?);
+                    frame.ip = target as usize;
+                }
+            }
+            Op::New { args, span: at } => {
+                let function = frame.pop();
+                let args = frame.pop_args(args);
+                let result = self.call_value(&function, args, span(at)).await?;
+                frame.stack.push(result);
+            }
+
+            // ----- statements -----
+            Op::Statement(at) => {
+                frame.statements.push(self.enter_statement(span(at)));
+                self.debug_hook(span(at), &frame.env).await?;
+            }
+            Op::ExprStatement(at) => frame.statements.push(self.enter_statement(span(at))),
+            Op::TopStatement(at) => {
+                self.enter_statement(span(at));
+                self.debug_hook(span(at), &frame.env).await?;
+            }
+            Op::EndStatement => {
+                let outer = frame.statements.pop().expect("statement to end");
+                self.enter_statement(outer);
+            }
+            Op::Print(args) => {
+                let values = frame.pop_args(args);
+                self.print(&values);
+            }
+            Op::Export(index) => {
+                let value = frame.pop();
+                frame.exports.push((name(index).to_string(), value));
+            }
+            Op::Return => return Ok(Some(frame.pop())),
+            Op::CheckDeadline(at) => self.check_deadline(span(at))?,
+
+            // ----- control flow -----
+            Op::Jump(target) => frame.ip = target as usize,
+            Op::JumpIfFalse(target) => {
+                if !frame.pop().is_truthy() {
+                    frame.ip = target as usize;
+                }
+            }
+            Op::PushScope => {
+                let scope = Scope::child(&frame.env);
+                self.frames.lock().unwrap().push(Arc::clone(&scope));
+                frame.outer.push(std::mem::replace(&mut frame.env, scope));
+            }
+            Op::PopScope => {
+                frame.env = frame.outer.pop().expect("scope to leave");
+                self.frames.lock().unwrap().pop();
+            }
+            Op::Param {
+                name: index,
+                rest,
+                default,
+            } => {
+                let value = if rest {
+                    Value::array(frame.args.by_ref().collect())
+                } else {
+                    match (frame.args.next(), default) {
+                        (Some(value), _) if !value.is_null() || default.is_none() => {
+                            if let Some(target) = default {
+                                frame.ip = target as usize;
+                            }
+                            value
+                        }
+                        // Fall through to the code computing the default
+                        (_, Some(_)) => return Ok(None),
+                        (_, None) => Value::Null,
+                    }
+                };
+                frame.env.declare(name(index), value);
+            }
+            Op::EnterFunction => {
+                if let Some(call) = frame.call.take() {
+                    let scope = Arc::clone(&frame.env);
+                    self.calls.lock().unwrap().push((call, scope));
+                }
+                self.frames.lock().unwrap().push(Arc::clone(&frame.env));
+            }
+            Op::IterStart(at) => {
+                let collection = frame.pop();
+                let items = iteration_items(&collection, span(at))?;
+                frame.iterators.push(items.into_iter());
+            }
+            Op::IterNext { target, span: at } => {
+                let next = frame.iterators.last_mut().and_then(Iterator::next);
+                match next {
+                    Some((item, key)) => {
+                        self.check_deadline(span(at))?;
+                        frame.args = vec![item, key].into_iter();
+                    }
+                    None => frame.ip = target as usize,
+                }
+            }
+            Op::IterCall { target, span: at } => {
+                let next = frame.iterators.last_mut().and_then(Iterator::next);
+                match next {
+                    Some((item, key)) => {
+                        let callback = frame.peek().clone();
+                        self.call_value(&callback, vec![item, key], span(at))
+                            .await?;
+                    }
+                    None => frame.ip = target as usize,
+                }
+            }
+            Op::IterEnd => {
+                frame.iterators.pop();
+            }
+            Op::PushTry(target) => {
+                let handler = Handler {
+                    target,
+                    depth: frame.depth(),
+                    stack: frame.stack.len(),
+                };
+                frame.handlers.push(handler);
+            }
+            Op::PopTry => {
+                frame.handlers.pop();
+            }
+            Op::Unwind(depth) => self.unwind(frame, depth),
+            Op::Escape {
+                message,
+                span: at,
+                depth,
+            } => {
+                self.unwind(frame, depth);
+                return Err(runtime_error(span(at), name(message)));
+            }
+            Op::Fail { message, span: at } => {
+                return Err(runtime_error(span(at), name(message)));
+            }
+        }
+
+        Ok(None)
+    }
+}
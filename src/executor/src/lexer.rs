@@ -0,0 +1,822 @@
+use crate::ast::{Comment, Span};
+
+/// Tokens produced by the lexer
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    // Literals
+    Number(f64),
+    Str(String),
+    Template(Vec<TemplateSegment>),
+    Regex {
+        pattern: String,
+        flags: String,
+    },
+    Ident(String),
+
+    /// An emoji or glyph that is called like a function (`📂`, `↗`, `✍`, ...)
+    Symbol(String),
+
+    // Glyph keywords (and their ASCII spellings)
+    Function,    // ƒ
+    Lambda,      // λ
+    Let,         // ι
+    Return,      // ↩ / return
+    Export,      // ⟼
+    If,          // ↪ / if
+    Else,        // ↛ / else
+    While,       // ↻ / while
+    Break,       // ↵ / break
+    Continue,    // continue
+    ForEach,     // ∀
+    Print,       // ⌽
+    Try,         // ÷
+    TryBlock,    // ↺
+    Catch,       // ⚠
+    Sigil(char), // σ
+
+    // Word keywords
+    True,
+    False,
+    Null,
+    New,
+    Typeof,
+
+    // Punctuation
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+    Ellipsis,
+    Semicolon,
+    Colon,
+    Question,
+    QuestionDot,
+    QuestionQuestion,
+
+    // Operators
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Bang,
+    Tilde,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PlusPlus,
+    MinusMinus,
+    EqEq,
+    EqEqEq,
+    BangEq,
+    BangEqEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    AndAnd,
+    OrOr,
+
+    Eof,
+}
+
+/// A piece of a template string
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSegment {
+    /// Literal text
+    Text(String),
+
+    /// Source of an interpolated `${...}` expression and where it starts
+    Code {
+        source: String,
+        offset: usize,
+        line: usize,
+        column: usize,
+    },
+}
+
+/// A token together with its location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+
+    /// Whether a line break separates this token from the previous one
+    pub newline_before: bool,
+}
+
+/// A lexing error with its location
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+/// Tokenizer for Anarchy-Inference source code
+pub struct Lexer<'a> {
+    source: &'a str,
+    chars: Vec<(usize, char)>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    base_offset: usize,
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    newline_before: bool,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a lexer for a complete source file
+    pub fn new(source: &'a str) -> Self {
+        Self::with_origin(source, 0, 1, 1)
+    }
+
+    /// Create a lexer for a fragment that starts at the given position of
+    /// a larger source (used for template interpolations)
+    pub fn with_origin(source: &'a str, offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().collect(),
+            pos: 0,
+            line,
+            column,
+            base_offset: offset,
+            tokens: Vec::new(),
+            comments: Vec::new(),
+            newline_before: false,
+        }
+    }
+
+    /// Tokenize the whole source, returning the tokens and the comments
+    /// between them
+    pub fn tokenize(mut self) -> Result<(Vec<Token>, Vec<Comment>), LexError> {
+        loop {
+            self.skip_trivia()?;
+
+            if self.is_at_end() {
+                let span = self.span_from(self.pos, self.line, self.column);
+                self.push(TokenKind::Eof, span);
+                break;
+            }
+
+            self.scan_token()?;
+        }
+
+        Ok((self.tokens, self.comments))
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|(_, c)| *c)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<char> {
+        self.chars.get(self.pos + n).map(|(_, c)| *c)
+    }
+
+    fn byte_offset(&self, pos: usize) -> usize {
+        self.chars
+            .get(pos)
+            .map(|(i, _)| *i)
+            .unwrap_or(self.source.len())
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn matches(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn span_from(&self, start_pos: usize, line: usize, column: usize) -> Span {
+        Span::new(
+            self.base_offset + self.byte_offset(start_pos),
+            self.base_offset + self.byte_offset(self.pos),
+            line,
+            column,
+        )
+    }
+
+    fn push(&mut self, kind: TokenKind, span: Span) {
+        self.tokens.push(Token {
+            kind,
+            span,
+            newline_before: self.newline_before,
+        });
+        self.newline_before = false;
+    }
+
+    fn push_comment(&mut self, start_pos: usize, line: usize, column: usize, trailing: bool) {
+        let span = self.span_from(start_pos, line, column);
+        let text = self.source[self.byte_offset(start_pos)..self.byte_offset(self.pos)]
+            .trim_end()
+            .to_string();
+        self.comments.push(Comment {
+            text,
+            span,
+            trailing,
+        });
+    }
+
+    fn error(
+        &self,
+        message: impl Into<String>,
+        start_pos: usize,
+        line: usize,
+        column: usize,
+    ) -> LexError {
+        LexError {
+            message: message.into(),
+            span: self.span_from(start_pos, line, column),
+        }
+    }
+
+    /// Skip whitespace and comments
+    fn skip_trivia(&mut self) -> Result<(), LexError> {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                self.newline_before = true;
+                self.advance();
+            } else if c.is_whitespace() || c == '\u{feff}' {
+                self.advance();
+            } else if c == '/' && self.peek_at(1) == Some('/') {
+                let (start, line, column) = (self.pos, self.line, self.column);
+                let trailing = !self.tokens.is_empty() && !self.newline_before;
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.advance();
+                }
+                self.push_comment(start, line, column, trailing);
+            } else if c == '/' && self.peek_at(1) == Some('*') {
+                let (start, line, column) = (self.pos, self.line, self.column);
+                let trailing = !self.tokens.is_empty() && !self.newline_before;
+                self.advance();
+                self.advance();
+
+                loop {
+                    match self.peek() {
+                        None => {
+                            return Err(self.error(
+                                "Unterminated block comment",
+                                start,
+                                line,
+                                column,
+                            ))
+                        }
+                        Some('*') if self.peek_at(1) == Some('/') => {
+                            self.advance();
+                            self.advance();
+                            self.push_comment(start, line, column, trailing);
+                            break;
+                        }
+                        Some('\n') => {
+                            self.newline_before = true;
+                            self.advance();
+                        }
+                        Some(_) => {
+                            self.advance();
+                        }
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scan_token(&mut self) -> Result<(), LexError> {
+        let (start, line, column) = (self.pos, self.line, self.column);
+        let c = self.advance().unwrap_or('\0');
+
+        let kind = match c {
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '{' => TokenKind::LBrace,
+            '}' => TokenKind::RBrace,
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            ',' => TokenKind::Comma,
+            ';' => TokenKind::Semicolon,
+            ':' => TokenKind::Colon,
+            '~' => TokenKind::Tilde,
+            '%' => TokenKind::Percent,
+            '.' if self.peek().is_some_and(|c| c.is_ascii_digit()) => {
+                return self.scan_number(start, line, column);
+            }
+            '.' if self.peek() == Some('.') && self.peek_at(1) == Some('.') => {
+                self.advance();
+                self.advance();
+                TokenKind::Ellipsis
+            }
+            '.' => TokenKind::Dot,
+            '?' => {
+                if self.peek() == Some('.')
+                    && !self.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+                {
+                    self.advance();
+                    TokenKind::QuestionDot
+                } else if self.matches('?') {
+                    TokenKind::QuestionQuestion
+                } else {
+                    TokenKind::Question
+                }
+            }
+            '+' => {
+                if self.matches('+') {
+                    TokenKind::PlusPlus
+                } else if self.matches('=') {
+                    TokenKind::PlusAssign
+                } else {
+                    TokenKind::Plus
+                }
+            }
+            '-' => {
+                if self.matches('-') {
+                    TokenKind::MinusMinus
+                } else if self.matches('=') {
+                    TokenKind::MinusAssign
+                } else {
+                    TokenKind::Minus
+                }
+            }
+            '*' => {
+                if self.matches('=') {
+                    TokenKind::StarAssign
+                } else {
+                    TokenKind::Star
+                }
+            }
+            '/' => {
+                if self.regex_allowed() {
+                    return self.scan_regex(start, line, column);
+                } else if self.matches('=') {
+                    TokenKind::SlashAssign
+                } else {
+                    TokenKind::Slash
+                }
+            }
+            '=' => {
+                if self.matches('=') {
+                    if self.matches('=') {
+                        TokenKind::EqEqEq
+                    } else {
+                        TokenKind::EqEq
+                    }
+                } else {
+                    TokenKind::Assign
+                }
+            }
+            '!' => {
+                if self.matches('=') {
+                    if self.matches('=') {
+                        TokenKind::BangEqEq
+                    } else {
+                        TokenKind::BangEq
+                    }
+                } else {
+                    TokenKind::Bang
+                }
+            }
+            '<' => {
+                if self.matches('=') {
+                    TokenKind::LtEq
+                } else {
+                    TokenKind::Lt
+                }
+            }
+            '>' => {
+                if self.matches('=') {
+                    TokenKind::GtEq
+                } else {
+                    TokenKind::Gt
+                }
+            }
+            '&' if self.matches('&') => TokenKind::AndAnd,
+            '|' if self.matches('|') => TokenKind::OrOr,
+            '"' | '\'' => return self.scan_string(c, start, line, column),
+            '`' => return self.scan_template(start, line, column),
+            c if c.is_ascii_digit() => return self.scan_number(start, line, column),
+            c if is_ident_start(c) => return self.scan_identifier(start, line, column),
+            c => match glyph_keyword(c) {
+                Some(kind) => kind,
+                None if is_symbol_char(c) => {
+                    self.consume_symbol_modifiers();
+                    let end = self.byte_offset(self.pos);
+                    TokenKind::Symbol(self.source[self.byte_offset(start)..end].to_string())
+                }
+                None => {
+                    return Err(self.error(
+                        format!("Unexpected character '{}'", c),
+                        start,
+                        line,
+                        column,
+                    ));
+                }
+            },
+        };
+
+        let span = self.span_from(start, line, column);
+        self.push(kind, span);
+        Ok(())
+    }
+
+    /// Consume variation selectors, skin tone modifiers and zero-width
+    /// joiner sequences that belong to the emoji just scanned
+    fn consume_symbol_modifiers(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '\u{fe0e}' | '\u{fe0f}' | '\u{1f3fb}'..='\u{1f3ff}' => {
+                    self.advance();
+                }
+                '\u{200d}' => {
+                    self.advance();
+                    if self.peek().is_some_and(is_symbol_char) {
+                        self.advance();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Whether a `/` at the current position starts a regular expression
+    fn regex_allowed(&self) -> bool {
+        match self.tokens.last().map(|t| &t.kind) {
+            None => true,
+            Some(kind) => !matches!(
+                kind,
+                TokenKind::Number(_)
+                    | TokenKind::Str(_)
+                    | TokenKind::Template(_)
+                    | TokenKind::Regex { .. }
+                    | TokenKind::Ident(_)
+                    | TokenKind::True
+                    | TokenKind::False
+                    | TokenKind::Null
+                    | TokenKind::RParen
+                    | TokenKind::RBracket
+                    | TokenKind::RBrace
+                    | TokenKind::PlusPlus
+                    | TokenKind::MinusMinus
+            ),
+        }
+    }
+
+    fn scan_number(&mut self, start: usize, line: usize, column: usize) -> Result<(), LexError> {
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '_')
+        {
+            self.advance();
+        }
+
+        if self.peek() == Some('.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+            }
+        }
+
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let sign = matches!(self.peek_at(1), Some('+') | Some('-'));
+            let digit_at = if sign { 2 } else { 1 };
+
+            if self.peek_at(digit_at).is_some_and(|c| c.is_ascii_digit()) {
+                for _ in 0..digit_at {
+                    self.advance();
+                }
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.advance();
+                }
+            }
+        }
+
+        let text: String = self.source[self.byte_offset(start)..self.byte_offset(self.pos)]
+            .chars()
+            .filter(|c| *c != '_')
+            .collect();
+        let value = text.parse::<f64>().map_err(|_| {
+            self.error(
+                format!("Invalid number literal '{}'", text),
+                start,
+                line,
+                column,
+            )
+        })?;
+
+        let span = self.span_from(start, line, column);
+        self.push(TokenKind::Number(value), span);
+        Ok(())
+    }
+
+    fn scan_identifier(
+        &mut self,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Result<(), LexError> {
+        while self.peek().is_some_and(is_ident_continue) {
+            self.advance();
+        }
+
+        let text = &self.source[self.byte_offset(start)..self.byte_offset(self.pos)];
+        let kind = match text {
+            "if" => TokenKind::If,
+            "else" => TokenKind::Else,
+            "while" => TokenKind::While,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
+            "return" => TokenKind::Return,
+            "true" => TokenKind::True,
+            "false" => TokenKind::False,
+            "null" | "undefined" => TokenKind::Null,
+            "new" => TokenKind::New,
+            "typeof" => TokenKind::Typeof,
+            _ => TokenKind::Ident(text.to_string()),
+        };
+
+        let span = self.span_from(start, line, column);
+        self.push(kind, span);
+        Ok(())
+    }
+
+    fn scan_escape(
+        &mut self,
+        out: &mut String,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Result<(), LexError> {
+        let c = self
+            .advance()
+            .ok_or_else(|| self.error("Unterminated escape sequence", start, line, column))?;
+
+        match c {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            '0' => out.push('\0'),
+            'u' => {
+                let braced = self.matches('{');
+                let mut hex = String::new();
+
+                while let Some(c) = self.peek() {
+                    if braced && c == '}' {
+                        self.advance();
+                        break;
+                    }
+                    if !c.is_ascii_hexdigit() || (!braced && hex.len() == 4) {
+                        break;
+                    }
+                    hex.push(c);
+                    self.advance();
+                }
+
+                let ch = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("Invalid unicode escape", start, line, column))?;
+                out.push(ch);
+            }
+            '\n' => {}
+            other => out.push(other),
+        }
+
+        Ok(())
+    }
+
+    fn scan_string(
+        &mut self,
+        quote: char,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Result<(), LexError> {
+        let mut value = String::new();
+
+        loop {
+            match self.advance() {
+                None => return Err(self.error("Unterminated string", start, line, column)),
+                Some(c) if c == quote => break,
+                Some('\\') => self.scan_escape(&mut value, start, line, column)?,
+                Some(c) => value.push(c),
+            }
+        }
+
+        let span = self.span_from(start, line, column);
+        self.push(TokenKind::Str(value), span);
+        Ok(())
+    }
+
+    fn scan_template(&mut self, start: usize, line: usize, column: usize) -> Result<(), LexError> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error("Unterminated template string", start, line, column))
+                }
+                Some('`') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') => {
+                    self.advance();
+                    self.scan_escape(&mut text, start, line, column)?;
+                }
+                Some('$') if self.peek_at(1) == Some('{') => {
+                    self.advance();
+                    self.advance();
+
+                    if !text.is_empty() {
+                        segments.push(TemplateSegment::Text(std::mem::take(&mut text)));
+                    }
+
+                    let code_start = self.pos;
+                    let (code_line, code_column) = (self.line, self.column);
+                    self.skip_interpolation(start, line, column)?;
+
+                    // The closing brace has been consumed, exclude it from the code
+                    let code =
+                        &self.source[self.byte_offset(code_start)..self.byte_offset(self.pos - 1)];
+                    segments.push(TemplateSegment::Code {
+                        source: code.to_string(),
+                        offset: self.base_offset + self.byte_offset(code_start),
+                        line: code_line,
+                        column: code_column,
+                    });
+                }
+                Some(c) => {
+                    self.advance();
+                    text.push(c);
+                }
+            }
+        }
+
+        if !text.is_empty() || segments.is_empty() {
+            segments.push(TemplateSegment::Text(text));
+        }
+
+        let span = self.span_from(start, line, column);
+        self.push(TokenKind::Template(segments), span);
+        Ok(())
+    }
+
+    /// Skip over the code of a `${...}` interpolation, including the closing brace
+    fn skip_interpolation(
+        &mut self,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Result<(), LexError> {
+        let mut depth = 0usize;
+
+        loop {
+            match self.advance() {
+                None => {
+                    return Err(self.error(
+                        "Unterminated template interpolation",
+                        start,
+                        line,
+                        column,
+                    ))
+                }
+                Some('{') => depth += 1,
+                Some('}') if depth == 0 => return Ok(()),
+                Some('}') => depth -= 1,
+                Some(q @ ('"' | '\'')) => {
+                    let mut ignored = String::new();
+                    loop {
+                        match self.advance() {
+                            None => {
+                                return Err(self.error("Unterminated string", start, line, column))
+                            }
+                            Some(c) if c == q => break,
+                            Some('\\') => self.scan_escape(&mut ignored, start, line, column)?,
+                            Some(_) => {}
+                        }
+                    }
+                }
+                Some('`') => {
+                    // Nested template: skip until its closing backtick
+                    let nested = self.tokens.len();
+                    let (s, l, c) = (self.pos - 1, self.line, self.column - 1);
+                    self.scan_template(s, l, c)?;
+                    self.tokens.truncate(nested);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn scan_regex(&mut self, start: usize, line: usize, column: usize) -> Result<(), LexError> {
+        let mut pattern = String::new();
+        let mut in_class = false;
+
+        loop {
+            match self.advance() {
+                None | Some('\n') => {
+                    return Err(self.error("Unterminated regular expression", start, line, column))
+                }
+                Some('\\') => {
+                    pattern.push('\\');
+                    if let Some(c) = self.advance() {
+                        pattern.push(c);
+                    }
+                }
+                Some('[') => {
+                    in_class = true;
+                    pattern.push('[');
+                }
+                Some(']') => {
+                    in_class = false;
+                    pattern.push(']');
+                }
+                Some('/') if !in_class => break,
+                Some(c) => pattern.push(c),
+            }
+        }
+
+        let mut flags = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            flags.push(c);
+            self.advance();
+        }
+
+        let span = self.span_from(start, line, column);
+        self.push(TokenKind::Regex { pattern, flags }, span);
+        Ok(())
+    }
+}
+
+/// Map single-character glyph keywords to their token kind
+fn glyph_keyword(c: char) -> Option<TokenKind> {
+    let kind = match c {
+        'ƒ' => TokenKind::Function,
+        'λ' => TokenKind::Lambda,
+        'ι' => TokenKind::Let,
+        '↩' => TokenKind::Return,
+        '⟼' => TokenKind::Export,
+        '↪' => TokenKind::If,
+        '↛' => TokenKind::Else,
+        '↻' => TokenKind::While,
+        '↵' => TokenKind::Break,
+        '∀' => TokenKind::ForEach,
+        '⌽' => TokenKind::Print,
+        '÷' => TokenKind::Try,
+        '↺' => TokenKind::TryBlock,
+        '⚠' => TokenKind::Catch,
+        'σ' => TokenKind::Sigil('σ'),
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+/// Whether a character can start an identifier
+pub(crate) fn is_ident_start(c: char) -> bool {
+    (c.is_alphabetic() || c == '_' || c == '$') && glyph_keyword(c).is_none()
+}
+
+/// Whether a character can continue an identifier
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+/// Whether a character can start a symbol (emoji, arrows, math glyphs, ...)
+fn is_symbol_char(c: char) -> bool {
+    !c.is_ascii() && !c.is_whitespace() && !c.is_alphanumeric() && glyph_keyword(c).is_none()
+}
@@ -1,14 +1,50 @@
 // Add input_workaround module
+pub mod ast;
+mod analyzer;
+mod builtins;
+mod bytecode;
 mod config;
+mod debugger;
+mod diagnostics;
+mod effects;
 mod error;
+mod formatter;
+mod http;
+mod interpreter;
+mod lexer;
+mod meter;
+mod migrate;
+mod modules;
+mod options;
 mod parser;
+mod provider;
+mod registry;
+mod report;
 mod sandbox;
 mod symbols;
+mod trace;
+mod value;
 mod input_workaround;
 
+pub use analyzer::{analyze, analyze_code, has_errors};
+pub use builtins::MAX_ARRAY_LENGTH;
 pub use config::Config;
+pub use debugger::{
+    DebugAction, Debugger, Pause, PauseReason, SharedDebugger, StackFrame, TerminalDebugger,
+};
+pub use diagnostics::{check_syntax, codes, render_all, Diagnostic, Severity};
+pub use effects::Effect;
 pub use error::Error;
+pub use formatter::{format_code, format_program};
 pub use input_workaround::InputWorkaround;
+pub use migrate::{migrate_code, migrate_code_with_keys};
+pub use options::{Engine, ExecutionOptions};
+pub use parser::{parse, ParsedCode, MAX_ELSE_IFS, MAX_NESTING};
+pub use provider::{SymbolProvider, SymbolRegistrar};
+pub use registry::{Capability, CapabilityProfile, SymbolParam, SymbolSignature, ValueType};
+pub use report::{ExecutionReport, ReportError, SymbolInvocation};
+pub use trace::{Trace, TracedCall, TRACE_VERSION};
+pub use value::Value;
 
 use memory::Memory;
 use browser::Browser;
@@ -25,6 +61,9 @@ pub struct Executor {
     system: Arc<Mutex<System>>,
     sandbox: sandbox::Sandbox,
     input_workaround: Arc<Mutex<InputWorkaround>>,
+    
+    /// Symbols from outside the executor, registered after the built-in ones
+    providers: Vec<Arc<dyn SymbolProvider>>,
 }
 
 impl Executor {
@@ -34,7 +73,16 @@ impl Executor {
         browser: Arc<Mutex<Browser>>,
         system: Arc<Mutex<System>>,
     ) -> Result<Self> {
-        let config = Config::default();
+        Self::with_config(Config::default(), memory, browser, system)
+    }
+    
+    /// Create a new Executor instance with a custom configuration
+    pub fn with_config(
+        config: Config,
+        memory: Arc<Mutex<Memory>>,
+        browser: Arc<Mutex<Browser>>,
+        system: Arc<Mutex<System>>,
+    ) -> Result<Self> {
         let sandbox = sandbox::Sandbox::new(&config)?;
         
         // Initialize input workaround with default directory
@@ -49,9 +97,17 @@ impl Executor {
             system,
             sandbox,
             input_workaround,
+            providers: Vec::new(),
         })
     }
     
+    /// Add symbols from a provider
+    ///
+    /// Must be called before `initialize`, which registers them.
+    pub fn add_provider(&mut self, provider: Arc<dyn SymbolProvider>) {
+        self.providers.push(provider);
+    }
+    
     /// Initialize the executor
     pub async fn initialize(&self) -> Result<()> {
         // Initialize the sandbox
@@ -83,18 +139,45 @@ impl Executor {
         // Register input workaround symbols
         input_workaround::register_input_symbols(&self.sandbox, Arc::clone(&self.input_workaround))?;
         
+        // Register symbols from providers
+        for provider in &self.providers {
+            provider.register(&mut SymbolRegistrar::new(&self.sandbox, provider.name()))?;
+        }
+        
         Ok(())
     }
     
     /// Execute Anarchy-Inference code in the sandbox
-    pub async fn execute_code(&self, code: &str) -> Result<String> {
+    ///
+    /// Syntax and runtime errors are reported in `ExecutionReport::error`.
+    pub async fn execute_code(&self, code: &str) -> Result<ExecutionReport> {
+        self.execute_with_options(code, &ExecutionOptions::default()).await
+    }
+    
+    /// Execute Anarchy-Inference code in the sandbox with per-run options,
+    /// e.g. a restricted capability profile
+    pub async fn execute_with_options(&self, code: &str, options: &ExecutionOptions) -> Result<ExecutionReport> {
         // 1. Parse the code
-        let parsed = parser::parse(code)?;
+        if code.trim().is_empty() {
+            let error = Error::CodeParsingError("Empty code".to_string()).into();
+            return Ok(ExecutionReport::failed(&error, None));
+        }
         
-        // 2. Execute in sandbox
-        let result = self.sandbox.execute(&parsed)?;
+        let parsed = match parser::parse_spanned(code) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                let span = err.span;
+                return Ok(ExecutionReport::failed(&Error::from(err).into(), Some(span)));
+            }
+        };
         
-        Ok(result)
+        // 2. Execute in sandbox
+        Ok(self.sandbox.execute(&parsed, options).await)
+    }
+    
+    /// Signatures of all symbols available to scripts
+    pub fn symbol_signatures(&self) -> Vec<SymbolSignature> {
+        self.sandbox.symbol_signatures()
     }
     
     /// Shutdown the executor
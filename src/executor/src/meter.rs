@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::interpreter::Scope;
+use crate::value::{Function, Value};
+
+/// Estimated bookkeeping overhead of a shared allocation (reference counts, lock, capacity)
+const SHARED_OVERHEAD: usize = 32;
+
+/// Estimated overhead of one entry in an object or scope map
+const ENTRY_OVERHEAD: usize = 16;
+
+/// Tracks how many bytes a script's values occupy
+///
+/// Allocations are charged as they happen. Charges only ever grow, so once
+/// they add up to more than the limit the interpreter measures what is
+/// actually still reachable and resets the meter to that figure; the script
+/// is aborted only if the measurement is over the limit too. This keeps
+/// garbage (e.g. intermediate strings built in a loop) from counting
+/// against the quota without walking the heap on every allocation.
+pub struct MemoryMeter {
+    /// Quota in bytes, 0 for unlimited
+    limit: usize,
+
+    /// Bytes found reachable by the last measurement
+    live: AtomicUsize,
+
+    /// Bytes charged since the last measurement
+    pending: AtomicUsize,
+}
+
+impl MemoryMeter {
+    /// Create a meter with a quota of `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            live: AtomicUsize::new(0),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// The quota in bytes
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Record an allocation of `bytes`, returning `true` when the charges
+    /// exceed the quota and usage has to be measured
+    pub fn charge(&self, bytes: usize) -> bool {
+        if self.limit == 0 || bytes == 0 {
+            return false;
+        }
+
+        let pending = self.pending.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.live.load(Ordering::Relaxed).saturating_add(pending) > self.limit
+    }
+
+    /// Whether `bytes` more than the last measurement would exceed the quota
+    pub fn would_exceed(&self, bytes: usize) -> bool {
+        self.limit > 0 && self.live.load(Ordering::Relaxed).saturating_add(bytes) > self.limit
+    }
+
+    /// Replace the charges with a measurement of live usage, returning
+    /// whether it is within the quota
+    pub fn settle(&self, live: usize) -> bool {
+        self.live.store(live, Ordering::Relaxed);
+        self.pending.store(0, Ordering::Relaxed);
+        self.limit == 0 || live <= self.limit
+    }
+}
+
+/// Estimated size of the storage a freshly created value owns itself,
+/// not counting the values it contains
+pub fn shallow_size(value: &Value) -> usize {
+    match value {
+        Value::Str(s) => s.len(),
+        Value::Bytes(data) => SHARED_OVERHEAD + data.len(),
+        Value::Array(items) => SHARED_OVERHEAD + items.lock().unwrap().len() * size_of::<Value>(),
+        Value::Object(props) => {
+            let props = props.lock().unwrap();
+            SHARED_OVERHEAD
+                + props
+                    .keys()
+                    .map(|key| key.len() + size_of::<Value>() + ENTRY_OVERHEAD)
+                    .sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+/// Walks values and scopes, adding up the memory they occupy
+///
+/// Shared storage is counted once, however many references to it are found.
+#[derive(Default)]
+pub struct Usage {
+    seen: HashSet<usize>,
+    total: usize,
+}
+
+impl Usage {
+    /// Create an empty measurement
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total bytes found so far
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Add a scope, its bindings and every enclosing scope
+    pub fn add_scope(&mut self, scope: &Arc<Scope>) {
+        let mut current = Some(Arc::clone(scope));
+
+        while let Some(scope) = current {
+            if !self.seen.insert(Arc::as_ptr(&scope) as usize) {
+                break;
+            }
+
+            scope.visit_bindings(|name, value| {
+                self.total += name.len() + size_of::<Value>() + ENTRY_OVERHEAD;
+                self.add_value(value);
+            });
+
+            current = scope.parent().cloned();
+        }
+    }
+
+    /// Add a value and everything reachable from it
+    pub fn add_value(&mut self, value: &Value) {
+        match value {
+            Value::Str(s) => self.total += s.len(),
+            Value::Array(items) => {
+                if !self.seen.insert(Arc::as_ptr(items) as usize) {
+                    return;
+                }
+
+                // Holding the lock while descending is fine: storage is
+                // marked as seen before it is locked, so it is never locked twice
+                let items = items.lock().unwrap();
+                self.total += SHARED_OVERHEAD + items.len() * size_of::<Value>();
+                for item in items.iter() {
+                    self.add_value(item);
+                }
+            }
+            Value::Object(props) => {
+                if !self.seen.insert(Arc::as_ptr(props) as usize) {
+                    return;
+                }
+
+                let props = props.lock().unwrap();
+                self.total += SHARED_OVERHEAD;
+                for (key, value) in props.iter() {
+                    self.total += key.len() + size_of::<Value>() + ENTRY_OVERHEAD;
+                    self.add_value(value);
+                }
+            }
+            Value::Function(Function::Closure(closure)) => {
+                if self.seen.insert(Arc::as_ptr(closure) as usize) {
+                    self.total += SHARED_OVERHEAD;
+                    self.add_scope(&closure.env);
+                }
+            }
+            Value::Regex(re) => {
+                if self.seen.insert(Arc::as_ptr(re) as usize) {
+                    self.total += SHARED_OVERHEAD + re.pattern.len();
+                }
+            }
+            Value::Bytes(data) => {
+                if self.seen.insert(Arc::as_ptr(data) as usize) {
+                    self.total += SHARED_OVERHEAD + data.len();
+                }
+            }
+            Value::Null
+            | Value::Bool(_)
+            | Value::Number(_)
+            | Value::Function(Function::Native(_)) => {}
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+
+use crate::analyzer;
+use crate::diagnostics::codes;
+use crate::error::Error;
+use crate::parser;
+use crate::registry::normalize;
+
+/// Rewrite code written for older versions of the language
+///
+/// `📖` used to read memory entries as well as files; memory is now read
+/// with `📚`. Calls to `📖` that the analyzer reports as reading a key
+/// stored with `📝` (`W0003`) become calls to `📚`. Everything else,
+/// including formatting and comments, is left as it is, and migrating code
+/// twice changes nothing.
+///
+/// Fails with `Error::CodeParsingError` if the code does not parse.
+pub fn migrate_code(code: &str) -> Result<String> {
+    migrate_code_with_keys(code, &[])
+}
+
+/// [`migrate_code`] for code that reads keys stored by earlier runs
+///
+/// `📖` calls that read one of `memory_keys` as a string literal become
+/// calls to `📚` too, even though the code never stores the key with `📝`.
+/// The keys usually come from the memory the code runs against.
+pub fn migrate_code_with_keys(code: &str, memory_keys: &[String]) -> Result<String> {
+    let parsed = parser::parse_spanned(code).map_err(Error::from)?;
+
+    // Symbols only matter for diagnostics the migration does not act on
+    let mut spans: Vec<_> = analyzer::analyze_with_memory_keys(&parsed.program, &[], memory_keys)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.code == codes::MEMORY_READ_AS_FILE)
+        .filter_map(|diagnostic| diagnostic.span)
+        .collect();
+    spans.sort_by_key(|span| span.start);
+
+    let mut migrated = String::with_capacity(code.len());
+    let mut copied = 0;
+    for span in spans {
+        if normalize(&code[span.start..span.end]) == "📖" {
+            migrated.push_str(&code[copied..span.start]);
+            migrated.push('📚');
+            copied = span.end;
+        }
+    }
+    migrated.push_str(&code[copied..]);
+
+    Ok(migrated)
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics;
+use crate::value::Value;
+
+/// Extension of Anarchy-Inference files, added to import paths that lack it
+pub(crate) const EXTENSION: &str = ".a.i";
+
+/// Name under which a module exports a value that is not a plain identifier
+pub(crate) const DEFAULT_EXPORT: &str = "default";
+
+/// Values a module exported with `⟼`, in order
+pub(crate) struct Exports {
+    values: Vec<(String, Value)>,
+}
+
+impl Exports {
+    pub fn new(values: Vec<(String, Value)>) -> Self {
+        Self { values }
+    }
+
+    /// The value `⟰(path)` returns: the first export, `null` if there is none
+    pub fn main(&self) -> Value {
+        self.values
+            .first()
+            .map_or(Value::Null, |(_, value)| value.clone())
+    }
+
+    /// The export `⟰(path, name)` returns
+    pub fn get(&self, module: &Path, name: &str) -> Result<Value> {
+        if let Some((_, value)) = self.values.iter().find(|(export, _)| export == name) {
+            return Ok(value.clone());
+        }
+
+        let names = self.values.iter().map(|(export, _)| export.as_str());
+        let message = format!("Module {} does not export '{}'", display(module), name);
+        match diagnostics::suggestions(name, names).first() {
+            Some(suggestion) => Err(anyhow::anyhow!(
+                "{}, did you mean '{}'?",
+                message,
+                suggestion
+            )),
+            None => Err(anyhow::anyhow!(message)),
+        }
+    }
+}
+
+/// Loading state of an imported module
+pub(crate) enum ModuleState {
+    /// The module is running, so importing it again is a cycle
+    Loading,
+
+    Loaded(std::sync::Arc<Exports>),
+}
+
+/// Files an import may refer to, the first existing one wins
+///
+/// `./` and `../` paths are relative to the importing file only. Other
+/// relative paths are looked up next to the importing file first and then in
+/// each directory of the library path.
+pub(crate) fn candidates(
+    spec: &str,
+    importer_dir: Option<&Path>,
+    library_path: &[PathBuf],
+) -> Vec<PathBuf> {
+    let file = if spec.ends_with(EXTENSION) {
+        PathBuf::from(spec)
+    } else {
+        PathBuf::from(format!("{}{}", spec, EXTENSION))
+    };
+
+    let local = match importer_dir {
+        Some(dir) => dir.join(&file),
+        None => file.clone(),
+    };
+    if file.is_absolute() || spec.starts_with("./") || spec.starts_with("../") {
+        vec![local]
+    } else {
+        std::iter::once(local)
+            .chain(library_path.iter().map(|dir| dir.join(&file)))
+            .collect()
+    }
+}
+
+/// The error for an import none of whose `candidates` exists
+pub(crate) fn not_found(spec: &str, candidates: &[PathBuf]) -> anyhow::Error {
+    let searched: Vec<String> = candidates.iter().map(|path| display(path)).collect();
+    anyhow::anyhow!(
+        "Module '{}' not found (searched {})",
+        spec,
+        searched.join(", ")
+    )
+}
+
+/// A module path for messages, relative to the current directory if possible
+pub(crate) fn display(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::debugger::{Debugger, SharedDebugger};
+use crate::registry::CapabilityProfile;
+use crate::trace::Trace;
+
+/// How a script is executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Evaluate the syntax tree directly
+    #[default]
+    TreeWalker,
+
+    /// Compile the syntax tree to bytecode and run it on a stack VM, which
+    /// is faster for long loops; scripts behave the same on both engines
+    Bytecode,
+}
+
+/// Per-run settings for executing a script
+///
+/// Unlike [`Config`](crate::Config), which is fixed when the executor is
+/// created, options can differ for each script, e.g. per task.
+#[derive(Clone, Default)]
+pub struct ExecutionOptions {
+    /// Capabilities the script may use, `None` for everything the
+    /// configuration allows
+    ///
+    /// The profile can only narrow the configuration: a capability disabled
+    /// in `Config` stays disabled whatever the profile says.
+    pub capabilities: Option<CapabilityProfile>,
+
+    /// Record the side effects the script would have instead of performing
+    /// them; the report lists them in `ExecutionReport::effects`
+    ///
+    /// Browser symbols that only read the page are stubbed as well, since
+    /// the page is never opened.
+    pub dry_run: bool,
+
+    /// Also replace symbols without side effects by stubs during a dry run,
+    /// so the script does not touch the real system at all
+    pub stub_reads: bool,
+
+    /// Debugger to pause the script at breakpoints and steps
+    ///
+    /// The execution time limit does not apply while a debugger is attached.
+    pub debugger: Option<SharedDebugger>,
+
+    /// Record every symbol call and its result in `ExecutionReport::trace`
+    pub record: bool,
+
+    /// Answer symbol calls from a recorded trace instead of running them
+    ///
+    /// The run fails with `Error::ReplayDivergence` as soon as the script
+    /// makes a call other than the next one in the trace.
+    pub replay: Option<Trace>,
+
+    /// Directory relative paths in the script, such as `🔠` dictionaries,
+    /// are resolved against; usually the directory of the script file
+    pub script_dir: Option<PathBuf>,
+
+    /// Directories searched for modules imported with `⟰`, after the
+    /// directory of the importing file
+    pub library_path: Vec<PathBuf>,
+
+    /// Engine running the script
+    pub engine: Engine,
+}
+
+impl fmt::Debug for ExecutionOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutionOptions")
+            .field("capabilities", &self.capabilities)
+            .field("dry_run", &self.dry_run)
+            .field("stub_reads", &self.stub_reads)
+            .field("debugger", &self.debugger.is_some())
+            .field("record", &self.record)
+            .field("replay", &self.replay.is_some())
+            .field("script_dir", &self.script_dir)
+            .field("library_path", &self.library_path)
+            .field("engine", &self.engine)
+            .finish()
+    }
+}
+
+impl ExecutionOptions {
+    /// Options with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict the script to the capabilities in `profile`
+    pub fn capabilities(mut self, profile: CapabilityProfile) -> Self {
+        self.capabilities = Some(profile);
+        self
+    }
+
+    /// Record side effects instead of performing them
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// During a dry run, stub out every symbol, not only those with side effects
+    pub fn stub_reads(mut self) -> Self {
+        self.stub_reads = true;
+        self
+    }
+
+    /// Run the script under `debugger`, pausing before the first statement
+    pub fn debugger(mut self, debugger: impl Debugger + 'static) -> Self {
+        self.debugger = Some(Arc::new(Mutex::new(debugger)));
+        self
+    }
+
+    /// Record the symbol calls of the run in a trace
+    pub fn record(mut self) -> Self {
+        self.record = true;
+        self
+    }
+
+    /// Replay the symbol calls recorded in `trace`
+    pub fn replay(mut self, trace: Trace) -> Self {
+        self.replay = Some(trace);
+        self
+    }
+
+    /// Resolve relative paths in the script against `dir`
+    pub fn script_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.script_dir = Some(dir.into());
+        self
+    }
+
+    /// Add a directory to search for imported modules
+    pub fn library_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.library_path.push(dir.into());
+        self
+    }
+
+    /// Run the script on `engine`
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+}
@@ -1,51 +1,1417 @@
 use anyhow::Result;
-use crate::config::Config;
+use std::sync::Arc;
+
+use crate::ast::*;
 use crate::error::Error;
+use crate::lexer::{Lexer, TemplateSegment, Token, TokenKind};
+
+/// A parse error with the location where it occurred
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::CodeParsingError(format!(
+            "{} at line {}, column {}",
+            err.message, err.span.line, err.span.column
+        ))
+    }
+}
+
+type ParseResult<T> = std::result::Result<T, ParseError>;
+
+/// How deeply statements and expressions may nest
+///
+/// Parsing, formatting, analysis and execution all recurse over the syntax
+/// tree, so deeper code would overflow the stack instead of failing.
+pub const MAX_NESTING: usize = 32;
 
-/// Parser for Anarchy-Inference code
+/// How many else-if branches may be open at once
+///
+/// An else-if is an if statement nested in the else branch of another, so
+/// long chains nest as deeply as blocks do, only more cheaply.
+pub const MAX_ELSE_IFS: usize = 128;
+
+/// Recursive-descent parser for Anarchy-Inference code
 pub struct Parser {
-    config: Config,
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    pos: usize,
+
+    /// The whole code, for the text of `!(...)` commands without quotes
+    source: Arc<str>,
+
+    /// Number of enclosing function bodies, used to tell `⟼` returns from exports
+    function_depth: usize,
+
+    /// Number of statements and operands being parsed, limited to `MAX_NESTING`
+    nesting: usize,
+
+    /// Number of else-if branches being parsed, limited to `MAX_ELSE_IFS`
+    else_ifs: usize,
 }
 
 impl Parser {
-    /// Create a new Parser instance
-    pub fn new(config: Config) -> Self {
-        Self { config }
+    /// Create a new Parser for the given source code
+    pub fn new(code: &str) -> ParseResult<Self> {
+        Self::from_lexer(Lexer::new(code), Arc::from(code))
+    }
+
+    fn from_lexer(lexer: Lexer<'_>, source: Arc<str>) -> ParseResult<Self> {
+        let (tokens, comments) = lexer.tokenize().map_err(|err| ParseError {
+            message: err.message,
+            span: err.span,
+        })?;
+
+        Ok(Self {
+            tokens,
+            comments,
+            pos: 0,
+            source,
+            function_depth: 0,
+            nesting: 0,
+            else_ifs: 0,
+        })
     }
-    
+
     /// Parse Anarchy-Inference code into an abstract syntax tree
-    pub fn parse(&self, code: &str) -> Result<ParsedCode> {
-        // In a real implementation, this would parse the code into an AST
-        // For now, just do some basic validation and return the code as-is
-        
-        if code.trim().is_empty() {
-            return Err(Error::ParserError("Empty code".to_string()).into());
-        }
-        
-        // Check for basic syntax elements
-        if !code.contains("ƒ") && !code.contains("λ") {
-            return Err(Error::ParserError(
-                "Code does not contain any function or library definitions".to_string()
-            ).into());
-        }
-        
-        Ok(ParsedCode {
-            raw_code: code.to_string(),
-            // In a real implementation, this would contain the AST
+    pub fn parse_program(&mut self) -> ParseResult<Program> {
+        let start = self.peek().span;
+        let mut body = Vec::new();
+
+        while !self.check(&TokenKind::Eof) {
+            body.push(self.statement()?);
+        }
+
+        let span = start.to(self.peek().span);
+        Ok(Program {
+            body,
+            span,
+            comments: std::mem::take(&mut self.comments),
+        })
+    }
+
+    // ----- token helpers -----
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek_kind(&self) -> &TokenKind {
+        &self.peek().kind
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.pos.saturating_sub(1)]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if !matches!(token.kind, TokenKind::Eof) {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        std::mem::discriminant(self.peek_kind()) == std::mem::discriminant(kind)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.check(kind) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind, what: &str) -> ParseResult<Token> {
+        if self.check(kind) {
+            Ok(self.advance())
+        } else {
+            Err(self.error_here(format!(
+                "Expected {}, found {}",
+                what,
+                describe(self.peek_kind())
+            )))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> ParseResult<Ident> {
+        match self.peek_kind().clone() {
+            TokenKind::Ident(name) => {
+                let span = self.advance().span;
+                Ok(Ident { name, span })
+            }
+            other => Err(self.error_here(format!("Expected {}, found {}", what, describe(&other)))),
+        }
+    }
+
+    fn error_here(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            span: self.peek().span,
+        }
+    }
+
+    /// Run `parse` one nesting level deeper, failing past `MAX_NESTING`
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error_here(format!(
+                "Code is nested more than {} levels deep",
+                MAX_NESTING
+            )));
+        }
+
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    /// Consume an optional statement terminator
+    fn end_statement(&mut self) {
+        self.eat(&TokenKind::Semicolon);
+    }
+
+    // ----- statements -----
+
+    fn statement(&mut self) -> ParseResult<Stmt> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> ParseResult<Stmt> {
+        let start = self.peek().span;
+
+        match self.peek_kind() {
+            TokenKind::Semicolon => {
+                self.advance();
+                Ok(Stmt::new(
+                    StmtKind::Block(Block {
+                        stmts: Vec::new(),
+                        span: start,
+                    }),
+                    start,
+                ))
+            }
+            TokenKind::Let => self.let_statement(),
+            TokenKind::Function if matches!(self.peek_nth(1).kind, TokenKind::Ident(_)) => {
+                self.advance();
+                let decl = self.function_rest(start, true)?;
+                let span = decl.span;
+                Ok(Stmt::new(StmtKind::Function(Arc::new(decl)), span))
+            }
+            TokenKind::Print => self.print_statement(),
+            TokenKind::If => self.if_statement(),
+            TokenKind::While if self.peek_nth(1).kind == TokenKind::Semicolon => {
+                // `↻;` continues the enclosing loop
+                self.advance();
+                self.advance();
+                Ok(Stmt::new(StmtKind::Continue, start))
+            }
+            TokenKind::While => self.while_statement(),
+            TokenKind::ForEach => self.foreach_statement(),
+            TokenKind::Return => self.return_statement(),
+            TokenKind::Export => self.export_statement(),
+            TokenKind::Break => {
+                self.advance();
+                self.end_statement();
+                Ok(Stmt::new(StmtKind::Break, start))
+            }
+            TokenKind::Continue => {
+                self.advance();
+                self.end_statement();
+                Ok(Stmt::new(StmtKind::Continue, start))
+            }
+            TokenKind::Try | TokenKind::TryBlock => self.try_statement(),
+            TokenKind::LBrace => {
+                let block = self.block()?;
+                let span = block.span;
+                Ok(Stmt::new(StmtKind::Block(block), span))
+            }
+            TokenKind::Bang if self.peek_nth(1).kind == TokenKind::LParen => {
+                // A statement-level `!(...)` is always a shell invocation
+                let expr = self.symbol_call("!".to_string())?;
+                let expr = self.postfix_rest(expr)?;
+                let expr = self.assignment_rest(expr)?;
+                self.end_statement();
+                let span = expr.span;
+                Ok(Stmt::new(StmtKind::Expr(expr), span))
+            }
+            _ => {
+                let expr = self.expression()?;
+                self.end_statement();
+                let span = expr.span;
+                Ok(Stmt::new(StmtKind::Expr(expr), span))
+            }
+        }
+    }
+
+    fn let_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        let name = self.expect_ident("variable name after 'ι'")?;
+
+        let init = if self.eat(&TokenKind::Assign) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        let end = init.as_ref().map(|e| e.span).unwrap_or(name.span);
+        self.end_statement();
+        Ok(Stmt::new(StmtKind::Let { name, init }, start.to(end)))
+    }
+
+    fn print_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        self.expect(&TokenKind::LParen, "'(' after '⌽'")?;
+        let args = self.arguments()?;
+        let end = self.previous().span;
+        self.end_statement();
+        Ok(Stmt::new(StmtKind::Print(args), start.to(end)))
+    }
+
+    fn if_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        self.expect(&TokenKind::LParen, "'(' after 'if'")?;
+        let cond = self.expression()?;
+        self.expect(&TokenKind::RParen, "')' after condition")?;
+        let then_branch = self.block()?;
+        let mut end = then_branch.span;
+
+        let else_branch = if self.eat(&TokenKind::Else) {
+            let stmt = if self.check(&TokenKind::If) {
+                self.else_if()?
+            } else {
+                let block = self.block()?;
+                let span = block.span;
+                Stmt::new(StmtKind::Block(block), span)
+            };
+            end = stmt.span;
+            Some(Box::new(stmt))
+        } else if self.check(&TokenKind::If) && !self.peek().newline_before {
+            // `↪(a) { ... } ↪(b) { ... }` on the same line chains like `else if`
+            let stmt = self.if_statement()?;
+            end = stmt.span;
+            Some(Box::new(stmt))
+        } else {
+            None
+        };
+
+        Ok(Stmt::new(
+            StmtKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            },
+            start.to(end),
+        ))
+    }
+
+    fn else_if(&mut self) -> ParseResult<Stmt> {
+        if self.else_ifs == MAX_ELSE_IFS {
+            return Err(self.error_here(format!(
+                "If statement has more than {} else-if branches",
+                MAX_ELSE_IFS
+            )));
+        }
+
+        self.else_ifs += 1;
+        let result = self.if_statement();
+        self.else_ifs -= 1;
+        result
+    }
+
+    fn while_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        self.expect(&TokenKind::LParen, "'(' after 'while'")?;
+        let cond = self.expression()?;
+        self.expect(&TokenKind::RParen, "')' after condition")?;
+        let body = self.block()?;
+        let span = start.to(body.span);
+        Ok(Stmt::new(StmtKind::While { cond, body }, span))
+    }
+
+    fn foreach_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        self.expect(&TokenKind::LParen, "'(' after '∀'")?;
+        let iterable = self.expression()?;
+        self.expect(&TokenKind::Comma, "',' after the collection in '∀'")?;
+
+        let body = if self.check(&TokenKind::Lambda) {
+            self.advance();
+            let params = self.lambda_params()?;
+            self.function_depth += 1;
+            let body = self.block();
+            self.function_depth -= 1;
+            ForEachBody::Inline {
+                params,
+                body: body?,
+            }
+        } else {
+            ForEachBody::Callback(self.expression()?)
+        };
+
+        self.expect(&TokenKind::RParen, "')' after '∀' body")?;
+        let end = self.previous().span;
+        self.end_statement();
+        Ok(Stmt::new(
+            StmtKind::ForEach { iterable, body },
+            start.to(end),
+        ))
+    }
+
+    fn return_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+
+        let value = if self.check(&TokenKind::Semicolon)
+            || self.check(&TokenKind::RBrace)
+            || self.check(&TokenKind::Eof)
+            || self.peek().newline_before
+        {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        let end = value.as_ref().map(|e| e.span).unwrap_or(start);
+        self.end_statement();
+        Ok(Stmt::new(StmtKind::Return(value), start.to(end)))
+    }
+
+    fn export_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        let value = self.expression()?;
+        let span = start.to(value.span);
+        self.end_statement();
+
+        if self.function_depth > 0 {
+            Ok(Stmt::new(StmtKind::Return(Some(value)), span))
+        } else {
+            Ok(Stmt::new(StmtKind::Export(value), span))
+        }
+    }
+
+    fn try_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance().span;
+        let is_retry_block = self.previous().kind == TokenKind::TryBlock;
+        let body = self.block()?;
+
+        if is_retry_block && !self.check(&TokenKind::Catch) {
+            // A `↺ { ... }` without a `⚠` handler loops until `↵`
+            let span = start.to(body.span);
+            let cond = Expr::new(ExprKind::Bool(true), start);
+            return Ok(Stmt::new(StmtKind::While { cond, body }, span));
+        }
+
+        let param = if self.eat(&TokenKind::Catch) && self.eat(&TokenKind::LParen) {
+            let ident = self.expect_ident("error binding name")?;
+            self.expect(&TokenKind::RParen, "')' after error binding")?;
+            Some(ident)
+        } else {
+            None
+        };
+
+        let handler = self.block()?;
+        let span = start.to(handler.span);
+        Ok(Stmt::new(
+            StmtKind::Try {
+                body,
+                param,
+                handler,
+            },
+            span,
+        ))
+    }
+
+    fn block(&mut self) -> ParseResult<Block> {
+        let start = self.expect(&TokenKind::LBrace, "'{'")?.span;
+        let mut stmts = Vec::new();
+
+        while !self.check(&TokenKind::RBrace) {
+            if self.check(&TokenKind::Eof) {
+                return Err(ParseError {
+                    message: "Unclosed '{'".to_string(),
+                    span: start,
+                });
+            }
+            stmts.push(self.statement()?);
+        }
+
+        let end = self.advance().span;
+        Ok(Block {
+            stmts,
+            span: start.to(end),
+        })
+    }
+
+    // ----- functions -----
+
+    /// Parse the rest of a function after `ƒ`
+    fn function_rest(&mut self, start: Span, named: bool) -> ParseResult<FunctionDecl> {
+        let name = if named || matches!(self.peek_kind(), TokenKind::Ident(_)) {
+            Some(self.expect_ident("function name after 'ƒ'")?)
+        } else {
+            None
+        };
+
+        self.expect(&TokenKind::LParen, "'(' after function name")?;
+        let params = self.param_list()?;
+
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        let body = body?;
+
+        let span = start.to(body.span);
+        Ok(FunctionDecl {
+            name,
+            params,
+            body,
+            span,
+        })
+    }
+
+    /// Parse a parenthesized parameter list, the opening paren already consumed
+    fn param_list(&mut self) -> ParseResult<Vec<Param>> {
+        let mut params = Vec::new();
+
+        while !self.check(&TokenKind::RParen) {
+            params.push(self.param()?);
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&TokenKind::RParen, "')' after parameters")?;
+        Ok(params)
+    }
+
+    fn param(&mut self) -> ParseResult<Param> {
+        let rest = self.eat(&TokenKind::Ellipsis);
+        let sigil = match self.peek_kind() {
+            TokenKind::Sigil(c) => {
+                let c = *c;
+                self.advance();
+                Some(c)
+            }
+            _ => None,
+        };
+
+        let name = self.expect_ident("parameter name")?;
+        let default = if self.eat(&TokenKind::Assign) {
+            Some(self.conditional()?)
+        } else {
+            None
+        };
+
+        Ok(Param {
+            name,
+            sigil,
+            default,
+            rest,
+        })
+    }
+
+    /// Parse lambda parameters: `λx`, `λ(x, y)` or `λ` with no parameters
+    fn lambda_params(&mut self) -> ParseResult<Vec<Param>> {
+        if self.eat(&TokenKind::LParen) {
+            self.param_list()
+        } else if matches!(self.peek_kind(), TokenKind::Ident(_) | TokenKind::Sigil(_)) {
+            let sigil = match self.peek_kind() {
+                TokenKind::Sigil(c) => {
+                    let c = *c;
+                    self.advance();
+                    Some(c)
+                }
+                _ => None,
+            };
+            let name = self.expect_ident("parameter name")?;
+            Ok(vec![Param {
+                name,
+                sigil,
+                default: None,
+                rest: false,
+            }])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn lambda(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span;
+        let params = self.lambda_params()?;
+
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        let body = body?;
+
+        let span = start.to(body.span);
+        let decl = FunctionDecl {
+            name: None,
+            params,
+            body,
+            span,
+        };
+        Ok(Expr::new(ExprKind::Lambda(Arc::new(decl)), span))
+    }
+
+    // ----- expressions -----
+
+    fn expression(&mut self) -> ParseResult<Expr> {
+        let expr = self.conditional()?;
+        self.assignment_rest(expr)
+    }
+
+    fn assignment_rest(&mut self, target: Expr) -> ParseResult<Expr> {
+        let op = match self.peek_kind() {
+            TokenKind::Assign => AssignOp::Assign,
+            TokenKind::PlusAssign => AssignOp::Add,
+            TokenKind::MinusAssign => AssignOp::Sub,
+            TokenKind::StarAssign => AssignOp::Mul,
+            TokenKind::SlashAssign => AssignOp::Div,
+            _ => return Ok(target),
+        };
+
+        if !is_assignable(&target) {
+            return Err(ParseError {
+                message: "Invalid assignment target".to_string(),
+                span: target.span,
+            });
+        }
+
+        self.advance();
+        let value = self.expression()?;
+        let span = target.span.to(value.span);
+        Ok(Expr::new(
+            ExprKind::Assign {
+                op,
+                target: Box::new(target),
+                value: Box::new(value),
+            },
+            span,
+        ))
+    }
+
+    fn conditional(&mut self) -> ParseResult<Expr> {
+        let cond = self.logical_or()?;
+
+        if !self.eat(&TokenKind::Question) {
+            return Ok(cond);
+        }
+
+        let then_branch = self.branch_expression()?;
+        self.expect(&TokenKind::Colon, "':' in conditional expression")?;
+        let else_branch = self.branch_expression()?;
+        let span = cond.span.to(else_branch.span);
+
+        Ok(Expr::new(
+            ExprKind::Conditional {
+                cond: Box::new(cond),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            },
+            span,
+        ))
+    }
+
+    /// A branch of a conditional expression, which may be a statement block
+    fn branch_expression(&mut self) -> ParseResult<Expr> {
+        if self.check(&TokenKind::LBrace) && !self.looks_like_object() {
+            let block = self.block()?;
+            let span = block.span;
+            return Ok(Expr::new(ExprKind::Block(block), span));
+        }
+
+        let expr = self.conditional()?;
+        self.assignment_rest(expr)
+    }
+
+    fn binary_level(
+        &mut self,
+        next: fn(&mut Self) -> ParseResult<Expr>,
+        ops: &[(TokenKind, BinaryOp)],
+    ) -> ParseResult<Expr> {
+        let mut left = next(self)?;
+
+        'outer: loop {
+            for (kind, op) in ops {
+                if self.check(kind) {
+                    self.advance();
+                    let right = next(self)?;
+                    let span = left.span.to(right.span);
+                    left = Expr::new(
+                        ExprKind::Binary {
+                            op: *op,
+                            left: Box::new(left),
+                            right: Box::new(right),
+                        },
+                        span,
+                    );
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn logical_level(
+        &mut self,
+        next: fn(&mut Self) -> ParseResult<Expr>,
+        kind: TokenKind,
+        op: LogicalOp,
+    ) -> ParseResult<Expr> {
+        let mut left = next(self)?;
+
+        while self.eat(&kind) {
+            let right = next(self)?;
+            let span = left.span.to(right.span);
+            left = Expr::new(
+                ExprKind::Logical {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span,
+            );
+        }
+
+        Ok(left)
+    }
+
+    fn logical_or(&mut self) -> ParseResult<Expr> {
+        self.logical_level(Self::nullish, TokenKind::OrOr, LogicalOp::Or)
+    }
+
+    fn nullish(&mut self) -> ParseResult<Expr> {
+        self.logical_level(
+            Self::logical_and,
+            TokenKind::QuestionQuestion,
+            LogicalOp::Nullish,
+        )
+    }
+
+    fn logical_and(&mut self) -> ParseResult<Expr> {
+        self.logical_level(Self::equality, TokenKind::AndAnd, LogicalOp::And)
+    }
+
+    fn equality(&mut self) -> ParseResult<Expr> {
+        self.binary_level(
+            Self::comparison,
+            &[
+                (TokenKind::EqEqEq, BinaryOp::StrictEq),
+                (TokenKind::BangEqEq, BinaryOp::StrictNotEq),
+                (TokenKind::EqEq, BinaryOp::Eq),
+                (TokenKind::BangEq, BinaryOp::NotEq),
+            ],
+        )
+    }
+
+    fn comparison(&mut self) -> ParseResult<Expr> {
+        self.binary_level(
+            Self::additive,
+            &[
+                (TokenKind::LtEq, BinaryOp::LtEq),
+                (TokenKind::GtEq, BinaryOp::GtEq),
+                (TokenKind::Lt, BinaryOp::Lt),
+                (TokenKind::Gt, BinaryOp::Gt),
+            ],
+        )
+    }
+
+    fn additive(&mut self) -> ParseResult<Expr> {
+        self.binary_level(
+            Self::multiplicative,
+            &[
+                (TokenKind::Plus, BinaryOp::Add),
+                (TokenKind::Minus, BinaryOp::Sub),
+            ],
+        )
+    }
+
+    fn multiplicative(&mut self) -> ParseResult<Expr> {
+        self.binary_level(
+            Self::unary,
+            &[
+                (TokenKind::Star, BinaryOp::Mul),
+                (TokenKind::Slash, BinaryOp::Div),
+                (TokenKind::Percent, BinaryOp::Rem),
+            ],
+        )
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        self.nested(Self::unary_inner)
+    }
+
+    fn unary_inner(&mut self) -> ParseResult<Expr> {
+        let start = self.peek().span;
+
+        let op = match self.peek_kind() {
+            TokenKind::Bang if self.is_shell_call() => return self.postfix(),
+            TokenKind::Bang => UnaryOp::Not,
+            TokenKind::Minus => UnaryOp::Neg,
+            TokenKind::Plus => UnaryOp::Plus,
+            TokenKind::Typeof => UnaryOp::Typeof,
+            TokenKind::PlusPlus | TokenKind::MinusMinus => {
+                let op = if self.check(&TokenKind::PlusPlus) {
+                    UpdateOp::Increment
+                } else {
+                    UpdateOp::Decrement
+                };
+                self.advance();
+                let target = self.unary()?;
+                if !is_assignable(&target) {
+                    return Err(ParseError {
+                        message: "Invalid increment/decrement target".to_string(),
+                        span: target.span,
+                    });
+                }
+                let span = start.to(target.span);
+                return Ok(Expr::new(
+                    ExprKind::Update {
+                        op,
+                        prefix: true,
+                        target: Box::new(target),
+                    },
+                    span,
+                ));
+            }
+            _ => return self.postfix(),
+        };
+
+        self.advance();
+        let operand = self.unary()?;
+        let span = start.to(operand.span);
+        Ok(Expr::new(
+            ExprKind::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+            span,
+        ))
+    }
+
+    /// `!(...)` whose first argument is a string is a shell call, not a negation
+    fn is_shell_call(&self) -> bool {
+        self.peek_nth(1).kind == TokenKind::LParen
+            && matches!(
+                self.peek_nth(2).kind,
+                TokenKind::Str(_) | TokenKind::Template(_)
+            )
+            || self.is_unquoted_command()
+    }
+
+    /// Whether the parser is at a shell command written without quotes, like
+    /// `!(exit 1)`: a word followed by another word on the same line, which
+    /// no expression starts with
+    fn is_unquoted_command(&self) -> bool {
+        let next = self.peek_nth(3);
+        self.peek_nth(1).kind == TokenKind::LParen
+            && matches!(self.peek_nth(2).kind, TokenKind::Ident(_))
+            && matches!(
+                next.kind,
+                TokenKind::Ident(_) | TokenKind::Number(_) | TokenKind::Str(_)
+            )
+            && !next.newline_before
+    }
+
+    /// Parse `!(command)` without quotes as the call `!("command")`
+    fn unquoted_command(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span;
+        let open = self.advance().span;
+        let first = self.peek().span;
+        let mut depth = 0usize;
+
+        loop {
+            let token = self.advance();
+            match token.kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen if depth > 0 => depth -= 1,
+                TokenKind::RParen => {
+                    let text = self.source[open.end..token.span.start].trim();
+                    let command = Expr::new(
+                        ExprKind::Str(text.to_string()),
+                        first.to(self.tokens[self.pos - 2].span),
+                    );
+                    return Ok(Expr::new(
+                        ExprKind::SymbolCall {
+                            symbol: "!".to_string(),
+                            args: vec![command],
+                        },
+                        start.to(token.span),
+                    ));
+                }
+                TokenKind::Eof => {
+                    return Err(ParseError {
+                        message: "Expected ')' after command".to_string(),
+                        span: token.span,
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn postfix(&mut self) -> ParseResult<Expr> {
+        let expr = self.primary()?;
+        self.postfix_rest(expr)
+    }
+
+    fn postfix_rest(&mut self, mut expr: Expr) -> ParseResult<Expr> {
+        loop {
+            match self.peek_kind() {
+                TokenKind::LParen => {
+                    self.advance();
+                    let args = self.arguments()?;
+                    let span = expr.span.to(self.previous().span);
+                    expr = Expr::new(
+                        ExprKind::Call {
+                            callee: Box::new(expr),
+                            args,
+                            optional: false,
+                        },
+                        span,
+                    );
+                }
+                TokenKind::Dot => {
+                    self.advance();
+                    let property = self.property_name()?;
+                    let span = expr.span.to(property.span);
+                    expr = Expr::new(
+                        ExprKind::Member {
+                            object: Box::new(expr),
+                            property,
+                            optional: false,
+                        },
+                        span,
+                    );
+                }
+                TokenKind::QuestionDot => {
+                    self.advance();
+                    if self.eat(&TokenKind::LParen) {
+                        let args = self.arguments()?;
+                        let span = expr.span.to(self.previous().span);
+                        expr = Expr::new(
+                            ExprKind::Call {
+                                callee: Box::new(expr),
+                                args,
+                                optional: true,
+                            },
+                            span,
+                        );
+                    } else if self.eat(&TokenKind::LBracket) {
+                        let index = self.expression()?;
+                        self.expect(&TokenKind::RBracket, "']' after index")?;
+                        let span = expr.span.to(self.previous().span);
+                        expr = Expr::new(
+                            ExprKind::Index {
+                                object: Box::new(expr),
+                                index: Box::new(index),
+                                optional: true,
+                            },
+                            span,
+                        );
+                    } else {
+                        let property = self.property_name()?;
+                        let span = expr.span.to(property.span);
+                        expr = Expr::new(
+                            ExprKind::Member {
+                                object: Box::new(expr),
+                                property,
+                                optional: true,
+                            },
+                            span,
+                        );
+                    }
+                }
+                TokenKind::LBracket => {
+                    self.advance();
+                    let index = self.expression()?;
+                    self.expect(&TokenKind::RBracket, "']' after index")?;
+                    let span = expr.span.to(self.previous().span);
+                    expr = Expr::new(
+                        ExprKind::Index {
+                            object: Box::new(expr),
+                            index: Box::new(index),
+                            optional: false,
+                        },
+                        span,
+                    );
+                }
+                TokenKind::PlusPlus | TokenKind::MinusMinus
+                    if !self.peek().newline_before && is_assignable(&expr) =>
+                {
+                    let op = if self.check(&TokenKind::PlusPlus) {
+                        UpdateOp::Increment
+                    } else {
+                        UpdateOp::Decrement
+                    };
+                    let end = self.advance().span;
+                    let span = expr.span.to(end);
+                    expr = Expr::new(
+                        ExprKind::Update {
+                            op,
+                            prefix: false,
+                            target: Box::new(expr),
+                        },
+                        span,
+                    );
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    /// Property names after `.` may be identifiers or keywords
+    fn property_name(&mut self) -> ParseResult<Ident> {
+        let token = self.advance();
+        let name = match &token.kind {
+            TokenKind::Ident(name) => name.clone(),
+            TokenKind::If => "if".to_string(),
+            TokenKind::Else => "else".to_string(),
+            TokenKind::While => "while".to_string(),
+            TokenKind::Break => "break".to_string(),
+            TokenKind::Continue => "continue".to_string(),
+            TokenKind::Return => "return".to_string(),
+            TokenKind::True => "true".to_string(),
+            TokenKind::False => "false".to_string(),
+            TokenKind::Null => "null".to_string(),
+            TokenKind::New => "new".to_string(),
+            TokenKind::Typeof => "typeof".to_string(),
+            other => {
+                return Err(ParseError {
+                    message: format!(
+                        "Expected property name after '.', found {}",
+                        describe(other)
+                    ),
+                    span: token.span,
+                })
+            }
+        };
+
+        Ok(Ident {
+            name,
+            span: token.span,
         })
     }
+
+    /// Parse call arguments, the opening paren already consumed
+    fn arguments(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut args = Vec::new();
+
+        while !self.check(&TokenKind::RParen) {
+            args.push(self.element()?);
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+
+        self.expect(&TokenKind::RParen, "')' after arguments")?;
+        Ok(args)
+    }
+
+    /// An argument or array element, which may be spread with `...`
+    fn element(&mut self) -> ParseResult<Expr> {
+        if self.check(&TokenKind::Ellipsis) {
+            let start = self.advance().span;
+            let inner = self.expression()?;
+            let span = start.to(inner.span);
+            return Ok(Expr::new(ExprKind::Spread(Box::new(inner)), span));
+        }
+
+        self.expression()
+    }
+
+    /// Whether the parser is at a `:key` string reference, a colon directly
+    /// followed by an identifier
+    fn is_string_ref(&self) -> bool {
+        let key = self.peek_nth(1);
+        matches!(key.kind, TokenKind::Ident(_)) && key.span.start == self.peek().span.end
+    }
+
+    fn symbol_call(&mut self, symbol: String) -> ParseResult<Expr> {
+        if symbol == "!" && self.is_unquoted_command() {
+            return self.unquoted_command();
+        }
+
+        let start = self.advance().span;
+        self.expect(
+            &TokenKind::LParen,
+            &format!("'(' after symbol '{}'", symbol),
+        )?;
+        let args = self.arguments()?;
+        let span = start.to(self.previous().span);
+        Ok(Expr::new(ExprKind::SymbolCall { symbol, args }, span))
+    }
+
+    fn primary(&mut self) -> ParseResult<Expr> {
+        let token = self.peek().clone();
+        let span = token.span;
+
+        match token.kind {
+            TokenKind::Number(n) => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Number(n), span))
+            }
+            TokenKind::Str(s) => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Str(s), span))
+            }
+            TokenKind::True => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Bool(true), span))
+            }
+            TokenKind::False => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Bool(false), span))
+            }
+            TokenKind::Null => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Null, span))
+            }
+            TokenKind::Template(segments) => {
+                self.advance();
+                let parts = self.template_parts(segments)?;
+                Ok(Expr::new(ExprKind::Template(parts), span))
+            }
+            TokenKind::Regex { pattern, flags } => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Regex { pattern, flags }, span))
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                Ok(Expr::new(ExprKind::Ident(name), span))
+            }
+            TokenKind::Colon if self.is_string_ref() => {
+                self.advance();
+                let key = self.advance();
+                let TokenKind::Ident(name) = key.kind else {
+                    unreachable!("is_string_ref checked for an identifier");
+                };
+                Ok(Expr::new(ExprKind::StringRef(name), span.to(key.span)))
+            }
+            TokenKind::Symbol(symbol) => self.symbol_call(symbol),
+            TokenKind::Question if self.peek_nth(1).kind == TokenKind::LParen => {
+                self.symbol_call("?".to_string())
+            }
+            TokenKind::Tilde if self.peek_nth(1).kind == TokenKind::LParen => {
+                self.symbol_call("~".to_string())
+            }
+            TokenKind::Bang if self.peek_nth(1).kind == TokenKind::LParen => {
+                self.symbol_call("!".to_string())
+            }
+            TokenKind::Lambda => self.lambda(),
+            TokenKind::Function => {
+                self.advance();
+                let decl = self.function_rest(span, false)?;
+                let span = decl.span;
+                Ok(Expr::new(ExprKind::Lambda(Arc::new(decl)), span))
+            }
+            TokenKind::LParen => {
+                self.advance();
+                let expr = self.expression()?;
+                self.expect(&TokenKind::RParen, "')' after expression")?;
+                let span = span.to(self.previous().span);
+                Ok(Expr::new(expr.kind, span))
+            }
+            TokenKind::LBracket => self.array_literal(),
+            TokenKind::LBrace => {
+                if self.looks_like_object() {
+                    self.object_literal()
+                } else {
+                    let block = self.block()?;
+                    let span = block.span;
+                    Ok(Expr::new(ExprKind::Block(block), span))
+                }
+            }
+            TokenKind::New => {
+                self.advance();
+                let callee = self.primary()?;
+                let callee = self.member_chain(callee)?;
+                let args = if self.eat(&TokenKind::LParen) {
+                    self.arguments()?
+                } else {
+                    Vec::new()
+                };
+                let span = span.to(self.previous().span);
+                Ok(Expr::new(
+                    ExprKind::New {
+                        callee: Box::new(callee),
+                        args,
+                    },
+                    span,
+                ))
+            }
+            other => Err(ParseError {
+                message: format!("Expected expression, found {}", describe(&other)),
+                span,
+            }),
+        }
+    }
+
+    /// Parse `.name` accessors following the callee of a `new` expression
+    fn member_chain(&mut self, mut expr: Expr) -> ParseResult<Expr> {
+        while self.eat(&TokenKind::Dot) {
+            let property = self.property_name()?;
+            let span = expr.span.to(property.span);
+            expr = Expr::new(
+                ExprKind::Member {
+                    object: Box::new(expr),
+                    property,
+                    optional: false,
+                },
+                span,
+            );
+        }
+
+        Ok(expr)
+    }
+
+    fn array_literal(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span;
+        let mut elements = Vec::new();
+
+        while !self.check(&TokenKind::RBracket) {
+            elements.push(self.element()?);
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+
+        let end = self
+            .expect(&TokenKind::RBracket, "']' after array elements")?
+            .span;
+        Ok(Expr::new(ExprKind::Array(elements), start.to(end)))
+    }
+
+    /// Decide whether a `{` in expression position opens an object literal
+    fn looks_like_object(&self) -> bool {
+        let first = &self.peek_nth(1).kind;
+        let second = &self.peek_nth(2).kind;
+
+        match first {
+            TokenKind::RBrace => true,
+            TokenKind::Ident(_) | TokenKind::Str(_) | TokenKind::Number(_) => matches!(
+                second,
+                TokenKind::Colon | TokenKind::Comma | TokenKind::RBrace
+            ),
+            _ => false,
+        }
+    }
+
+    fn object_literal(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span;
+        let mut properties = Vec::new();
+
+        while !self.check(&TokenKind::RBrace) {
+            let token = self.advance();
+            let key = match &token.kind {
+                TokenKind::Ident(name) => name.clone(),
+                TokenKind::Str(s) => s.clone(),
+                TokenKind::Number(n) => format_number_key(*n),
+                other => {
+                    return Err(ParseError {
+                        message: format!("Expected property name, found {}", describe(other)),
+                        span: token.span,
+                    })
+                }
+            };
+
+            let property = if self.eat(&TokenKind::Colon) {
+                let value = self.expression()?;
+                let span = token.span.to(value.span);
+                Property {
+                    key,
+                    value,
+                    shorthand: false,
+                    span,
+                }
+            } else if matches!(token.kind, TokenKind::Ident(_)) {
+                Property {
+                    value: Expr::new(ExprKind::Ident(key.clone()), token.span),
+                    key,
+                    shorthand: true,
+                    span: token.span,
+                }
+            } else {
+                return Err(self.error_here("Expected ':' after property name"));
+            };
+
+            properties.push(property);
+
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+
+        let end = self
+            .expect(&TokenKind::RBrace, "'}' after object properties")?
+            .span;
+        Ok(Expr::new(ExprKind::Object(properties), start.to(end)))
+    }
+
+    fn template_parts(&mut self, segments: Vec<TemplateSegment>) -> ParseResult<Vec<TemplatePart>> {
+        let mut parts = Vec::new();
+
+        for segment in segments {
+            match segment {
+                TemplateSegment::Text(text) => parts.push(TemplatePart::Text(text)),
+                TemplateSegment::Code {
+                    source,
+                    offset,
+                    line,
+                    column,
+                } => {
+                    let lexer = Lexer::with_origin(&source, offset, line, column);
+                    let mut parser = Parser::from_lexer(lexer, Arc::clone(&self.source))?;
+                    parser.function_depth = self.function_depth;
+                    parser.nesting = self.nesting;
+                    parser.else_ifs = self.else_ifs;
+                    let expr = parser.expression()?;
+
+                    if !parser.check(&TokenKind::Eof) {
+                        return Err(parser.error_here("Unexpected token in template interpolation"));
+                    }
+
+                    parts.push(TemplatePart::Expr(expr));
+                }
+            }
+        }
+
+        Ok(parts)
+    }
+}
+
+fn is_assignable(expr: &Expr) -> bool {
+    matches!(
+        expr.kind,
+        ExprKind::Ident(_) | ExprKind::Member { .. } | ExprKind::Index { .. }
+    )
+}
+
+fn format_number_key(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Human-readable description of a token for error messages
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Number(n) => format!("number {}", n),
+        TokenKind::Str(_) => "string".to_string(),
+        TokenKind::Template(_) => "template string".to_string(),
+        TokenKind::Regex { .. } => "regular expression".to_string(),
+        TokenKind::Ident(name) => format!("identifier '{}'", name),
+        TokenKind::Symbol(s) => format!("symbol '{}'", s),
+        TokenKind::Eof => "end of input".to_string(),
+        other => format!("'{}'", token_text(other)),
+    }
+}
+
+/// Source text of fixed tokens
+fn token_text(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Function => "ƒ",
+        TokenKind::Lambda => "λ",
+        TokenKind::Let => "ι",
+        TokenKind::Return => "↩",
+        TokenKind::Export => "⟼",
+        TokenKind::If => "↪",
+        TokenKind::Else => "↛",
+        TokenKind::While => "↻",
+        TokenKind::Break => "↵",
+        TokenKind::Continue => "continue",
+        TokenKind::ForEach => "∀",
+        TokenKind::Print => "⌽",
+        TokenKind::Try => "÷",
+        TokenKind::TryBlock => "↺",
+        TokenKind::Catch => "⚠",
+        TokenKind::Sigil(_) => "σ",
+        TokenKind::True => "true",
+        TokenKind::False => "false",
+        TokenKind::Null => "null",
+        TokenKind::New => "new",
+        TokenKind::Typeof => "typeof",
+        TokenKind::LParen => "(",
+        TokenKind::RParen => ")",
+        TokenKind::LBrace => "{",
+        TokenKind::RBrace => "}",
+        TokenKind::LBracket => "[",
+        TokenKind::RBracket => "]",
+        TokenKind::Comma => ",",
+        TokenKind::Dot => ".",
+        TokenKind::Ellipsis => "...",
+        TokenKind::Semicolon => ";",
+        TokenKind::Colon => ":",
+        TokenKind::Question => "?",
+        TokenKind::QuestionDot => "?.",
+        TokenKind::QuestionQuestion => "??",
+        TokenKind::Plus => "+",
+        TokenKind::Minus => "-",
+        TokenKind::Star => "*",
+        TokenKind::Slash => "/",
+        TokenKind::Percent => "%",
+        TokenKind::Bang => "!",
+        TokenKind::Tilde => "~",
+        TokenKind::Assign => "=",
+        TokenKind::PlusAssign => "+=",
+        TokenKind::MinusAssign => "-=",
+        TokenKind::StarAssign => "*=",
+        TokenKind::SlashAssign => "/=",
+        TokenKind::PlusPlus => "++",
+        TokenKind::MinusMinus => "--",
+        TokenKind::EqEq => "==",
+        TokenKind::EqEqEq => "===",
+        TokenKind::BangEq => "!=",
+        TokenKind::BangEqEq => "!==",
+        TokenKind::Lt => "<",
+        TokenKind::LtEq => "<=",
+        TokenKind::Gt => ">",
+        TokenKind::GtEq => ">=",
+        TokenKind::AndAnd => "&&",
+        TokenKind::OrOr => "||",
+        _ => "token",
+    }
 }
 
 /// Represents parsed Anarchy-Inference code
+#[derive(Debug, Clone)]
 pub struct ParsedCode {
     /// The raw code string
     pub raw_code: String,
-    // In a real implementation, this would contain the AST
+
+    /// The abstract syntax tree
+    pub program: Program,
 }
 
 /// Parse Anarchy-Inference code
 pub fn parse(code: &str) -> Result<ParsedCode> {
-    let config = Config::default();
-    let parser = Parser::new(config);
-    parser.parse(code)
+    if code.trim().is_empty() {
+        return Err(Error::CodeParsingError("Empty code".to_string()).into());
+    }
+
+    Ok(parse_spanned(code).map_err(Error::from)?)
+}
+
+/// Parse code, keeping the location of a syntax error
+pub(crate) fn parse_spanned(code: &str) -> ParseResult<ParsedCode> {
+    let program = Parser::new(code)?.parse_program()?;
+
+    Ok(ParsedCode {
+        raw_code: code.to_string(),
+        program,
+    })
 }
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::error::Error;
+use crate::registry::SymbolSignature;
+use crate::sandbox::Sandbox;
+use crate::value::Value;
+
+/// A set of symbols contributed from outside the executor
+///
+/// Implement this to give scripts new tools without changing the executor,
+/// then pass the provider to `Executor::add_provider` or to the `Agent` and
+/// `Core` builders. Its symbols are registered after the built-in ones
+/// when the executor is initialized and behave like them: the capability in
+/// each signature decides whether a capability profile allows the symbol,
+/// the help text shows up in symbol listings and generated prompts, and
+/// dry runs record calls to symbols with side effects instead of making
+/// them.
+///
+/// ```ignore
+/// struct Tickets;
+///
+/// impl SymbolProvider for Tickets {
+///     fn name(&self) -> &str {
+///         "tickets"
+///     }
+///
+///     fn register(&self, symbols: &mut SymbolRegistrar<'_>) -> Result<()> {
+///         let signature = SymbolSignature::new("🎫", Capability::Network)
+///             .param("title", ValueType::String)
+///             .returns(ValueType::String)
+///             .side_effects()
+///             .help("Open a ticket and return its id");
+///         symbols.register(signature, |args| async move {
+///             let id = open_ticket(&args[0].to_string()).await?;
+///             Ok(Value::from(id))
+///         })
+///     }
+/// }
+/// ```
+pub trait SymbolProvider: Send + Sync {
+    /// Name of the provider, used in registration errors
+    fn name(&self) -> &str;
+
+    /// Register the provider's symbols
+    fn register(&self, symbols: &mut SymbolRegistrar<'_>) -> Result<()>;
+}
+
+/// Where a `SymbolProvider` registers its symbols
+pub struct SymbolRegistrar<'a> {
+    sandbox: &'a Sandbox,
+    provider: &'a str,
+}
+
+impl<'a> SymbolRegistrar<'a> {
+    pub(crate) fn new(sandbox: &'a Sandbox, provider: &'a str) -> Self {
+        Self { sandbox, provider }
+    }
+
+    /// Register a handler for a symbol
+    ///
+    /// The handler gets the arguments of a call and returns its result.
+    /// Arities are checked against the signature before the handler runs,
+    /// argument types are not. Fails if a built-in symbol or a symbol of
+    /// another provider already has the name.
+    pub fn register<F, Fut>(&mut self, signature: SymbolSignature, handler: F) -> Result<()>
+    where
+        F: Fn(Vec<Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.sandbox
+            .register_symbol(signature, handler)
+            .map_err(|err| match err.downcast::<Error>() {
+                Ok(Error::SymbolRegistrationError(message)) => Error::SymbolRegistrationError(
+                    format!("{} (provider '{}')", message, self.provider),
+                )
+                .into(),
+                Ok(other) => other.into(),
+                Err(err) => err,
+            })
+    }
+}
@@ -0,0 +1,443 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::builtins;
+use crate::config::Config;
+use crate::error::Error;
+use crate::sandbox::SymbolHandler;
+
+/// Category of side effect a symbol can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Reading files and directories
+    FileRead,
+
+    /// Creating, modifying or deleting files
+    FileWrite,
+
+    /// Running shell commands
+    Shell,
+
+    /// Making network requests
+    Network,
+
+    /// Driving the browser
+    Browser,
+
+    /// Reading agent memory
+    MemoryRead,
+
+    /// Writing or deleting agent memory
+    MemoryWrite,
+
+    /// Exchanging data with the user through the input workaround
+    UserIo,
+
+    /// Pure helpers with no side effects
+    Core,
+}
+
+impl Capability {
+    /// Name of the capability as shown to users
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::FileRead => "file_read",
+            Capability::FileWrite => "file_write",
+            Capability::Shell => "shell",
+            Capability::Network => "network",
+            Capability::Browser => "browser",
+            Capability::MemoryRead => "memory_read",
+            Capability::MemoryWrite => "memory_write",
+            Capability::UserIo => "user_io",
+            Capability::Core => "core",
+        }
+    }
+
+    /// Whether symbols requiring the capability change state outside the script
+    pub fn has_side_effects(&self) -> bool {
+        matches!(
+            self,
+            Capability::FileWrite | Capability::Shell | Capability::MemoryWrite
+        )
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Set of capabilities a script is allowed to use
+///
+/// `Core` symbols have no side effects and are always allowed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityProfile {
+    allowed: BTreeSet<Capability>,
+}
+
+impl CapabilityProfile {
+    /// Allow every capability
+    pub fn all() -> Self {
+        Self::none()
+            .allow(Capability::FileRead)
+            .allow(Capability::FileWrite)
+            .allow(Capability::Shell)
+            .allow(Capability::Network)
+            .allow(Capability::Browser)
+            .allow(Capability::MemoryRead)
+            .allow(Capability::MemoryWrite)
+            .allow(Capability::UserIo)
+    }
+
+    /// Allow only side-effect free symbols
+    pub fn none() -> Self {
+        Self {
+            allowed: BTreeSet::from([Capability::Core]),
+        }
+    }
+
+    /// Allow reading files, the web and memory, but no shell and no writes
+    pub fn read_only() -> Self {
+        Self::none()
+            .allow(Capability::FileRead)
+            .allow(Capability::Network)
+            .allow(Capability::Browser)
+            .allow(Capability::MemoryRead)
+            .allow(Capability::UserIo)
+    }
+
+    /// Capabilities permitted by the executor configuration
+    pub fn from_config(config: &Config) -> Self {
+        let mut profile = Self::all();
+        if !config.allow_file_system {
+            profile = profile.deny(Capability::FileRead).deny(Capability::FileWrite);
+        }
+        if !config.allow_shell {
+            profile = profile.deny(Capability::Shell);
+        }
+        if !config.allow_network {
+            profile = profile.deny(Capability::Network);
+        }
+        profile
+    }
+
+    /// Add a capability
+    pub fn allow(mut self, capability: Capability) -> Self {
+        self.allowed.insert(capability);
+        self
+    }
+
+    /// Remove a capability
+    pub fn deny(mut self, capability: Capability) -> Self {
+        if capability != Capability::Core {
+            self.allowed.remove(&capability);
+        }
+        self
+    }
+
+    /// Capabilities allowed by both profiles
+    pub fn intersect(&self, other: &CapabilityProfile) -> Self {
+        Self {
+            allowed: self.allowed.intersection(&other.allowed).copied().collect(),
+        }
+    }
+
+    /// Whether symbols requiring `capability` may be called
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.allowed.contains(&capability)
+    }
+
+    /// The allowed capabilities, in order
+    pub fn capabilities(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.allowed.iter().copied()
+    }
+}
+
+impl Default for CapabilityProfile {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Type of a symbol argument or result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Any,
+    String,
+    Number,
+    Bool,
+    Array,
+    Object,
+    Bytes,
+    Null,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::Any => "any",
+            ValueType::String => "string",
+            ValueType::Number => "number",
+            ValueType::Bool => "bool",
+            ValueType::Array => "array",
+            ValueType::Object => "object",
+            ValueType::Bytes => "bytes",
+            ValueType::Null => "null",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A named symbol parameter
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolParam {
+    pub name: String,
+    pub ty: ValueType,
+
+    /// Whether the argument may be omitted
+    pub optional: bool,
+}
+
+/// Describes how a symbol is called and what it does
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolSignature {
+    /// The symbol itself, e.g. `📂`
+    pub name: String,
+
+    /// Declared parameters
+    pub params: Vec<SymbolParam>,
+
+    /// Whether additional arguments after `params` are accepted
+    pub variadic: bool,
+
+    /// Type of the value the symbol returns
+    pub return_type: ValueType,
+
+    /// Capability the symbol requires
+    pub capability: Capability,
+
+    /// Whether calling the symbol changes state outside the script
+    ///
+    /// Dry runs record these calls instead of making them.
+    pub side_effects: bool,
+
+    /// One-line description shown in listings
+    pub help: String,
+}
+
+impl SymbolSignature {
+    /// Create a signature for a symbol without parameters
+    pub fn new(name: &str, capability: Capability) -> Self {
+        Self {
+            name: normalize(name),
+            params: Vec::new(),
+            variadic: false,
+            return_type: ValueType::Any,
+            capability,
+            side_effects: capability.has_side_effects(),
+            help: String::new(),
+        }
+    }
+
+    /// Add a required parameter
+    pub fn param(mut self, name: &str, ty: ValueType) -> Self {
+        self.params.push(SymbolParam {
+            name: name.to_string(),
+            ty,
+            optional: false,
+        });
+        self
+    }
+
+    /// Add an optional parameter
+    pub fn optional(mut self, name: &str, ty: ValueType) -> Self {
+        self.params.push(SymbolParam {
+            name: name.to_string(),
+            ty,
+            optional: true,
+        });
+        self
+    }
+
+    /// Accept any number of additional arguments
+    pub fn variadic(mut self) -> Self {
+        self.variadic = true;
+        self
+    }
+
+    /// Set the return type
+    pub fn returns(mut self, ty: ValueType) -> Self {
+        self.return_type = ty;
+        self
+    }
+
+    /// Mark the symbol as changing state outside the script even though its
+    /// capability usually does not, e.g. a network request or a browser click
+    pub fn side_effects(mut self) -> Self {
+        self.side_effects = true;
+        self
+    }
+
+    /// Set the help text
+    pub fn help(mut self, help: &str) -> Self {
+        self.help = help.to_string();
+        self
+    }
+
+    /// Minimum number of arguments
+    pub fn min_arity(&self) -> usize {
+        self.params.iter().filter(|p| !p.optional).count()
+    }
+
+    /// Maximum number of arguments, `None` for variadic symbols
+    pub fn max_arity(&self) -> Option<usize> {
+        if self.variadic {
+            None
+        } else {
+            Some(self.params.len())
+        }
+    }
+
+    /// Whether a call with `count` arguments is valid
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_arity() && self.max_arity().is_none_or(|max| count <= max)
+    }
+
+    /// Human-readable description of the expected argument count
+    pub fn arity_description(&self) -> String {
+        let noun = |n: usize| if n == 1 { "argument" } else { "arguments" };
+        match (self.min_arity(), self.max_arity()) {
+            (min, Some(max)) if min == max => format!("{} {}", min, noun(min)),
+            (min, Some(max)) => format!("{} to {} arguments", min, max),
+            (min, None) => format!("at least {} {}", min, noun(min)),
+        }
+    }
+
+    /// Call syntax, e.g. `📝(key: string, value: string) -> string`
+    pub fn usage(&self) -> String {
+        let mut params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| {
+                if p.optional {
+                    format!("{}?: {}", p.name, p.ty)
+                } else {
+                    format!("{}: {}", p.name, p.ty)
+                }
+            })
+            .collect();
+
+        if self.variadic {
+            params.push("...".to_string());
+        }
+
+        format!("{}({}) -> {}", self.name, params.join(", "), self.return_type)
+    }
+}
+
+/// How a registered symbol is implemented
+#[derive(Clone)]
+pub enum SymbolImpl {
+    /// Implemented by the interpreter itself
+    Builtin,
+
+    /// Implemented by a registered handler
+    Handler(SymbolHandler),
+}
+
+/// A symbol together with its implementation
+#[derive(Clone)]
+pub struct RegisteredSymbol {
+    pub signature: SymbolSignature,
+    pub implementation: SymbolImpl,
+}
+
+/// Registry of the symbols available to scripts
+#[derive(Clone)]
+pub struct SymbolRegistry {
+    symbols: HashMap<String, RegisteredSymbol>,
+}
+
+impl SymbolRegistry {
+    /// Create a registry containing the interpreter's built-in symbols
+    pub fn new() -> Self {
+        let mut registry = Self {
+            symbols: HashMap::new(),
+        };
+
+        for signature in builtins::core_symbols() {
+            registry.insert(signature, SymbolImpl::Builtin).expect("built-in symbols are unique");
+        }
+
+        registry
+    }
+
+    /// Register a handler for a symbol
+    pub fn register(&mut self, signature: SymbolSignature, handler: SymbolHandler) -> Result<()> {
+        self.insert(signature, SymbolImpl::Handler(handler))
+    }
+
+    fn insert(&mut self, signature: SymbolSignature, implementation: SymbolImpl) -> Result<()> {
+        let name = normalize(&signature.name);
+        if name.is_empty() {
+            return Err(Error::SymbolRegistrationError("Empty symbol".to_string()).into());
+        }
+
+        if self.symbols.contains_key(&name) {
+            return Err(Error::SymbolRegistrationError(format!(
+                "Symbol '{}' is already registered",
+                name
+            ))
+            .into());
+        }
+
+        let signature = SymbolSignature { name: name.clone(), ..signature };
+        self.symbols.insert(
+            name,
+            RegisteredSymbol {
+                signature,
+                implementation,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Look up a symbol
+    pub fn get(&self, name: &str) -> Option<&RegisteredSymbol> {
+        self.symbols.get(&normalize(name))
+    }
+
+    /// Signatures of all registered symbols, ordered by capability and name
+    pub fn signatures(&self) -> Vec<SymbolSignature> {
+        let mut signatures: Vec<SymbolSignature> =
+            self.symbols.values().map(|symbol| symbol.signature.clone()).collect();
+        signatures.sort_by(|a, b| (a.capability, &a.name).cmp(&(b.capability, &b.name)));
+        signatures
+    }
+
+    /// Remove every handler, keeping the built-in symbols
+    pub fn clear_handlers(&mut self) {
+        self.symbols
+            .retain(|_, symbol| matches!(symbol.implementation, SymbolImpl::Builtin));
+    }
+}
+
+impl Default for SymbolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Canonical form of a symbol name
+///
+/// Emoji may be written with or without the U+FE0F variation selector
+/// (`🏷️` vs `🏷`); both spellings refer to the same symbol.
+pub fn normalize(name: &str) -> String {
+    name.chars().filter(|c| *c != '\u{fe0f}').collect()
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::ast::Span;
+use crate::diagnostics::{self, Diagnostic};
+use crate::effects::Effect;
+use crate::registry::Capability;
+use crate::trace::Trace;
+use crate::value::Value;
+
+/// Everything a script did when it ran
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReport {
+    /// Lines printed with `⌽`
+    pub output: Vec<String>,
+
+    /// Value returned with `⟼`, or the value of the last top-level expression
+    pub value: Value,
+
+    /// Why the script stopped early, `None` if it ran to completion
+    pub error: Option<ReportError>,
+
+    /// Calls to registered symbols, in the order they were made
+    ///
+    /// The interpreter's built-in `Core` symbols are not recorded.
+    pub invocations: Vec<SymbolInvocation>,
+
+    /// Side effects the script would have had, recorded instead of performed
+    /// during a dry run; empty for real runs
+    pub effects: Vec<Effect>,
+
+    /// Total time spent running the script, in milliseconds
+    pub duration_ms: f64,
+
+    /// Symbol calls and their results, when the run was recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Trace>,
+}
+
+/// An error that stopped a script
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportError {
+    /// Diagnostic code, e.g. `E0002` for runtime errors
+    pub code: String,
+
+    /// What went wrong, without the location
+    pub message: String,
+
+    /// Location of the code that failed, if known
+    pub span: Option<Span>,
+}
+
+impl ReportError {
+    /// Describe an executor error that occurred at `span`
+    pub fn new(error: &anyhow::Error, span: Option<Span>) -> Self {
+        let (code, message) = diagnostics::describe(error);
+        Self {
+            code: code.to_string(),
+            message,
+            span,
+        }
+    }
+
+    /// The error as a diagnostic, for rendering against the source
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(&self.code, self.message.clone(), self.span)
+    }
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(
+                f,
+                "{} at line {}, column {}",
+                self.message, span.line, span.column
+            ),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// A call to a registered symbol
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolInvocation {
+    /// The symbol that was called, e.g. `📂`
+    pub symbol: String,
+
+    /// Capability the symbol requires
+    pub capability: Capability,
+
+    /// Arguments the symbol was called with
+    pub args: Vec<Value>,
+
+    /// What the symbol returned, `None` if it failed
+    pub result: Option<Value>,
+
+    /// Why the symbol failed
+    pub error: Option<String>,
+
+    /// Location of the call
+    pub span: Span,
+
+    /// Time the symbol took, in milliseconds
+    pub duration_ms: f64,
+}
+
+impl ExecutionReport {
+    /// Report for a script that could not run at all, e.g. because it does not parse
+    pub fn failed(error: &anyhow::Error, span: Option<Span>) -> Self {
+        Self {
+            output: Vec::new(),
+            value: Value::Null,
+            error: Some(ReportError::new(error, span)),
+            invocations: Vec::new(),
+            effects: Vec::new(),
+            duration_ms: 0.0,
+            trace: None,
+        }
+    }
+
+    /// Whether the script ran to completion
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The report as JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+impl fmt::Display for ExecutionReport {
+    /// The printed output followed by the intended effects of a dry run and
+    /// the result or the error
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sections = Vec::new();
+
+        if !self.output.is_empty() {
+            sections.push(self.output.join("\n"));
+        }
+        if !self.effects.is_empty() {
+            let effects: Vec<String> = self.effects.iter().map(|e| format!("  {}", e)).collect();
+            sections.push(format!("Intended effects:\n{}", effects.join("\n")));
+        }
+        if !self.value.is_null() {
+            sections.push(format!("Result: {}", self.value));
+        }
+        if let Some(error) = &self.error {
+            sections.push(format!("Error: {}", error));
+        }
+
+        f.write_str(&sections.join("\n\n"))
+    }
+}
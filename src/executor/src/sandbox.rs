@@ -1,16 +1,29 @@
 use anyhow::Result;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use log::warn;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
 
 use crate::config::Config;
-use crate::error::Error;
+use crate::interpreter::{BoxFuture, Interpreter};
+use crate::options::ExecutionOptions;
 use crate::parser::ParsedCode;
+use crate::registry::{SymbolRegistry, SymbolSignature};
+use crate::report::ExecutionReport;
+use crate::value::Value;
+
+/// Handler invoked when a script calls a registered symbol
+///
+/// Handlers receive the call's arguments and return a future that is
+/// awaited on the runtime executing the script. Arguments and results are
+/// script values, so a handler can return arrays and objects that scripts
+/// index and iterate directly.
+pub type SymbolHandler = Arc<dyn Fn(Vec<Value>) -> BoxFuture<'static, Result<Value>> + Send + Sync>;
 
 /// Sandbox for safely executing Anarchy-Inference code
 pub struct Sandbox {
     config: Config,
-    // In a real implementation, this would contain the sandbox environment
+
+    /// Symbols scripts are allowed to call
+    symbols: RwLock<SymbolRegistry>,
 }
 
 impl Sandbox {
@@ -18,54 +31,55 @@ impl Sandbox {
     pub fn new(config: &Config) -> Result<Self> {
         Ok(Self {
             config: config.clone(),
+            symbols: RwLock::new(SymbolRegistry::new()),
         })
     }
-    
+
     /// Initialize the sandbox
     pub fn initialize(&self) -> Result<()> {
-        // In a real implementation, this would set up the sandbox environment
-        if !self.config.sandbox_enabled {
-            warn!("Warning: Sandbox is disabled. Code will run with full permissions.");
+        if !self.config.allow_file_system || !self.config.allow_shell || !self.config.allow_network {
+            log::info!(
+                "Sandbox restrictions: file system {}, shell {}, network {}",
+                self.config.allow_file_system,
+                self.config.allow_shell,
+                self.config.allow_network
+            );
         }
-        
+
         Ok(())
     }
-    
+
     /// Register a symbol handler
-    pub fn register_symbol<F>(&self, symbol: &str, _handler: F) -> Result<()>
+    ///
+    /// Fails if a symbol with the same name is already registered.
+    pub fn register_symbol<F, Fut>(&self, signature: SymbolSignature, handler: F) -> Result<()>
     where
-        F: Fn(&[&str]) -> Result<String> + Send + Sync + 'static,
+        F: Fn(Vec<Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
     {
-        // In a real implementation, this would register the handler for the symbol
-        // For now, just validate the symbol
-        if symbol.is_empty() {
-            return Err(Error::SymbolRegistrationError(
-                "Empty symbol".to_string()
-            ).into());
-        }
-        
-        Ok(())
+        let handler: SymbolHandler = Arc::new(move |args| Box::pin(handler(args)));
+        self.symbols.write().unwrap().register(signature, handler)
     }
-    
+
+    /// Signatures of all symbols available to scripts
+    pub fn symbol_signatures(&self) -> Vec<SymbolSignature> {
+        self.symbols.read().unwrap().signatures()
+    }
+
     /// Execute parsed code in the sandbox
-    pub fn execute(&self, code: &ParsedCode) -> Result<String> {
-        // In a real implementation, this would execute the code in a sandboxed environment
-        // For now, just return a placeholder result
-        
-        if !self.config.sandbox_enabled {
-            warn!("Warning: Executing code without sandbox protection.");
-        }
-        
-        // Simulate execution
-        Ok(format!(
-            "Executed code:\n{}\n\nResult: Task completed successfully",
-            code.raw_code
-        ))
+    ///
+    /// Errors raised by the script are part of the report rather than
+    /// returned, so the output printed before the failure is not lost.
+    pub async fn execute(&self, code: &ParsedCode, options: &ExecutionOptions) -> ExecutionReport {
+        let symbols = self.symbols.read().unwrap().clone();
+        let interpreter = Interpreter::new(symbols, &self.config, options);
+
+        interpreter.run(code).await
     }
-    
+
     /// Shutdown the sandbox
     pub fn shutdown(&self) -> Result<()> {
-        // In a real implementation, this would clean up the sandbox environment
+        self.symbols.write().unwrap().clear_handlers();
         Ok(())
     }
 }
@@ -1,153 +1,588 @@
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::error::Error;
+use crate::http::{self, HttpClient, HttpRequest};
+use crate::registry::{Capability, SymbolSignature, ValueType};
 use crate::sandbox::Sandbox;
+use crate::value::Value;
 
 /// Register symbol handlers for file operations
+///
+/// Paths are relative to the system's working directory, and the system
+/// sandbox decides which of them may be touched.
 pub fn register_file_symbols(
     sandbox: &Sandbox,
     system: Arc<Mutex<system::System>>,
 ) -> Result<()> {
     // Register 📂 (list directory)
-    sandbox.register_symbol("📂", move |args| {
-        if args.len() != 1 {
-            return Err(Error::SymbolRegistrationError(
-                "📂 requires exactly one argument (path)".to_string()
-            ).into());
-        }
-        
-        let path = args[0];
-        let system_clone = Arc::clone(&system);
-        
-        // This would be properly async in a real implementation
-        let files = tokio::runtime::Runtime::new()?.block_on(async {
-            system_clone.lock().await.list_directory(path).await
-        })?;
-        
-        Ok(format!("{:?}", files))
-    })?;
-    
-    // Register other file symbols (📖, ✍, ✂, etc.)
-    // Similar implementation pattern for each symbol
+    let signature = SymbolSignature::new("📂", Capability::FileRead)
+        .param("path", ValueType::String)
+        .returns(ValueType::Array)
+        .help("List the entries of a directory");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let path = string_arg("📂", &args, 0, "path")?;
+            
+            let files = system.lock().await.list_directory(&path).await?;
+            
+            Ok(Value::array(files.into_iter().map(Value::from).collect()))
+        }
+    })?;
+    
+    // Register 📖 (read file)
+    let signature = SymbolSignature::new("📖", Capability::FileRead)
+        .param("path", ValueType::String)
+        .returns(ValueType::String)
+        .help("Read a text file");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let path = string_arg("📖", &args, 0, "path")?;
+            
+            let contents = system.lock().await.read_file(&path).await?;
+            
+            Ok(Value::Str(contents))
+        }
+    })?;
+    
+    // Register ✍ (write file)
+    let signature = SymbolSignature::new("✍", Capability::FileWrite)
+        .param("path", ValueType::String)
+        .param("content", ValueType::Any)
+        .returns(ValueType::Null)
+        .help("Write text to a file, replacing its contents");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let path = string_arg("✍", &args, 0, "path")?;
+            let content = text_arg("✍", &args, 1, "content")?;
+            
+            system.lock().await.write_file(&path, &content).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register ✂ (remove file or directory)
+    let signature = SymbolSignature::new("✂", Capability::FileWrite)
+        .param("path", ValueType::String)
+        .returns(ValueType::Null)
+        .help("Delete a file, or a directory with everything in it");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let path = string_arg("✂", &args, 0, "path")?;
+            
+            system.lock().await.remove_path(&path).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register ⧉ (copy file)
+    let signature = SymbolSignature::new("⧉", Capability::FileWrite)
+        .param("source", ValueType::String)
+        .param("destination", ValueType::String)
+        .returns(ValueType::Null)
+        .help("Copy a file");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let source = string_arg("⧉", &args, 0, "source")?;
+            let destination = string_arg("⧉", &args, 1, "destination")?;
+            
+            system.lock().await.copy_file(&source, &destination).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register ↷ (move file)
+    let signature = SymbolSignature::new("↷", Capability::FileWrite)
+        .param("source", ValueType::String)
+        .param("destination", ValueType::String)
+        .returns(ValueType::Null)
+        .help("Move or rename a file");
+    let system_clone = Arc::clone(&system);
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system_clone);
+        async move {
+            let source = string_arg("↷", &args, 0, "source")?;
+            let destination = string_arg("↷", &args, 1, "destination")?;
+            
+            system.lock().await.move_file(&source, &destination).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register ? (file exists)
+    let signature = SymbolSignature::new("?", Capability::FileRead)
+        .param("path", ValueType::String)
+        .returns(ValueType::Bool)
+        .help("Check whether a file or directory exists");
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system);
+        async move {
+            let path = string_arg("?", &args, 0, "path")?;
+            
+            let exists = system.lock().await.file_exists(&path).await?;
+            
+            Ok(Value::Bool(exists))
+        }
+    })?;
     
     Ok(())
 }
 
+/// The string argument at `index`
+fn string_arg(symbol: &str, args: &[Value], index: usize, name: &str) -> Result<String> {
+    match args.get(index) {
+        Some(Value::Str(s)) => Ok(s.clone()),
+        Some(other) => Err(Error::InvalidArgument(format!(
+            "{} expects {} to be a string, got {}",
+            symbol,
+            name,
+            other.type_name()
+        ))
+        .into()),
+        None => Err(Error::InvalidArgument(format!("{} requires {}", symbol, name)).into()),
+    }
+}
+
+/// The argument at `index` as text: strings as they are, numbers, booleans,
+/// arrays and objects in the form `⌽` prints them
+fn text_arg(symbol: &str, args: &[Value], index: usize, name: &str) -> Result<String> {
+    match args.get(index) {
+        Some(
+            value @ (Value::Str(_)
+            | Value::Number(_)
+            | Value::Bool(_)
+            | Value::Array(_)
+            | Value::Object(_)),
+        ) => Ok(value.to_string()),
+        Some(other) => Err(Error::InvalidArgument(format!(
+            "{} cannot use a value of type {} as {}",
+            symbol,
+            other.type_name(),
+            name
+        ))
+        .into()),
+        None => Err(Error::InvalidArgument(format!("{} requires {}", symbol, name)).into()),
+    }
+}
+
 /// Register symbol handlers for shell operations
 pub fn register_shell_symbols(
     sandbox: &Sandbox,
     system: Arc<Mutex<system::System>>,
 ) -> Result<()> {
     // Register ! (execute shell)
-    sandbox.register_symbol("!", move |args| {
-        if args.len() != 1 {
-            return Err(Error::SymbolRegistrationError(
-                "! requires exactly one argument (command)".to_string()
-            ).into());
-        }
-        
-        let command = args[0];
-        let system_clone = Arc::clone(&system);
-        
-        // This would be properly async in a real implementation
-        let result = tokio::runtime::Runtime::new()?.block_on(async {
-            system_clone.lock().await.execute_shell(command).await
-        })?;
-        
-        Ok(format!("{:?}", result))
-    })?;
-    
-    // Register other shell symbols (🖥, 🌐, etc.)
-    // Similar implementation pattern for each symbol
+    let signature = SymbolSignature::new("!", Capability::Shell)
+        .param("command", ValueType::String)
+        .returns(ValueType::Object)
+        .help("Run a shell command, returning its output (o), errors (e) and exit code (c)");
+    sandbox.register_symbol(signature, move |args| {
+        let system = Arc::clone(&system);
+        async move {
+            let command = string_arg("!", &args, 0, "command")?;
+            let result = system.lock().await.execute_shell(&command).await?;
+            
+            let mut fields = BTreeMap::new();
+            fields.insert("o".to_string(), Value::Str(result.stdout));
+            fields.insert("e".to_string(), Value::Str(result.stderr));
+            fields.insert("c".to_string(), Value::Number(result.code as f64));
+            Ok(Value::object(fields))
+        }
+    })?;
     
     Ok(())
 }
 
 /// Register symbol handlers for network operations
+///
+/// Each symbol takes an optional object of request options (`headers`,
+/// `query`, `json`, `form`, `body`, `timeout`, `max_bytes`, `redirects`) and
+/// returns the response as `{ s: status, h: headers, b: body }`. JSON
+/// bodies can be decoded with `⎋`.
 pub fn register_network_symbols(
     sandbox: &Sandbox,
 ) -> Result<()> {
+    let client = Arc::new(HttpClient::new()?);
+    
     // Register ↗ (HTTP GET)
-    sandbox.register_symbol("↗", |args| {
-        if args.len() != 1 {
-            return Err(Error::SymbolRegistrationError(
-                "↗ requires exactly one argument (url)".to_string()
-            ).into());
+    let signature = SymbolSignature::new("↗", Capability::Network)
+        .param("url", ValueType::String)
+        .optional("options", ValueType::Object)
+        .returns(ValueType::Object)
+        .side_effects()
+        .help("Send an HTTP GET request, returning its status (s), headers (h) and body (b)");
+    let client_clone = Arc::clone(&client);
+    sandbox.register_symbol(signature, move |args| {
+        let client = Arc::clone(&client_clone);
+        async move {
+            let url = string_arg("↗", &args, 0, "url")?;
+            let options = args.get(1).cloned().unwrap_or(Value::Null);
+            
+            let request = HttpRequest::from_options(&options, Some("GET"), Some(&url))?;
+            
+            client.send(request).await
         }
-        
-        let url = args[0];
-        
-        // In a real implementation, this would make an HTTP request
-        // For now, just return a placeholder result
-        Ok(format!("{{\"s\":200,\"b\":\"Content from {}\"}}", url))
     })?;
     
-    // Register other network symbols (↓, ⎋, ~, etc.)
-    // Similar implementation pattern for each symbol
+    // Register ↓ (HTTP POST)
+    let signature = SymbolSignature::new("↓", Capability::Network)
+        .param("url", ValueType::String)
+        .param("body", ValueType::Any)
+        .optional("options", ValueType::Object)
+        .returns(ValueType::Object)
+        .side_effects()
+        .help("Send an HTTP POST request with a body: strings as they are, other values as JSON");
+    let client_clone = Arc::clone(&client);
+    sandbox.register_symbol(signature, move |args| {
+        let client = Arc::clone(&client_clone);
+        async move {
+            let url = string_arg("↓", &args, 0, "url")?;
+            let body = args.get(1).cloned().unwrap_or(Value::Null);
+            let options = args.get(2).cloned().unwrap_or(Value::Null);
+            
+            let mut request = HttpRequest::from_options(&options, Some("POST"), Some(&url))?;
+            if request.body.is_none() {
+                request.body = Some(http::raw_body(&body)?);
+            }
+            
+            client.send(request).await
+        }
+    })?;
+    
+    // Register ~ (HTTP request with any method)
+    let signature = SymbolSignature::new("~", Capability::Network)
+        .param("request", ValueType::Object)
+        .returns(ValueType::Object)
+        .side_effects()
+        .help("Send an HTTP request described by an object with a url, a method and request options");
+    sandbox.register_symbol(signature, move |args| {
+        let client = Arc::clone(&client);
+        async move {
+            let options = args.first().cloned().unwrap_or(Value::Null);
+            
+            let request = HttpRequest::from_options(&options, None, None)?;
+            
+            client.send(request).await
+        }
+    })?;
     
     Ok(())
 }
 
 /// Register symbol handlers for browser operations
+///
+/// There is one browser per executor. `🌐` opens a page in it and returns a
+/// handle that the other symbols take as their first argument, as in
+/// `🖱(page, "button")`; interacting before a page is open, or through the
+/// handle of a page that was closed or replaced, is an error.
 pub fn register_browser_symbols(
     sandbox: &Sandbox,
     browser: Arc<Mutex<browser::Browser>>,
 ) -> Result<()> {
     // Register 🌐 (open page)
-    sandbox.register_symbol("🌐", move |args| {
-        if args.len() != 1 {
-            return Err(Error::SymbolRegistrationError(
-                "🌐 requires exactly one argument (url)".to_string()
-            ).into());
-        }
-        
-        let url = args[0];
-        let browser_clone = Arc::clone(&browser);
-        
-        // This would be properly async in a real implementation
-        tokio::runtime::Runtime::new()?.block_on(async {
-            let mut browser_lock = browser_clone.lock().await;
-            browser_lock.open_page(url).await
-        })?;
-        
-        Ok("Browser opened".to_string())
-    })?;
-    
-    // Register other browser symbols (🖱, ⌨, 👁, etc.)
-    // Similar implementation pattern for each symbol
+    let signature = SymbolSignature::new("🌐", Capability::Browser)
+        .param("url", ValueType::String)
+        .returns(ValueType::Number)
+        .side_effects()
+        .help("Open a page in the browser and return a handle to it");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let url = string_arg("🌐", &args, 0, "url")?;
+            
+            let page = browser.lock().await.open_page(&url).await?;
+            
+            Ok(Value::Number(page as f64))
+        }
+    })?;
+    
+    // Register 🖱 (click element)
+    let signature = SymbolSignature::new("🖱", Capability::Browser)
+        .param("page", ValueType::Number)
+        .param("selector", ValueType::String)
+        .returns(ValueType::Null)
+        .side_effects()
+        .help("Click the element matching a CSS selector");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let page = page_arg("🖱", &args)?;
+            let selector = selector_arg("🖱", &args, 1)?;
+            
+            browser.lock().await.click_element(page, &selector).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register ⌨ (input text)
+    let signature = SymbolSignature::new("⌨", Capability::Browser)
+        .param("page", ValueType::Number)
+        .param("selector", ValueType::String)
+        .param("text", ValueType::Any)
+        .returns(ValueType::Null)
+        .side_effects()
+        .help("Type text into the element matching a CSS selector");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let page = page_arg("⌨", &args)?;
+            let selector = selector_arg("⌨", &args, 1)?;
+            let text = text_arg("⌨", &args, 2, "text")?;
+            
+            browser.lock().await.input_text(page, &selector, &text).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register 👁 (get text)
+    let signature = SymbolSignature::new("👁", Capability::Browser)
+        .param("page", ValueType::Number)
+        .param("selector", ValueType::String)
+        .returns(ValueType::String)
+        .help("Get the text of the element matching a CSS selector");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let page = page_arg("👁", &args)?;
+            let selector = selector_arg("👁", &args, 1)?;
+            
+            let text = browser.lock().await.get_text(page, &selector).await?;
+            
+            Ok(Value::Str(text))
+        }
+    })?;
+    
+    // Register 🧠 (execute JavaScript)
+    let signature = SymbolSignature::new("🧠", Capability::Browser)
+        .param("page", ValueType::Number)
+        .param("script", ValueType::String)
+        .returns(ValueType::Any)
+        .side_effects()
+        .help("Run JavaScript in the page and return its result");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let page = page_arg("🧠", &args)?;
+            let script = string_arg("🧠", &args, 1, "script")?;
+            
+            let result = browser.lock().await.execute_js(page, &script).await?;
+            
+            // Results that are JSON come back as values, anything else as text
+            Ok(match serde_json::from_str::<serde_json::Value>(&result) {
+                Ok(json) => Value::from(json),
+                Err(_) => Value::Str(result),
+            })
+        }
+    })?;
+    
+    // Register ❌ (close browser)
+    let signature = SymbolSignature::new("❌", Capability::Browser)
+        .param("page", ValueType::Number)
+        .returns(ValueType::Null)
+        .side_effects()
+        .help("Close the browser");
+    let browser_clone = Arc::clone(&browser);
+    sandbox.register_symbol(signature, move |args| {
+        let browser = Arc::clone(&browser_clone);
+        async move {
+            let page = page_arg("❌", &args)?;
+            
+            browser.lock().await.close(page).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
     
     Ok(())
 }
 
+/// The first argument as a page handle from `🌐`
+fn page_arg(symbol: &str, args: &[Value]) -> Result<u64> {
+    match args.first() {
+        Some(Value::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as u64),
+        Some(Value::Number(n)) => Err(Error::InvalidArgument(format!(
+            "{} got {} as page, which is not a page handle from 🌐",
+            symbol, n
+        ))
+        .into()),
+        Some(other) => Err(Error::InvalidArgument(format!(
+            "{} expects page to be a page handle from 🌐, got {}",
+            symbol,
+            other.type_name()
+        ))
+        .into()),
+        None => Err(Error::InvalidArgument(format!("{} requires page", symbol)).into()),
+    }
+}
+
+/// The argument at `index` as a CSS selector
+///
+/// Only catches selectors that are clearly broken, i.e. empty ones and ones
+/// with unbalanced brackets or quotes; the page decides whether anything
+/// matches.
+fn selector_arg(symbol: &str, args: &[Value], index: usize) -> Result<String> {
+    let selector = string_arg(symbol, args, index, "selector")?;
+    if selector.trim().is_empty() {
+        return Err(Error::InvalidArgument(format!("{} requires a non-empty selector", symbol)).into());
+    }
+    if !is_balanced(&selector) {
+        return Err(Error::InvalidArgument(format!(
+            "{} got an invalid selector '{}': unbalanced brackets or quotes",
+            symbol, selector
+        ))
+        .into());
+    }
+    
+    Ok(selector)
+}
+
+/// Whether the brackets and quotes in a selector are balanced
+fn is_balanced(selector: &str) -> bool {
+    let mut open = Vec::new();
+    let mut quote = None;
+    let mut chars = selector.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (_, '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '(') => open.push(c),
+            (None, ']') if open.pop() != Some('[') => return false,
+            (None, ')') if open.pop() != Some('(') => return false,
+            _ => {}
+        }
+    }
+    
+    quote.is_none() && open.is_empty()
+}
+
 /// Register symbol handlers for memory operations
 pub fn register_memory_symbols(
     sandbox: &Sandbox,
     memory: Arc<Mutex<memory::Memory>>,
 ) -> Result<()> {
     // Register 📝 (set memory)
-    sandbox.register_symbol("📝", move |args| {
-        if args.len() != 2 {
-            return Err(Error::SymbolRegistrationError(
-                "📝 requires exactly two arguments (key, value)".to_string()
-            ).into());
-        }
-        
-        let key = args[0];
-        let value = args[1];
-        let memory_clone = Arc::clone(&memory);
-        
-        // This would be properly async in a real implementation
-        tokio::runtime::Runtime::new()?.block_on(async {
-            memory_clone.lock().await.set_memory(key, value).await
-        })?;
-        
-        Ok("Memory set".to_string())
-    })?;
-    
-    // Register other memory symbols (📖, 🗑, etc.)
-    // Similar implementation pattern for each symbol
+    let signature = SymbolSignature::new("📝", Capability::MemoryWrite)
+        .param("key", ValueType::String)
+        .param("value", ValueType::String)
+        .returns(ValueType::String)
+        .help("Store a value in memory under a key");
+    let memory_clone = Arc::clone(&memory);
+    sandbox.register_symbol(signature, move |args| {
+        let memory = Arc::clone(&memory_clone);
+        async move {
+            let key = string_arg("📝", &args, 0, "key")?;
+            
+            memory.lock().await.set_memory(&key, &args[1].to_string()).await?;
+            
+            Ok(Value::string("Memory set"))
+        }
+    })?;
+    
+    // Register 📚 (get memory)
+    //
+    // 📖 reads files; memory used to be read with it too
+    let signature = SymbolSignature::new("📚", Capability::MemoryRead)
+        .param("key", ValueType::String)
+        .returns(ValueType::String)
+        .help("Read the value stored in memory under a key");
+    let memory_clone = Arc::clone(&memory);
+    sandbox.register_symbol(signature, move |args| {
+        let memory = Arc::clone(&memory_clone);
+        async move {
+            let key = string_arg("📚", &args, 0, "key")?;
+            
+            let value = memory.lock().await.get_memory(&key).await?;
+            
+            Ok(Value::Str(value))
+        }
+    })?;
+    
+    // Register 🗑 (forget key)
+    let signature = SymbolSignature::new("🗑", Capability::MemoryWrite)
+        .param("key", ValueType::String)
+        .returns(ValueType::Null)
+        .help("Delete the value stored in memory under a key");
+    let memory_clone = Arc::clone(&memory);
+    sandbox.register_symbol(signature, move |args| {
+        let memory = Arc::clone(&memory_clone);
+        async move {
+            let key = string_arg("🗑", &args, 0, "key")?;
+            
+            memory.lock().await.forget_key(&key).await?;
+            
+            Ok(Value::Null)
+        }
+    })?;
+    
+    // Register 🏷️ (search by tags)
+    let signature = SymbolSignature::new("🏷️", Capability::MemoryRead)
+        .param("tags", ValueType::Array)
+        .returns(ValueType::Array)
+        .help("Find the memory entries that have all of the given tags");
+    let memory_clone = Arc::clone(&memory);
+    sandbox.register_symbol(signature, move |args| {
+        let memory = Arc::clone(&memory_clone);
+        async move {
+            let tags = match args.first() {
+                Some(Value::Array(items)) => items
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|tag| match tag {
+                        Value::Str(tag) => Ok(tag.clone()),
+                        other => Err(Error::InvalidArgument(format!(
+                            "🏷️ expects tags to be strings, got {}",
+                            other.type_name()
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                Some(other) => {
+                    return Err(Error::InvalidArgument(format!(
+                        "🏷️ expects tags to be an array, got {}",
+                        other.type_name()
+                    ))
+                    .into())
+                }
+                None => return Err(Error::InvalidArgument("🏷️ requires tags".to_string()).into()),
+            };
+            
+            let entries = memory.lock().await.search_by_tags(&tags).await?;
+            
+            // Entries become objects with the fields of `EnhancedMemoryEntry`
+            let entries = entries
+                .iter()
+                .map(|entry| serde_json::to_value(entry).map(Value::from))
+                .collect::<Result<Vec<_>, _>>()?;
+            
+            Ok(Value::array(entries))
+        }
+    })?;
     
     Ok(())
 }
@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::ast::Span;
+use crate::error::Error;
+use crate::registry::SymbolSignature;
+use crate::value::Value;
+
+/// Version of the trace file format
+pub const TRACE_VERSION: u32 = 1;
+
+/// The symbol calls of one run and their results, for replaying the run
+///
+/// Only calls to registered symbols are recorded: everything the script
+/// learns from `System`, `Browser`, `Memory` or the network. The built-in
+/// `Core` symbols are deterministic and run normally during a replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trace {
+    /// Format version, `TRACE_VERSION` when written by this executor
+    pub version: u32,
+
+    /// The script that was run
+    pub code: String,
+
+    /// Symbol calls in the order they were made
+    pub calls: Vec<TracedCall>,
+}
+
+/// A recorded symbol call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedCall {
+    pub symbol: String,
+
+    pub args: Vec<Value>,
+
+    /// Value the symbol returned, `None` if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    /// Message of the handler error, `None` if the call succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Kind of the handler error, e.g. `PathNotAllowed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+
+    /// Whether the run timed out during the call
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+
+    /// Line of the call
+    pub line: usize,
+}
+
+impl Trace {
+    /// An empty trace for `code`
+    pub fn new(code: impl Into<String>) -> Self {
+        Self {
+            version: TRACE_VERSION,
+            code: code.into(),
+            calls: Vec::new(),
+        }
+    }
+
+    /// Read a trace file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read trace {}", path.display()))?;
+        let trace: Trace = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid trace {}", path.display()))?;
+
+        if trace.version != TRACE_VERSION {
+            anyhow::bail!(
+                "Trace {} has version {}, expected {}",
+                path.display(),
+                trace.version,
+                TRACE_VERSION
+            );
+        }
+
+        Ok(trace)
+    }
+
+    /// Write the trace to a file as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write trace {}", path.display()))
+    }
+}
+
+/// How a symbol call ended
+pub(crate) enum Outcome {
+    Returned(Value),
+    Failed { kind: String, message: String },
+    TimedOut,
+}
+
+/// Hands out the recorded results of a trace in order
+pub(crate) struct Replay {
+    calls: Vec<TracedCall>,
+    next: Mutex<usize>,
+}
+
+impl Replay {
+    pub fn new(trace: &Trace) -> Self {
+        Self {
+            calls: trace.calls.clone(),
+            next: Mutex::new(0),
+        }
+    }
+
+    /// The recorded outcome of the next call, which has to be the same call
+    /// the script is making now
+    pub fn next(&self, signature: &SymbolSignature, args: &[Value], span: Span) -> Result<Outcome> {
+        let mut next = self.next.lock().unwrap();
+        let index = *next;
+        let actual = describe_call(&signature.name, args);
+
+        let Some(call) = self.calls.get(index) else {
+            return Err(Error::ReplayDivergence {
+                index,
+                line: span.line,
+                expected: "the end of the trace".to_string(),
+                actual,
+            }
+            .into());
+        };
+
+        let same_args = call.args.len() == args.len()
+            && call
+                .args
+                .iter()
+                .zip(args)
+                .all(|(recorded, arg)| recorded.to_json() == arg.to_json());
+        if call.symbol != signature.name || !same_args {
+            return Err(Error::ReplayDivergence {
+                index,
+                line: span.line,
+                expected: describe_call(&call.symbol, &call.args),
+                actual,
+            }
+            .into());
+        }
+
+        *next += 1;
+        Ok(match (&call.result, &call.error) {
+            _ if call.timed_out => Outcome::TimedOut,
+            (_, Some(error)) => Outcome::Failed {
+                kind: call
+                    .error_kind
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                message: error.clone(),
+            },
+            (Some(result), None) => Outcome::Returned(result.clone()),
+            (None, None) => Outcome::Returned(Value::Null),
+        })
+    }
+
+    /// Fail if the script finished without making every recorded call
+    pub fn finish(&self, line: usize) -> Result<()> {
+        let index = *self.next.lock().unwrap();
+        match self.calls.get(index) {
+            Some(call) => Err(Error::ReplayDivergence {
+                index,
+                line,
+                expected: describe_call(&call.symbol, &call.args),
+                actual: "the end of the script".to_string(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A call in source form, e.g. `!("ls -la")`
+fn describe_call(symbol: &str, args: &[Value]) -> String {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_json().to_string()).collect();
+    format!("{}({})", symbol, args.join(", "))
+}
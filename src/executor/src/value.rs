@@ -0,0 +1,404 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+
+use crate::ast::FunctionDecl;
+use crate::interpreter::Env;
+
+/// Shared, mutable array storage
+pub type Array = Arc<Mutex<Vec<Value>>>;
+
+/// Shared, mutable object storage
+pub type Object = Arc<Mutex<BTreeMap<String, Value>>>;
+
+/// Key of the single-property JSON object that stands for a bytes value
+pub const BYTES_KEY: &str = "$bytes";
+
+/// JSON form of an array or object that contains itself
+pub const CIRCULAR: &str = "[Circular]";
+
+/// Signature of functions implemented in Rust (globals such as `Date.now`)
+pub type NativeFn = dyn Fn(Vec<Value>) -> anyhow::Result<Value> + Send + Sync;
+
+/// A runtime value produced by evaluating Anarchy-Inference code
+///
+/// Arrays and objects have reference semantics: copies share the same
+/// storage, so `＋(list, item)` is visible through every alias of `list`.
+#[derive(Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Array),
+    Object(Object),
+    Function(Function),
+    Regex(Arc<RegexValue>),
+
+    /// Binary data such as a downloaded file; immutable, so copies can
+    /// share it
+    Bytes(Arc<Vec<u8>>),
+}
+
+/// A callable value
+#[derive(Clone)]
+pub enum Function {
+    /// A function declared in the script, together with its captured scope
+    Closure(Arc<Closure>),
+
+    /// A function implemented in Rust
+    Native(Arc<NativeFunction>),
+}
+
+/// A script function and the environment it was created in
+pub struct Closure {
+    pub decl: Arc<FunctionDecl>,
+    pub env: Env,
+}
+
+/// A function implemented in Rust
+pub struct NativeFunction {
+    pub name: String,
+    pub func: Box<NativeFn>,
+}
+
+/// A compiled regular expression literal
+pub struct RegexValue {
+    pub pattern: String,
+    pub flags: String,
+    pub regex: regex::Regex,
+}
+
+impl Value {
+    /// Create a string value
+    pub fn string(s: impl Into<String>) -> Self {
+        Value::Str(s.into())
+    }
+
+    /// Create an array value from a list of elements
+    pub fn array(items: Vec<Value>) -> Self {
+        Value::Array(Arc::new(Mutex::new(items)))
+    }
+
+    /// Create an object value from a map of properties
+    pub fn object(props: BTreeMap<String, Value>) -> Self {
+        Value::Object(Arc::new(Mutex::new(props)))
+    }
+
+    /// Create a bytes value
+    pub fn bytes(data: impl Into<Vec<u8>>) -> Self {
+        Value::Bytes(Arc::new(data.into()))
+    }
+
+    /// Create a native function value
+    pub fn native<F>(name: &str, func: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> anyhow::Result<Value> + Send + Sync + 'static,
+    {
+        Value::Function(Function::Native(Arc::new(NativeFunction {
+            name: name.to_string(),
+            func: Box::new(func),
+        })))
+    }
+
+    /// Whether the value is `null`
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Truthiness as used by conditions and logical operators
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::Str(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+
+    /// Name of the value's type as reported by `typeof`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+            Value::Array(_) | Value::Object(_) | Value::Regex(_) => "object",
+            Value::Function(_) => "function",
+            Value::Bytes(_) => "bytes",
+        }
+    }
+
+    /// Convert the value to a number, yielding NaN when it has no numeric meaning
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Value::Null => 0.0,
+            Value::Bool(b) => {
+                if *b {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Value::Number(n) => *n,
+            Value::Str(s) => {
+                let trimmed = s.trim();
+                if trimmed.is_empty() {
+                    0.0
+                } else {
+                    trimmed.parse().unwrap_or(f64::NAN)
+                }
+            }
+            _ => f64::NAN,
+        }
+    }
+
+    /// Loose equality (`==`): values of different primitive types are
+    /// compared through their string form, so `"true" == true` holds
+    pub fn loose_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Number(_), Value::Str(_))
+            | (Value::Str(_), Value::Number(_))
+            | (Value::Bool(_), Value::Str(_))
+            | (Value::Str(_), Value::Bool(_))
+            | (Value::Bool(_), Value::Number(_))
+            | (Value::Number(_), Value::Bool(_)) => self.to_string() == other.to_string(),
+            _ => self.strict_eq(other),
+        }
+    }
+
+    /// Strict equality (`===`): same type and same value, arrays and
+    /// objects compare by identity
+    pub fn strict_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => Arc::ptr_eq(a, b),
+            (Value::Object(a), Value::Object(b)) => Arc::ptr_eq(a, b),
+            (Value::Regex(a), Value::Regex(b)) => Arc::ptr_eq(a, b),
+            (Value::Function(Function::Closure(a)), Value::Function(Function::Closure(b))) => {
+                Arc::ptr_eq(a, b)
+            }
+            (Value::Function(Function::Native(a)), Value::Function(Function::Native(b))) => {
+                Arc::ptr_eq(a, b)
+            }
+            _ => false,
+        }
+    }
+
+    /// Convert the value to JSON
+    ///
+    /// Bytes become `{"$bytes": "<base64>"}`, which [`Value::from_json`]
+    /// turns back into bytes, and regexes their `/pattern/flags` source.
+    /// Functions and numbers JSON cannot represent (NaN, infinities) become
+    /// `null`, and an array or object inside itself the string
+    /// `"[Circular]"`; every other value survives the round trip unchanged.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.to_json_within(&mut Vec::new())
+    }
+
+    /// Convert the value to JSON inside the arrays and objects whose
+    /// storage is in `ancestors`
+    fn to_json_within(&self, ancestors: &mut Vec<usize>) -> serde_json::Value {
+        match self {
+            Value::Null | Value::Function(_) => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < 9.0e15 {
+                    serde_json::Value::from(*n as i64)
+                } else {
+                    serde_json::Number::from_f64(*n)
+                        .map(serde_json::Value::Number)
+                        .unwrap_or(serde_json::Value::Null)
+                }
+            }
+            Value::Str(s) => serde_json::Value::String(s.clone()),
+            Value::Array(items) => {
+                let storage = Arc::as_ptr(items) as usize;
+                if ancestors.contains(&storage) {
+                    return serde_json::Value::String(CIRCULAR.to_string());
+                }
+
+                let items = items.lock().unwrap().clone();
+                ancestors.push(storage);
+                let json = items
+                    .iter()
+                    .map(|item| item.to_json_within(ancestors))
+                    .collect();
+                ancestors.pop();
+                serde_json::Value::Array(json)
+            }
+            Value::Object(props) => {
+                let storage = Arc::as_ptr(props) as usize;
+                if ancestors.contains(&storage) {
+                    return serde_json::Value::String(CIRCULAR.to_string());
+                }
+
+                let props = props.lock().unwrap().clone();
+                ancestors.push(storage);
+                let json = props
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_json_within(ancestors)))
+                    .collect();
+                ancestors.pop();
+                serde_json::Value::Object(json)
+            }
+            Value::Regex(re) => serde_json::Value::String(format!("/{}/{}", re.pattern, re.flags)),
+            Value::Bytes(data) => {
+                let mut object = serde_json::Map::new();
+                object.insert(
+                    BYTES_KEY.to_string(),
+                    serde_json::Value::String(BASE64.encode(data.as_slice())),
+                );
+                serde_json::Value::Object(object)
+            }
+        }
+    }
+
+    /// Build a value from JSON, the inverse of [`Value::to_json`]
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Value::Str(s.clone()),
+            serde_json::Value::Array(items) => {
+                Value::array(items.iter().map(Value::from_json).collect())
+            }
+            serde_json::Value::Object(props) => {
+                if let Some(data) = decode_bytes(props) {
+                    return Value::bytes(data);
+                }
+                Value::object(
+                    props
+                        .iter()
+                        .map(|(k, v)| (k.clone(), Value::from_json(v)))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+/// The data of a `{"$bytes": "<base64>"}` object
+fn decode_bytes(props: &serde_json::Map<String, serde_json::Value>) -> Option<Vec<u8>> {
+    match props.get(BYTES_KEY) {
+        Some(serde_json::Value::String(encoded)) if props.len() == 1 => BASE64.decode(encoded).ok(),
+        _ => None,
+    }
+}
+
+/// Format a number the way scripts expect: integers without a fractional part
+///
+/// Whole numbers past the range `i64` holds exactly are formatted as `f64`,
+/// which prints them without a fractional part as well.
+pub fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if n.fract() == 0.0 && n.abs() < 9.0e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{}", n)
+    }
+}
+
+impl fmt::Display for Value {
+    /// String form used by `⌽`, template interpolation and string concatenation
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", format_number(*n)),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Array(_) | Value::Object(_) => write!(f, "{}", self.to_json()),
+            Value::Function(Function::Closure(closure)) => match &closure.decl.name {
+                Some(name) => write!(f, "[function {}]", name.name),
+                None => write!(f, "[function]"),
+            },
+            Value::Function(Function::Native(native)) => write!(f, "[function {}]", native.name),
+            Value::Regex(re) => write!(f, "/{}/{}", re.pattern, re.flags),
+            Value::Bytes(data) => write!(f, "[bytes {}]", data.len()),
+        }
+    }
+}
+
+impl serde::Serialize for Value {
+    /// Values serialize as their JSON form
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Value {
+    /// Values deserialize from their JSON form
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_json::Value::deserialize(deserializer).map(|json| Value::from_json(&json))
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{:?}", s),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(data: Vec<u8>) -> Self {
+        Value::bytes(data)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Value::array(items)
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(json: serde_json::Value) -> Self {
+        Value::from_json(&json)
+    }
+}
+
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        value.to_json()
+    }
+}
@@ -0,0 +1,52 @@
+//! Page handles returned by `🌐` and checked by the other browser symbols
+
+mod common;
+
+use common::Workspace;
+use serde_json::json;
+
+#[tokio::test]
+async fn pages_are_used_through_their_handles() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι first = 🌐("https://example.com");
+        🖱(first, "a.more");
+        ⌨(first, "input[name=q]", "rust");
+        ι second = 🌐("https://example.org");
+        👁(second, "h1");
+        ❌(second);
+        [first, second];
+    "#;
+
+    let report = executor.execute_code(code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!([1, 2]));
+}
+
+#[tokio::test]
+async fn stale_and_invalid_handles_are_errors() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι kinds = [];
+        ι first = 🌐("https://example.com");
+        ι second = 🌐("https://example.org");
+        ÷{ 🖱(first, "a.more"); }⚠(e){ ＋(kinds, e.kind); }
+        ÷{ ❌(first); }⚠(e){ ＋(kinds, e.kind); }
+        ÷{ 👁(-1, "h1"); }⚠(e){ ＋(kinds, e.kind); }
+        ❌(second);
+        ÷{ 🧠(second, "1 + 1"); }⚠(e){ ＋(kinds, e.kind); }
+        ❌(second);
+        kinds;
+    "#;
+
+    let report = executor.execute_code(code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(
+        report.value.to_json(),
+        json!(["NoPageOpen", "NoPageOpen", "InvalidArgument", "NoPageOpen"])
+    );
+}
@@ -0,0 +1,74 @@
+//! Denies symbols whose capability the configuration or the profile does
+//! not allow
+
+mod common;
+
+use common::Workspace;
+use executor::{codes, CapabilityProfile, Config, ExecutionOptions, ExecutionReport, Executor};
+use serde_json::json;
+
+/// Each call as a script of its own, with the error it failed with
+const CALLS: &[(&str, &str)] = &[
+    (r#"!("echo hi");"#, "'!' requires the 'shell' capability"),
+    (
+        r#"✍("out.txt", "x");"#,
+        "'✍' requires the 'file_write' capability",
+    ),
+    (
+        r#"📝("theme", "dark");"#,
+        "'📝' requires the 'memory_write' capability",
+    ),
+];
+
+async fn run(executor: &Executor, code: &str, options: &ExecutionOptions) -> ExecutionReport {
+    executor.execute_with_options(code, options).await.unwrap()
+}
+
+/// Fail unless `report` was denied with `message` before the symbol ran
+fn assert_denied(report: &ExecutionReport, message: &str) {
+    let error = report.error.as_ref().expect("the call was allowed");
+    assert_eq!(error.code, codes::PERMISSION_DENIED, "{}", report.to_json());
+    assert!(error.message.contains(message), "{}", error.message);
+    assert!(report.invocations.is_empty(), "{}", report.to_json());
+}
+
+/// Fail if a denied call left anything behind
+async fn assert_no_side_effects(workspace: &Workspace) {
+    assert!(!workspace.dir().join("out.txt").exists());
+
+    let executor = workspace.executor().await;
+    let report = executor
+        .execute_code(r#"ι kind = null; ÷{ 📚("theme"); }⚠(e){ kind = e.kind; } kind;"#)
+        .await
+        .unwrap();
+    assert_eq!(report.value.to_json(), json!("KeyNotFound"));
+}
+
+#[tokio::test]
+async fn shell_commands_are_denied_by_the_configuration() {
+    let workspace = Workspace::new();
+    let executor = workspace.build(Config {
+        allow_shell: false,
+        ..Config::default()
+    });
+    executor.initialize().await.unwrap();
+    let (code, message) = CALLS[0];
+
+    let report = run(&executor, code, &ExecutionOptions::new()).await;
+
+    assert_denied(&report, message);
+}
+
+#[tokio::test]
+async fn read_only_profiles_deny_changes() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let options = ExecutionOptions::new().capabilities(CapabilityProfile::read_only());
+
+    for (code, message) in CALLS {
+        let report = run(&executor, code, &options).await;
+        assert_denied(&report, message);
+    }
+
+    assert_no_side_effects(&workspace).await;
+}
@@ -0,0 +1,62 @@
+//! Fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use browser::Browser;
+use executor::{Config, Executor};
+use memory::Memory;
+use system::System;
+use tokio::sync::Mutex;
+
+/// A temporary directory for one test, removed when dropped
+///
+/// Tests in a binary run in parallel, so every workspace gets its own
+/// directory and memory database.
+pub struct Workspace {
+    dir: PathBuf,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "anarchy-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// An executor confined to the workspace by the system sandbox, not
+    /// initialized yet so that providers can still be added
+    pub fn build(&self, config: Config) -> Executor {
+        let memory = Arc::new(Mutex::new(
+            Memory::new(&self.dir.join("memory.db")).unwrap(),
+        ));
+        let browser = Arc::new(Mutex::new(Browser::new(true).unwrap()));
+        let system = Arc::new(Mutex::new(System::new(&self.dir, true).unwrap()));
+        Executor::with_config(config, memory, browser, system).unwrap()
+    }
+
+    /// An initialized executor with the default configuration
+    pub async fn executor(&self) -> Executor {
+        let executor = self.build(Config::default());
+        executor.initialize().await.unwrap();
+        executor
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
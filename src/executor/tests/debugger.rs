@@ -0,0 +1,80 @@
+//! Steps through scripts with a debugger that follows a script of actions
+
+mod common;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use common::Workspace;
+use executor::{DebugAction, Debugger, Engine, ExecutionOptions, Pause, PauseReason, Value};
+
+const CODE: &str = "ƒdouble(x) {
+    ι y = x * 2;
+    ⟼ y;
+}
+ι a = 1;
+ι b = double(a);
+ι c = double(b);
+⌽(c);
+";
+
+/// Answers each pause with the next action, recording where it stopped
+struct Scripted {
+    actions: VecDeque<DebugAction>,
+    stops: Arc<Mutex<Vec<(PauseReason, usize)>>>,
+}
+
+impl Debugger for Scripted {
+    fn paused(&mut self, pause: &Pause) -> DebugAction {
+        self.stops
+            .lock()
+            .unwrap()
+            .push((pause.reason, pause.span.line));
+        self.actions.pop_front().unwrap_or(DebugAction::Continue)
+    }
+
+    fn evaluated(&mut self, _expression: &str, _result: Result<Value, String>) {}
+}
+
+#[tokio::test]
+async fn steps_over_into_and_out_of_calls() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let stops = Arc::new(Mutex::new(Vec::new()));
+        let debugger = Scripted {
+            actions: VecDeque::from([
+                DebugAction::AddBreakpoint(6),
+                DebugAction::Continue,
+                DebugAction::StepOver,
+                DebugAction::StepIn,
+                DebugAction::StepOver,
+                DebugAction::StepOut,
+                DebugAction::Continue,
+            ]),
+            stops: Arc::clone(&stops),
+        };
+        let options = ExecutionOptions::new().debugger(debugger).engine(engine);
+
+        let report = executor.execute_with_options(CODE, &options).await.unwrap();
+
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.output, vec!["4"]);
+        assert_eq!(
+            *stops.lock().unwrap(),
+            [
+                (PauseReason::Entry, 1),
+                // Adding a breakpoint does not resume
+                (PauseReason::Entry, 1),
+                (PauseReason::Breakpoint, 6),
+                (PauseReason::Step, 7),
+                (PauseReason::Step, 2),
+                (PauseReason::Step, 3),
+                (PauseReason::Step, 8),
+            ],
+            "{:?}",
+            engine
+        );
+    }
+}
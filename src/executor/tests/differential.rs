@@ -0,0 +1,167 @@
+//! Runs the example and test scripts on both engines and checks that they
+//! behave the same
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::Workspace;
+use executor::{Engine, ExecutionOptions, Executor};
+use regex::Regex;
+
+/// Directories of scripts to compare, relative to the repository root
+const SCRIPT_DIRS: &[&str] = &["examples_ai", "tests"];
+
+/// Single scripts to compare as well, from directories that also hold
+/// scripts which only run inside the agent
+const SCRIPTS: &[&str] = &[
+    "src/bin_ai/main.a.i",
+    "src/bin_ai/main_updated.a.i",
+    "src/bin_ai/string_dictionary.a.i",
+    "src/bin_ai/string_dictionary_updated.a.i",
+];
+
+/// Scripts that cannot run to the end in a dry run stubbing reads, with the
+/// error both engines have to fail with
+const EXPECTED_ERRORS: &[(&str, &str)] = &[
+    // Stubbed `📚` reads return "", which `⎋` cannot decode
+    ("examples_ai/memory_operations.a.i", "Invalid JSON"),
+    ("tests/memory_system_test.a.i", "Invalid JSON"),
+    // Imports modules named by `:key` strings, empty when reads are stubbed
+    ("src/bin_ai/main_updated.a.i", "Module '' not found"),
+];
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// Copy the directories holding the scripts into the workspace, where the
+/// sandbox lets them load their dictionaries and modules, returning the
+/// copied scripts relative to the workspace
+fn copy_scripts(workspace: &Workspace) -> Vec<PathBuf> {
+    fn copy(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            let target = to.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+
+    let mut scripts = Vec::new();
+    for dir in SCRIPT_DIRS {
+        copy(&root().join(dir), &workspace.dir().join(dir));
+        for entry in std::fs::read_dir(root().join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.to_string_lossy().ends_with(".a.i") {
+                scripts.push(Path::new(dir).join(path.file_name().unwrap()));
+            }
+        }
+    }
+    for script in SCRIPTS {
+        let dir = Path::new(script).parent().unwrap();
+        copy(&root().join(dir), &workspace.dir().join(dir));
+        scripts.push(PathBuf::from(script));
+    }
+    scripts.sort();
+    scripts
+}
+
+/// The JSON report of a dry run, without timings
+async fn report(executor: &Executor, path: &Path, engine: Engine) -> serde_json::Value {
+    let code = std::fs::read_to_string(path).unwrap();
+    let options = ExecutionOptions::new()
+        .dry_run()
+        .stub_reads()
+        .script_dir(path.parent().unwrap())
+        .engine(engine);
+    let report = executor
+        .execute_with_options(&code, &options)
+        .await
+        .unwrap();
+
+    let mut json = report.to_json();
+    strip_durations(&mut json);
+    mask_clock(json)
+}
+
+fn strip_durations(json: &mut serde_json::Value) {
+    match json {
+        serde_json::Value::Object(map) => {
+            map.remove("duration_ms");
+            map.values_mut().for_each(strip_durations);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_durations),
+        _ => {}
+    }
+}
+
+/// Replace readings of the wall clock, which differ between the two runs of
+/// a script that calls `Date.now()` or formats the current date
+fn mask_clock(json: serde_json::Value) -> serde_json::Value {
+    let clock = Regex::new(r"\b1\d{12}\b|\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?Z").unwrap();
+    serde_json::from_str(&clock.replace_all(&json.to_string(), "0")).unwrap()
+}
+
+#[tokio::test]
+async fn engines_agree_on_scripts() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+
+    let scripts = copy_scripts(&workspace);
+    assert!(!scripts.is_empty(), "no scripts found");
+
+    let mut mismatches = Vec::new();
+    let mut failures = Vec::new();
+    for script in &scripts {
+        let path = workspace.dir().join(script);
+        let tree_walker = report(&executor, &path, Engine::TreeWalker).await;
+        let bytecode = report(&executor, &path, Engine::Bytecode).await;
+        if tree_walker != bytecode {
+            mismatches.push(format!(
+                "{}\n  tree walker: {}\n  bytecode:    {}",
+                script.display(),
+                tree_walker,
+                bytecode
+            ));
+        }
+
+        let expected = EXPECTED_ERRORS
+            .iter()
+            .find(|(name, _)| Path::new(name) == script)
+            .map(|(_, error)| *error);
+        let error = tree_walker["error"]["message"].as_str();
+        let failed_as_expected = match (expected, error) {
+            (None, None) => true,
+            (Some(expected), Some(error)) => error.contains(expected),
+            _ => false,
+        };
+        if !failed_as_expected {
+            failures.push(format!(
+                "{}\n  expected error: {:?}\n  error:          {:?}",
+                script.display(),
+                expected,
+                error
+            ));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "engines disagree on {} of {} scripts:\n{}",
+        mismatches.len(),
+        scripts.len(),
+        mismatches.join("\n")
+    );
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts did not run as expected:\n{}",
+        failures.len(),
+        scripts.len(),
+        failures.join("\n")
+    );
+}
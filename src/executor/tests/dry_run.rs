@@ -0,0 +1,66 @@
+//! Runs scripts without their side effects
+
+mod common;
+
+use std::path::Path;
+
+use common::Workspace;
+use executor::{Capability, Engine, ExecutionOptions};
+use serde_json::json;
+
+#[tokio::test]
+async fn browser_reads_are_stubbed() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι page = 🌐("https://example.com");
+        ι title = 👁(page, "h1");
+        🖱(page, "a.more");
+        ❌(page);
+        title;
+    "#;
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new().dry_run().engine(engine);
+        let report = executor.execute_with_options(code, &options).await.unwrap();
+
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.value.to_json(), json!(""));
+        let effects: Vec<_> = report.effects.iter().map(|e| e.symbol.as_str()).collect();
+        assert_eq!(effects, ["🌐", "🖱", "❌"]);
+    }
+}
+
+/// The examples go on to read files the dry run never wrote, so only the
+/// browser part of them has to succeed
+#[tokio::test]
+async fn browser_examples_dry_run() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples_ai");
+
+    for name in ["browser_automation.a.i", "complete_workflow.a.i"] {
+        let code = std::fs::read_to_string(examples.join(name)).unwrap();
+        let options = ExecutionOptions::new().dry_run();
+        let report = executor
+            .execute_with_options(&code, &options)
+            .await
+            .unwrap();
+
+        let browser: Vec<_> = report
+            .invocations
+            .iter()
+            .filter(|invocation| invocation.capability == Capability::Browser)
+            .collect();
+        assert!(browser.iter().any(|invocation| invocation.symbol == "👁"));
+        assert!(browser.iter().any(|invocation| invocation.symbol == "❌"));
+        for invocation in browser {
+            assert!(
+                invocation.error.is_none(),
+                "{}: {:?}",
+                name,
+                invocation.error
+            );
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Formats code in the canonical style
+
+use std::path::{Path, PathBuf};
+
+use executor::{format_code, parse};
+
+/// Every script in the repository that parses
+fn scripts() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mut scripts = Vec::new();
+    for dir in ["examples_ai", "tests", "src/bin_ai"] {
+        for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.to_string_lossy().ends_with(".a.i") {
+                scripts.push(path);
+            }
+        }
+    }
+    scripts.sort();
+    scripts
+}
+
+/// The syntax tree of `code` as JSON, without locations and comments
+fn shape(code: &str) -> serde_json::Value {
+    fn strip(json: &mut serde_json::Value) {
+        match json {
+            serde_json::Value::Object(map) => {
+                map.remove("span");
+                map.remove("comments");
+                map.values_mut().for_each(strip);
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(strip),
+            _ => {}
+        }
+    }
+
+    let mut json = serde_json::to_value(&parse(code).unwrap().program).unwrap();
+    strip(&mut json);
+    json
+}
+
+#[test]
+fn formatting_is_idempotent_and_keeps_meaning() {
+    let mut formatted_any = false;
+    for path in scripts() {
+        let code = std::fs::read_to_string(&path).unwrap();
+        let Ok(once) = format_code(&code) else {
+            continue;
+        };
+        formatted_any = true;
+
+        let twice = format_code(&once).unwrap();
+        assert_eq!(
+            once,
+            twice,
+            "{} is not formatted idempotently",
+            path.display()
+        );
+        assert_eq!(
+            shape(&code),
+            shape(&once),
+            "{} changed meaning",
+            path.display()
+        );
+    }
+    assert!(formatted_any, "no scripts found");
+}
+
+#[test]
+fn comments_are_kept() {
+    let code = r#"// leading
+ι x = 1;   // trailing
+/* block
+   comment */
+ƒadd(a,b){
+// inside
+⟼ a + /* operand */ b;
+}
+
+
+
+⌽(add(x, 2));
+"#;
+
+    let formatted = format_code(code).unwrap();
+
+    assert_eq!(
+        formatted,
+        r#"// leading
+ι x = 1; // trailing
+/* block
+   comment */
+ƒadd(a, b) {
+    // inside
+    /* operand */
+    ↩ a + b;
+}
+
+⌽(add(x, 2));
+"#
+    );
+    assert_eq!(format_code(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn code_that_does_not_parse_is_not_formatted() {
+    let err = format_code("ι x = (1 + ;").unwrap_err();
+
+    assert!(err.to_string().contains("line 1"), "{}", err);
+}
@@ -0,0 +1,263 @@
+//! Runs the network symbols against a local HTTP server
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use common::Workspace;
+use executor::{Config, ExecutionReport};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request as the test server received it
+struct Request {
+    method: String,
+    target: String,
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// Start a server on a free local port, returning its base URL
+///
+/// - `/echo` answers with the request as JSON
+/// - `/redirect` redirects to `/echo`
+/// - `/status/<code>` answers with that status
+/// - `/slow` answers after two seconds
+/// - `/big` answers with 64 KiB
+/// - `/binary` answers with bytes that are not UTF-8
+async fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(handle(stream));
+        }
+    });
+    format!("http://{}", address)
+}
+
+async fn handle(mut stream: TcpStream) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    let path = request
+        .target
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let (status, headers, body): (u16, Vec<(&str, String)>, Vec<u8>) = match path.as_str() {
+        "/echo" => {
+            let echo = json!({
+                "method": request.method,
+                "target": request.target,
+                "headers": request.headers,
+                "body": String::from_utf8_lossy(&request.body),
+            });
+            (
+                200,
+                vec![("Content-Type", "application/json".to_string())],
+                echo.to_string().into_bytes(),
+            )
+        }
+        "/redirect" => (302, vec![("Location", "/echo".to_string())], Vec::new()),
+        "/slow" => {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            (200, Vec::new(), b"late".to_vec())
+        }
+        "/big" => (200, Vec::new(), vec![b'x'; 64 * 1024]),
+        "/binary" => (200, Vec::new(), vec![0xff, 0x00, 0xfe]),
+        _ => match path.strip_prefix("/status/") {
+            Some(code) => (code.parse().unwrap(), Vec::new(), b"status".to_vec()),
+            None => (404, Vec::new(), b"not found".to_vec()),
+        },
+    };
+
+    let mut response = format!("HTTP/1.1 {} Test\r\n", status);
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.write_all(&body).await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buffer[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?.to_string();
+    let headers: BTreeMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = data[head_end + 4..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buffer[..read]);
+    }
+
+    Some(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+/// Run `code` with `{base}` replaced by the server URL, expecting success
+async fn run(code: &str) -> serde_json::Value {
+    let report = try_run(Config::default(), code).await;
+    assert!(report.is_success(), "script failed: {}", report.to_json());
+    report.value.to_json()
+}
+
+async fn try_run(config: Config, code: &str) -> ExecutionReport {
+    let base = start_server().await;
+    let workspace = Workspace::new();
+    let executor = workspace.build(config);
+    executor.initialize().await.unwrap();
+    executor
+        .execute_code(&code.replace("{base}", &base))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn get_sends_headers_and_query() {
+    let value = run(r#"
+        ι r = ↗("{base}/echo?a=1", { headers: { "X-Test": "yes" }, query: { b: "two words" } });
+        ι echo = ⎋(r.b);
+        [r.s, r.h["content-type"], echo.method, echo.target, echo.headers["x-test"]];
+    "#)
+    .await;
+
+    assert_eq!(
+        value,
+        json!([
+            200,
+            "application/json",
+            "GET",
+            "/echo?a=1&b=two+words",
+            "yes"
+        ])
+    );
+}
+
+#[tokio::test]
+async fn post_sends_strings_as_is_and_values_as_json() {
+    let value = run(r#"
+        ι text = ⎋(↓("{base}/echo", "plain text").b);
+        ι data = ⎋(↓("{base}/echo", { key: [1, 2] }).b);
+        [text.method, text.body, data.headers["content-type"], ⎋(data.body).key];
+    "#)
+    .await;
+
+    assert_eq!(
+        value,
+        json!(["POST", "plain text", "application/json", [1, 2]])
+    );
+}
+
+#[tokio::test]
+async fn request_supports_methods_and_form_bodies() {
+    let value = run(r#"
+        ι r = ~({ method: "put", url: "{base}/echo", form: { name: "a b", n: 1 } });
+        ι echo = ⎋(r.b);
+        [echo.method, echo.headers["content-type"], echo.body];
+    "#)
+    .await;
+
+    assert_eq!(
+        value,
+        json!(["PUT", "application/x-www-form-urlencoded", "n=1&name=a+b"])
+    );
+}
+
+#[tokio::test]
+async fn error_statuses_are_responses() {
+    let value = run(r#"
+        ι r = ↗("{base}/status/404");
+        [r.s, r.b];
+    "#)
+    .await;
+
+    assert_eq!(value, json!([404, "status"]));
+}
+
+#[tokio::test]
+async fn redirects_are_followed_unless_disabled() {
+    let value = run(r#"
+        ι followed = ↗("{base}/redirect");
+        ι returned = ↗("{base}/redirect", { redirects: false });
+        [followed.s, ⎋(followed.b).target, returned.s, returned.h.location];
+    "#)
+    .await;
+
+    assert_eq!(value, json!([200, "/echo", 302, "/echo"]));
+}
+
+#[tokio::test]
+async fn timeouts_and_size_limits_are_catchable() {
+    let value = run(r#"
+        ι kinds = [];
+        ÷{ ↗("{base}/slow", { timeout: 100 }); }⚠(e){ ＋(kinds, e.kind); }
+        ÷{ ↗("{base}/big", { max_bytes: 1024 }); }⚠(e){ ＋(kinds, e.kind, e.symbol); }
+        kinds;
+    "#)
+    .await;
+
+    assert_eq!(value, json!(["Timeout", "Network", "↗"]));
+}
+
+#[tokio::test]
+async fn binary_bodies_are_bytes() {
+    let value = run(r#"
+        ι r = ↗("{base}/binary");
+        [typeof r.b, r.b.length, r.b[0]];
+    "#)
+    .await;
+
+    assert_eq!(value, json!(["bytes", 3, 255]));
+}
+
+#[tokio::test]
+async fn network_symbols_honour_allow_network() {
+    let config = Config {
+        allow_network: false,
+        ..Config::default()
+    };
+    let report = try_run(config, r#"↗("{base}/echo");"#).await;
+
+    let error = report.error.expect("request should be denied");
+    assert_eq!(error.code, executor::codes::PERMISSION_DENIED);
+}
@@ -0,0 +1,80 @@
+//! Aborts scripts that run too long or use too much memory
+
+mod common;
+
+use common::Workspace;
+use executor::{codes, Config, Engine, ExecutionOptions, ExecutionReport};
+
+async fn run(config: Config, code: &str, engine: Engine) -> ExecutionReport {
+    let workspace = Workspace::new();
+    let executor = workspace.build(config);
+    executor.initialize().await.unwrap();
+    executor
+        .execute_with_options(code, &ExecutionOptions::new().engine(engine))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn timeouts_are_reported_where_the_deadline_passed() {
+    let config = Config {
+        max_execution_time_ms: 100,
+        ..Config::default()
+    };
+    let code = "ƒspin() {\n    ι i = 0;\n    while (true) { i = i + 1; }\n}\nspin();\n";
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let report = run(config.clone(), code, engine).await;
+
+        let error = report.error.expect("script should time out");
+        assert_eq!(error.code, codes::TIMEOUT);
+        assert_eq!(error.message, "Execution timed out after 100 ms");
+        assert_eq!(error.span.unwrap().line, 3, "{:?}", engine);
+        assert_eq!(
+            error.to_string(),
+            "Execution timed out after 100 ms at line 3, column 5"
+        );
+    }
+}
+
+#[tokio::test]
+async fn memory_limits_are_reported_at_the_growing_statement() {
+    let config = Config {
+        max_memory_bytes: 64 * 1024,
+        ..Config::default()
+    };
+    let code = "ƒgrow() {\n    ι xs = [];\n    while (true) { ＋(xs, \"0123456789abcdef\"); }\n}\ngrow();\n";
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let report = run(config.clone(), code, engine).await;
+
+        let error = report.error.expect("script should run out of memory");
+        assert_eq!(error.code, codes::MEMORY_LIMIT);
+        assert_eq!(
+            error.message,
+            "Memory limit of 65536 bytes exceeded: ＋(xs, \"0123456789abcdef\")"
+        );
+        assert_eq!(error.span.unwrap().line, 3, "{:?}", engine);
+    }
+}
+
+#[tokio::test]
+async fn growing_arrays_by_index_counts_against_the_memory_limit() {
+    let config = Config {
+        max_memory_bytes: 10 * 1024 * 1024,
+        ..Config::default()
+    };
+    let code = "ι a = [];\na[200000000] = 1;\n";
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let report = run(config.clone(), code, engine).await;
+
+        let error = report.error.expect("script should run out of memory");
+        assert_eq!(error.code, codes::MEMORY_LIMIT);
+        assert_eq!(
+            error.message,
+            "Memory limit of 10485760 bytes exceeded: a[200000000] = 1"
+        );
+        assert_eq!(error.span.unwrap().line, 2, "{:?}", engine);
+    }
+}
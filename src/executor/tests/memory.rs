@@ -0,0 +1,67 @@
+//! Stores, reads and forgets memory entries
+
+mod common;
+
+use common::Workspace;
+use serde_json::json;
+
+#[tokio::test]
+async fn forgotten_keys_cannot_be_read() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        📝("theme", "dark");
+        ι before = 📚("theme");
+        🗑("theme");
+        ι after = "unread";
+        ÷{
+            after = 📚("theme");
+        }⚠(e){
+            after = e.kind;
+        }
+        [before, after];
+    "#;
+
+    let report = executor.execute_code(code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!(["dark", "KeyNotFound"]));
+}
+
+#[tokio::test]
+async fn keys_never_stored_cannot_be_read() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+
+    let report = executor.execute_code(r#"📚("missing");"#).await.unwrap();
+
+    let error = report.error.unwrap();
+    assert!(error.message.contains("missing"), "{}", error.message);
+}
+
+#[tokio::test]
+async fn keys_must_be_strings() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι kinds = [];
+        ∀([42, null, ["theme"]], λ(key){
+            ÷{ 📝(key, "dark"); }⚠(e){ ＋(kinds, e.kind); }
+        });
+        ÷{ 📚("42"); }⚠(e){ ＋(kinds, e.kind); }
+        kinds;
+    "#;
+
+    let report = executor.execute_code(code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(
+        report.value.to_json(),
+        json!([
+            "InvalidArgument",
+            "InvalidArgument",
+            "InvalidArgument",
+            "KeyNotFound"
+        ])
+    );
+}
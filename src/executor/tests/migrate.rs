@@ -0,0 +1,48 @@
+//! Migrates `📖` memory reads written for older versions of the language
+
+mod common;
+
+use common::Workspace;
+use executor::{migrate_code, migrate_code_with_keys};
+use serde_json::json;
+
+#[test]
+fn keys_stored_by_the_script_are_read_from_memory() {
+    let code = r#"
+        📝("theme", "dark");
+        ι key = "theme";
+        [📖("theme"), 📖(key), 📖("notes.txt")];
+    "#;
+
+    let migrated = migrate_code(code).unwrap();
+
+    assert_eq!(
+        migrated,
+        code.replacen(r#"📖("theme")"#, r#"📚("theme")"#, 1)
+    );
+    assert_eq!(migrate_code(&migrated).unwrap(), migrated);
+}
+
+#[tokio::test]
+async fn keys_stored_by_earlier_runs_are_read_from_memory() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let report = executor
+        .execute_code(r#"📝("theme", "dark");"#)
+        .await
+        .unwrap();
+    assert!(report.is_success(), "{}", report.to_json());
+
+    // A later script reads the key without storing it
+    let code = r#"📖("theme");"#;
+    assert_eq!(migrate_code(code).unwrap(), code);
+
+    let keys = vec!["theme".to_string()];
+    let migrated = migrate_code_with_keys(code, &keys).unwrap();
+    assert_eq!(migrated, r#"📚("theme");"#);
+    assert_eq!(migrate_code_with_keys(&migrated, &keys).unwrap(), migrated);
+
+    let report = executor.execute_code(&migrated).await.unwrap();
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!("dark"));
+}
@@ -0,0 +1,121 @@
+//! Imports modules with `⟰`
+
+mod common;
+
+use common::Workspace;
+use executor::{Engine, ExecutionOptions, ExecutionReport, Executor};
+use serde_json::json;
+
+/// Run `code` as a script in the workspace on both engines
+async fn run(workspace: &Workspace, executor: &Executor, code: &str) -> Vec<ExecutionReport> {
+    let mut reports = Vec::new();
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new()
+            .script_dir(workspace.dir())
+            .library_dir(workspace.dir().join("lib"))
+            .engine(engine);
+        reports.push(executor.execute_with_options(code, &options).await.unwrap());
+    }
+    reports
+}
+
+fn write(workspace: &Workspace, path: &str, code: &str) {
+    let path = workspace.dir().join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, code).unwrap();
+}
+
+#[tokio::test]
+async fn modules_run_once_per_run() {
+    let workspace = Workspace::new();
+    write(
+        &workspace,
+        "counter.a.i",
+        r#"⌽("loading counter"); ι state = { n: 0 }; ⟼ state;"#,
+    );
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι a = ⟰("./counter");
+        ι b = ⟰("counter.a.i");
+        a.n = 5;
+        [b.n, a === b];
+    "#;
+
+    for report in run(&workspace, &executor, code).await {
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.output, vec!["loading counter"]);
+        assert_eq!(report.value.to_json(), json!([5, true]));
+    }
+
+    // The cache does not outlive the run
+    for report in run(&workspace, &executor, r#"⟰("counter").n;"#).await {
+        assert_eq!(report.output, vec!["loading counter"]);
+        assert_eq!(report.value.to_json(), json!(0));
+    }
+}
+
+#[tokio::test]
+async fn named_exports_are_found_on_the_library_path() {
+    let workspace = Workspace::new();
+    write(
+        &workspace,
+        "lib/math.a.i",
+        "ƒdouble(x) { ⟼ x * 2; }\nƒhalf(x) { ⟼ x / 2; }\n⟼ double;\n⟼ half;\n",
+    );
+    let executor = workspace.executor().await;
+    let code = r#"[⟰("math")(21), ⟰("math", "half")(21)];"#;
+
+    for report in run(&workspace, &executor, code).await {
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.value.to_json(), json!([42, 10.5]));
+    }
+
+    for report in run(&workspace, &executor, r#"⟰("math", "haf");"#).await {
+        let message = report.error.unwrap().message;
+        assert!(
+            message.ends_with("does not export 'haf', did you mean 'half'?"),
+            "{}",
+            message
+        );
+    }
+}
+
+#[tokio::test]
+async fn import_cycles_are_errors() {
+    let workspace = Workspace::new();
+    write(&workspace, "a.a.i", r#"ι b = ⟰("./b"); ⟼ 1;"#);
+    write(&workspace, "b.a.i", r#"ι a = ⟰("./a"); ⟼ 2;"#);
+    let executor = workspace.executor().await;
+    let dir = workspace.dir().canonicalize().unwrap();
+    let cycle = format!(
+        "Import cycle: {a} -> {b} -> {a}",
+        a = dir.join("a.a.i").display(),
+        b = dir.join("b.a.i").display()
+    );
+
+    let code = r#"ι message = null; ÷{ ⟰("./a"); }⚠(e){ message = e.message; } message;"#;
+    for report in run(&workspace, &executor, code).await {
+        assert!(report.is_success(), "{}", report.to_json());
+        let message = report.value.to_string();
+        assert!(message.contains(&cycle), "{}", message);
+    }
+}
+
+#[tokio::test]
+async fn modules_outside_the_workspace_are_not_imported() {
+    let workspace = Workspace::new();
+    let outside = Workspace::new();
+    let path = outside.dir().join("secret.a.i");
+    std::fs::write(&path, r#"⌽("imported"); ⟼ 1;"#).unwrap();
+    let executor = workspace.executor().await;
+
+    let code = format!(
+        r#"ι kind = null; ÷{{ ⟰("{}"); }}⚠(e){{ kind = e.kind; }} kind;"#,
+        path.display()
+    );
+    for report in run(&workspace, &executor, &code).await {
+        assert!(report.is_success(), "{}", report.to_json());
+        assert!(report.output.is_empty(), "{:?}", report.output);
+        assert_eq!(report.value.to_json(), json!("PathNotAllowed"));
+    }
+}
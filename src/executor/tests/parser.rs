@@ -0,0 +1,161 @@
+//! Parses code through the public parser API
+
+mod common;
+
+use common::Workspace;
+use executor::ast::{BinaryOp, ExprKind, StmtKind};
+use executor::{
+    analyze_code, check_syntax, codes, format_code, parse, Engine, ExecutionOptions, MAX_ELSE_IFS,
+    MAX_NESTING,
+};
+use serde_json::json;
+
+/// `depth` parentheses around `1`
+fn parenthesized(depth: usize) -> String {
+    format!("{}1{};", "(".repeat(depth), ")".repeat(depth))
+}
+
+/// An if statement with `branches` else-if branches
+fn else_ifs(branches: usize) -> String {
+    format!(
+        "if (false) {{}}{} else {{ ⌽(1); }}",
+        " else if (false) {}".repeat(branches)
+    )
+}
+
+/// `depth` nested blocks of if statements around `⌽(1);`
+fn nested_ifs(depth: usize) -> String {
+    format!(
+        "{}⌽(1);{}",
+        "if (true) { ".repeat(depth),
+        " }".repeat(depth)
+    )
+}
+
+#[test]
+fn binary_operators_follow_precedence() {
+    let parsed = parse("1 + 2 * 3;").unwrap();
+
+    let StmtKind::Expr(expr) = &parsed.program.body[0].kind else {
+        panic!("expected an expression statement");
+    };
+    let ExprKind::Binary { op, right, .. } = &expr.kind else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!(*op, BinaryOp::Add);
+    assert!(matches!(
+        right.kind,
+        ExprKind::Binary {
+            op: BinaryOp::Mul,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn syntax_errors_have_spans() {
+    let diagnostics = check_syntax("ι x = 1;\nι y = (2 + ;");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, codes::SYNTAX_ERROR);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 12));
+}
+
+#[test]
+fn shell_commands_may_be_written_without_quotes() {
+    for (code, command) in [
+        ("!(exit 1);", "exit 1"),
+        ("ι r = !(git status --short);", "git status --short"),
+        ("↪(x) { !(echo done (1)); }", "echo done (1)"),
+    ] {
+        let formatted = format_code(code).unwrap();
+        assert!(
+            formatted.contains(&format!("!(\"{}\")", command)),
+            "{}",
+            formatted
+        );
+    }
+
+    // A single word is still an expression
+    let parsed = parse("ι x = !(done);").unwrap();
+    let StmtKind::Let { init, .. } = &parsed.program.body[0].kind else {
+        panic!("expected a let statement");
+    };
+    assert!(matches!(
+        init.as_ref().unwrap().kind,
+        ExprKind::Unary { .. }
+    ));
+}
+
+#[test]
+fn deep_nesting_is_a_syntax_error() {
+    for code in [
+        parenthesized(MAX_NESTING * 3),
+        nested_ifs(MAX_NESTING * 3),
+        format!("{}1;", "-".repeat(100_000)),
+        format!(
+            "`{}1{}`;",
+            "${`".repeat(MAX_NESTING),
+            "`}".repeat(MAX_NESTING)
+        ),
+    ] {
+        let diagnostics = check_syntax(&code);
+
+        assert_eq!(diagnostics.len(), 1, "{}", code);
+        assert!(
+            diagnostics[0].message.contains("nested more than"),
+            "{}",
+            diagnostics[0].message
+        );
+        assert!(diagnostics[0].span.is_some());
+        assert!(format_code(&code).is_err());
+    }
+}
+
+#[test]
+fn long_else_if_chains_are_a_syntax_error() {
+    let diagnostics = check_syntax(&else_ifs(100_000));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        format!(
+            "If statement has more than {} else-if branches",
+            MAX_ELSE_IFS
+        )
+    );
+}
+
+#[tokio::test]
+async fn code_nested_to_the_limit_still_runs() {
+    // The statement and the operand of `⌽` or of the expression statement
+    // take up two levels
+    let depth = MAX_NESTING - 2;
+    assert!(!check_syntax(&parenthesized(depth + 1)).is_empty());
+    assert!(!check_syntax(&nested_ifs(depth + 1)).is_empty());
+
+    assert!(!check_syntax(&else_ifs(MAX_ELSE_IFS + 1)).is_empty());
+
+    let code = format!(
+        "{}\n{}\n{}",
+        else_ifs(MAX_ELSE_IFS),
+        nested_ifs(depth),
+        parenthesized(depth)
+    );
+    assert!(check_syntax(&code).is_empty());
+    assert!(format_code(&code).is_ok());
+    analyze_code(&code, &[]);
+
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new().engine(engine);
+        let report = executor
+            .execute_with_options(&code, &options)
+            .await
+            .unwrap();
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.value.to_json(), json!(1));
+    }
+}
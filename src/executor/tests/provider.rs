@@ -0,0 +1,146 @@
+//! Registers symbols from outside the executor through `SymbolProvider`
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::Workspace;
+use executor::{
+    codes, Capability, CapabilityProfile, Config, ExecutionOptions, Executor, SymbolProvider,
+    SymbolRegistrar, SymbolSignature, Value, ValueType,
+};
+use serde_json::json;
+
+/// Splits CSV text into rows and opens tickets, counting the tickets opened
+#[derive(Default)]
+struct Tools {
+    tickets: Arc<AtomicUsize>,
+}
+
+impl SymbolProvider for Tools {
+    fn name(&self) -> &str {
+        "tools"
+    }
+
+    fn register(&self, symbols: &mut SymbolRegistrar<'_>) -> Result<()> {
+        let signature = SymbolSignature::new("📊", Capability::UserIo)
+            .param("text", ValueType::String)
+            .returns(ValueType::Array)
+            .help("Split CSV text into rows of fields");
+        symbols.register(signature, |args| async move {
+            let rows = args[0]
+                .to_string()
+                .lines()
+                .map(|line| Value::from(json!(line.split(',').collect::<Vec<_>>())))
+                .collect::<Vec<_>>();
+            Ok(Value::from(rows))
+        })?;
+
+        let tickets = Arc::clone(&self.tickets);
+        let signature = SymbolSignature::new("🎫", Capability::Network)
+            .param("title", ValueType::String)
+            .returns(ValueType::Number)
+            .side_effects()
+            .help("Open a ticket and return its number");
+        symbols.register(signature, move |_args| {
+            let tickets = Arc::clone(&tickets);
+            async move {
+                Ok(Value::Number(
+                    tickets.fetch_add(1, Ordering::SeqCst) as f64 + 1.0,
+                ))
+            }
+        })
+    }
+}
+
+/// Tries to take a name the executor already uses
+struct Clashing;
+
+impl SymbolProvider for Clashing {
+    fn name(&self) -> &str {
+        "clashing"
+    }
+
+    fn register(&self, symbols: &mut SymbolRegistrar<'_>) -> Result<()> {
+        let signature = SymbolSignature::new("📂", Capability::FileRead);
+        symbols.register(signature, |_args| async move { Ok(Value::Null) })
+    }
+}
+
+async fn executor(
+    workspace: &Workspace,
+    provider: impl SymbolProvider + 'static,
+) -> Result<Executor> {
+    let mut executor = workspace.build(Config::default());
+    executor.add_provider(Arc::new(provider));
+    executor.initialize().await?;
+    Ok(executor)
+}
+
+#[tokio::test]
+async fn provider_symbols_can_be_called() {
+    let tools = Tools::default();
+    let tickets = Arc::clone(&tools.tickets);
+    let workspace = Workspace::new();
+    let executor = executor(&workspace, tools).await.unwrap();
+
+    let report = executor
+        .execute_code(r#"ι rows = 📊("a,b\nc,d"); [rows[1][0], 🎫("Broken"), 🎫("Also broken")];"#)
+        .await
+        .unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!(["c", 1, 2]));
+    assert_eq!(tickets.load(Ordering::SeqCst), 2);
+
+    let signatures = executor.symbol_signatures();
+    let csv = signatures.iter().find(|s| s.name == "📊").unwrap();
+    assert_eq!(csv.help, "Split CSV text into rows of fields");
+}
+
+#[tokio::test]
+async fn provider_symbols_follow_capabilities() {
+    let workspace = Workspace::new();
+    let executor = executor(&workspace, Tools::default()).await.unwrap();
+    let profile = CapabilityProfile::all().deny(Capability::Network);
+    let options = ExecutionOptions::new().capabilities(profile);
+
+    let report = executor
+        .execute_with_options(r#"🎫("Broken");"#, &options)
+        .await
+        .unwrap();
+
+    assert_eq!(report.error.unwrap().code, codes::PERMISSION_DENIED);
+}
+
+#[tokio::test]
+async fn dry_runs_record_provider_effects() {
+    let tools = Tools::default();
+    let tickets = Arc::clone(&tools.tickets);
+    let workspace = Workspace::new();
+    let executor = executor(&workspace, tools).await.unwrap();
+    let options = ExecutionOptions::new().dry_run();
+
+    let report = executor
+        .execute_with_options(r#"🎫("Broken");"#, &options)
+        .await
+        .unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.effects.len(), 1);
+    assert_eq!(report.effects[0].symbol, "🎫");
+    assert_eq!(tickets.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn providers_cannot_replace_built_in_symbols() {
+    let workspace = Workspace::new();
+    let err = executor(&workspace, Clashing).await.err().unwrap();
+
+    assert_eq!(
+        err.to_string(),
+        "Symbol registration error: Symbol '📂' is already registered (provider 'clashing')"
+    );
+}
@@ -0,0 +1,110 @@
+//! Replays recorded runs from their traces
+
+mod common;
+
+use common::Workspace;
+use executor::{codes, Engine, ExecutionOptions, ExecutionReport, Executor, Trace};
+
+/// Run `code`, returning its trace
+async fn record(executor: &Executor, code: &str) -> Trace {
+    let options = ExecutionOptions::new().record();
+    let report = executor.execute_with_options(code, &options).await.unwrap();
+    assert!(report.is_success(), "{}", report.to_json());
+    report.trace.unwrap()
+}
+
+async fn replay(executor: &Executor, code: &str, trace: &Trace) -> Vec<ExecutionReport> {
+    let mut reports = Vec::new();
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new().replay(trace.clone()).engine(engine);
+        reports.push(executor.execute_with_options(code, &options).await.unwrap());
+    }
+    reports
+}
+
+#[tokio::test]
+async fn replays_return_recorded_results() {
+    let workspace = Workspace::new();
+    std::fs::write(workspace.dir().join("note.txt"), "recorded").unwrap();
+    let executor = workspace.executor().await;
+    let code = r#"⌽(📖("note.txt"));"#;
+    let trace = record(&executor, code).await;
+
+    // The replay must not read the file again
+    std::fs::write(workspace.dir().join("note.txt"), "changed").unwrap();
+    for report in replay(&executor, code, &trace).await {
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.output, vec!["recorded"]);
+    }
+}
+
+#[tokio::test]
+async fn traces_survive_a_round_trip_through_a_file() {
+    let workspace = Workspace::new();
+    std::fs::write(workspace.dir().join("note.txt"), "recorded").unwrap();
+    let executor = workspace.executor().await;
+    let code = r#"⌽(📖("note.txt"));"#;
+    let path = workspace.dir().join("run.trace.json");
+    record(&executor, code).await.save(&path).unwrap();
+
+    let trace = Trace::load(&path).unwrap();
+
+    assert_eq!(trace.code, code);
+    assert_eq!(trace.calls.len(), 1);
+    assert_eq!(trace.calls[0].symbol, "📖");
+}
+
+#[tokio::test]
+async fn different_calls_diverge() {
+    let workspace = Workspace::new();
+    std::fs::write(workspace.dir().join("a.txt"), "a").unwrap();
+    let executor = workspace.executor().await;
+    let trace = record(&executor, r#"📖("a.txt");"#).await;
+
+    for report in replay(&executor, "\n📖(\"b.txt\");", &trace).await {
+        let error = report.error.expect("replay should diverge");
+        assert_eq!(error.code, codes::REPLAY_DIVERGENCE);
+        assert_eq!(
+            error.message,
+            r#"Replay diverged after 0 recorded calls: expected 📖("a.txt"), got 📖("b.txt")"#
+        );
+        assert_eq!(error.span.unwrap().line, 2);
+    }
+}
+
+#[tokio::test]
+async fn missing_and_extra_calls_diverge() {
+    let workspace = Workspace::new();
+    std::fs::write(workspace.dir().join("a.txt"), "a").unwrap();
+    let executor = workspace.executor().await;
+    let trace = record(&executor, r#"📖("a.txt");"#).await;
+
+    for report in replay(&executor, "⌽(1);", &trace).await {
+        assert_eq!(
+            report.error.unwrap().message,
+            r#"Replay diverged after 0 recorded calls: expected 📖("a.txt"), got the end of the script"#
+        );
+    }
+
+    let code = r#"📖("a.txt"); 📖("a.txt");"#;
+    for report in replay(&executor, code, &trace).await {
+        assert_eq!(
+            report.error.unwrap().message,
+            r#"Replay diverged after 1 recorded calls: expected the end of the trace, got 📖("a.txt")"#
+        );
+    }
+}
+
+#[tokio::test]
+async fn divergence_cannot_be_caught() {
+    let workspace = Workspace::new();
+    std::fs::write(workspace.dir().join("a.txt"), "a").unwrap();
+    let executor = workspace.executor().await;
+    let trace = record(&executor, r#"📖("a.txt");"#).await;
+
+    let code = r#"÷{ 📖("b.txt"); }⚠(e){ ⌽("caught"); }"#;
+    for report in replay(&executor, code, &trace).await {
+        assert!(report.output.is_empty());
+        assert_eq!(report.error.unwrap().code, codes::REPLAY_DIVERGENCE);
+    }
+}
@@ -0,0 +1,72 @@
+//! Keeps file symbols inside the workspace when it contains symbolic links
+
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::fs::symlink;
+
+use common::Workspace;
+use serde_json::json;
+
+/// The kind of error each call in `calls` fails with, or its result
+const KINDS: &str = r#"
+    ι kinds = [];
+    ∀(calls, λ(call){
+        ÷{
+            ＋(kinds, call());
+        }⚠(e){
+            ＋(kinds, e.kind);
+        }
+    });
+    kinds;
+"#;
+
+#[tokio::test]
+async fn links_out_of_the_workspace_are_not_followed() {
+    let workspace = Workspace::new();
+    let outside = Workspace::new();
+    std::fs::write(outside.dir().join("secret.txt"), "secret").unwrap();
+    symlink(outside.dir(), workspace.dir().join("link")).unwrap();
+    symlink(
+        outside.dir().join("missing.txt"),
+        workspace.dir().join("dangling"),
+    )
+    .unwrap();
+    let executor = workspace.executor().await;
+
+    let code = format!(
+        r#"
+        ι calls = [
+            λ(){{ ⟼ 📖("link/secret.txt"); }},
+            λ(){{ ⟼ ✍("link/new.txt", "x"); }},
+            λ(){{ ⟼ ✍("dangling", "x"); }},
+            λ(){{ ⟼ 📂("link"); }}
+        ];
+        {}"#,
+        KINDS
+    );
+    let report = executor.execute_code(&code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!(vec!["PathNotAllowed"; 4]));
+    assert!(!outside.dir().join("new.txt").exists());
+    assert!(!outside.dir().join("missing.txt").exists());
+}
+
+#[tokio::test]
+async fn links_within_the_workspace_are_followed() {
+    let workspace = Workspace::new();
+    std::fs::create_dir(workspace.dir().join("data")).unwrap();
+    std::fs::write(workspace.dir().join("data/notes.txt"), "notes").unwrap();
+    symlink(workspace.dir().join("data"), workspace.dir().join("link")).unwrap();
+    let executor = workspace.executor().await;
+
+    let report = executor
+        .execute_code(r#"📖("link/notes.txt");"#)
+        .await
+        .unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!("notes"));
+}
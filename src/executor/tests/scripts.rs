@@ -0,0 +1,153 @@
+//! Checks the scripts the agent itself is written in
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::Workspace;
+use executor::{
+    analyze_code, check_syntax, render_all, Engine, ExecutionOptions, Executor, Severity, Trace,
+};
+use serde_json::json;
+
+const SCRIPTS: &[&str] = &[
+    "main.a.i",
+    "main_updated.a.i",
+    "string_dictionary.a.i",
+    "string_dictionary_updated.a.i",
+];
+
+fn bin_ai() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../bin_ai")
+}
+
+#[tokio::test]
+async fn scripts_pass_the_analyzer() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let symbols = executor.symbol_signatures();
+
+    for script in SCRIPTS {
+        let code = std::fs::read_to_string(bin_ai().join(script)).unwrap();
+        let syntax = check_syntax(&code);
+        assert!(
+            syntax.is_empty(),
+            "{}:\n{}",
+            script,
+            render_all(&syntax, &code)
+        );
+
+        let errors: Vec<_> = analyze_code(&code, &symbols)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect();
+        assert!(
+            errors.is_empty(),
+            "{}:\n{}",
+            script,
+            render_all(&errors, &code)
+        );
+    }
+}
+
+/// A copy of the scripts in the workspace, where the sandbox lets them
+/// read and write
+fn copy_bin_ai(workspace: &Workspace) -> PathBuf {
+    fn copy(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            let target = to.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+
+    let dir = workspace.dir().join("bin_ai");
+    copy(&bin_ai(), &dir);
+    dir
+}
+
+/// Run the dictionary scripts, which save their strings with `💾`
+async fn save_dictionaries(executor: &Executor, dir: &Path) {
+    let options = ExecutionOptions::new().script_dir(dir);
+    for script in ["string_dictionary.a.i", "string_dictionary_updated.a.i"] {
+        let code = std::fs::read_to_string(dir.join(script)).unwrap();
+        let report = executor
+            .execute_with_options(&code, &options)
+            .await
+            .unwrap();
+        assert!(report.is_success(), "{}: {}", script, report.to_json());
+    }
+}
+
+#[tokio::test]
+async fn dictionary_scripts_add_to_the_dictionary() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let dir = copy_bin_ai(&workspace);
+    save_dictionaries(&executor, &dir).await;
+
+    let options = ExecutionOptions::new().script_dir(&dir);
+    let code = r#"
+        🔠("string_dictionary.json");
+        [:agent_title, :core_agent_module, :shell_initialized];
+    "#;
+    let report = executor.execute_with_options(code, &options).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(
+        report.value.to_json(),
+        json!([
+            "Anarchy Agent - A fully local, cross-platform AI assistant",
+            "anarchy_agent_core/agent",
+            "Shell module initialized successfully"
+        ])
+    );
+}
+
+/// The system has no real shell behind `!` yet, so a trace answers the
+/// `!("echo $@")` that reads the arguments with none
+#[tokio::test]
+async fn main_prints_its_usage_without_arguments() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let dir = copy_bin_ai(&workspace);
+    save_dictionaries(&executor, &dir).await;
+
+    for script in ["main.a.i", "main_updated.a.i"] {
+        let code = std::fs::read_to_string(dir.join(script)).unwrap();
+        let mut trace = Trace::new(code.clone());
+        trace.calls = serde_json::from_value(json!([{
+            "symbol": "!",
+            "args": ["echo $@"],
+            "result": { "o": "", "e": "", "c": 0 },
+            "line": 20
+        }]))
+        .unwrap();
+
+        for engine in [Engine::TreeWalker, Engine::Bytecode] {
+            let options = ExecutionOptions::new()
+                .script_dir(&dir)
+                .replay(trace.clone())
+                .engine(engine);
+            let report = executor
+                .execute_with_options(&code, &options)
+                .await
+                .unwrap();
+
+            assert!(report.is_success(), "{}: {}", script, report.to_json());
+            assert_eq!(
+                report.output.first().map(String::as_str),
+                Some("Anarchy Agent - A fully local, cross-platform AI assistant")
+            );
+            assert_eq!(
+                report.output.last().map(String::as_str),
+                Some("  anarchy-agent --repl")
+            );
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Runs shell commands with `!`
+
+mod common;
+
+use common::Workspace;
+use serde_json::json;
+
+#[tokio::test]
+async fn commands_must_be_strings() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι errors = [];
+        ∀([42, ["echo", "hi"], null], λ(command){
+            ÷{ !(command); }⚠(e){ ＋(errors, [e.kind, e.message]); }
+        });
+        errors;
+    "#;
+
+    let report = executor.execute_code(code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    let errors = report.value.to_json();
+    for (error, type_name) in errors
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(["number", "object", "null"])
+    {
+        assert_eq!(error[0], json!("InvalidArgument"));
+        let message = error[1].as_str().unwrap();
+        assert!(
+            message.contains(&format!(
+                "! expects command to be a string, got {}",
+                type_name
+            )),
+            "{}",
+            message
+        );
+    }
+    assert_eq!(errors.as_array().unwrap().len(), 3);
+}
@@ -0,0 +1,47 @@
+//! Loads strings for `:key` references with `🔠`
+
+mod common;
+
+use common::Workspace;
+use executor::ExecutionOptions;
+use serde_json::json;
+
+#[tokio::test]
+async fn dictionaries_outside_the_workspace_are_not_read() {
+    let workspace = Workspace::new();
+    let outside = Workspace::new();
+    let path = outside.dir().join("strings.json");
+    std::fs::write(&path, r#"{"greeting": "hello"}"#).unwrap();
+    let executor = workspace.executor().await;
+
+    let code = format!(
+        r#"ι kind = null; ÷{{ 🔠("{}"); }}⚠(e){{ kind = e.kind; }} kind;"#,
+        path.display()
+    );
+    let report = executor.execute_code(&code).await.unwrap();
+
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!("PathNotAllowed"));
+}
+
+#[tokio::test]
+async fn dry_runs_stubbing_reads_load_no_dictionary() {
+    let workspace = Workspace::new();
+    std::fs::write(
+        workspace.dir().join("strings.json"),
+        r#"{"greeting": "hello"}"#,
+    )
+    .unwrap();
+    let executor = workspace.executor().await;
+    let code = r#"[🔠("strings.json"), :greeting];"#;
+
+    let options = ExecutionOptions::new().script_dir(workspace.dir());
+    let report = executor.execute_with_options(code, &options).await.unwrap();
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!([1, "hello"]));
+
+    let options = options.dry_run().stub_reads();
+    let report = executor.execute_with_options(code, &options).await.unwrap();
+    assert!(report.is_success(), "{}", report.to_json());
+    assert_eq!(report.value.to_json(), json!([0, ""]));
+}
@@ -0,0 +1,107 @@
+//! Converts values to text and JSON and grows arrays by assignment
+
+mod common;
+
+use common::Workspace;
+use executor::{Config, Engine, ExecutionOptions, Value, MAX_ARRAY_LENGTH};
+use serde_json::json;
+
+#[test]
+fn cycles_become_circular_markers() {
+    let array = Value::array(Vec::new());
+    let Value::Array(items) = &array else {
+        unreachable!()
+    };
+    items.lock().unwrap().push(Value::from(1.0));
+    items.lock().unwrap().push(array.clone());
+
+    assert_eq!(array.to_json(), json!([1, "[Circular]"]));
+    assert_eq!(array.to_string(), r#"[1,"[Circular]"]"#);
+}
+
+#[test]
+fn shared_values_that_are_not_cycles_are_repeated() {
+    let shared = Value::from(json!({ "a": 1 }));
+    let value = Value::array(vec![shared.clone(), shared]);
+
+    assert_eq!(value.to_json(), json!([{ "a": 1 }, { "a": 1 }]));
+}
+
+#[test]
+fn large_whole_numbers_are_printed_in_full() {
+    for (n, text) in [
+        (9007199254740993.0, "9007199254740992"),
+        (1e20, "100000000000000000000"),
+        (-1e20, "-100000000000000000000"),
+        (1e300, &format!("1{}", "0".repeat(300))),
+        (-0.0, "0"),
+    ] {
+        assert_eq!(Value::from(n).to_string(), text);
+    }
+}
+
+#[tokio::test]
+async fn scripts_can_print_and_encode_cycles() {
+    let workspace = Workspace::new();
+    let executor = workspace.executor().await;
+    let code = r#"
+        ι a = [];
+        ＋(a, a);
+        ⌽(a);
+        ι o = { name: "o" };
+        o.self = o;
+        [⎋(o), a];
+    "#;
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new().engine(engine).record();
+        let report = executor.execute_with_options(code, &options).await.unwrap();
+
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.output, vec![r#"["[Circular]"]"#]);
+        assert_eq!(
+            report.value.to_json(),
+            json!([r#"{"name":"o","self":"[Circular]"}"#, ["[Circular]"]])
+        );
+        assert!(report.trace.is_some());
+    }
+}
+
+#[tokio::test]
+async fn arrays_grow_by_assignment_up_to_a_limit() {
+    // Without a memory limit only the length limit stops the growth
+    let config = Config {
+        max_memory_bytes: 0,
+        ..Config::default()
+    };
+    let workspace = Workspace::new();
+    let executor = workspace.build(config);
+    executor.initialize().await.unwrap();
+    let grown = format!("ι a = []; a[{}] = 1; a.length;", MAX_ARRAY_LENGTH - 1);
+
+    for engine in [Engine::TreeWalker, Engine::Bytecode] {
+        let options = ExecutionOptions::new().engine(engine);
+        let report = executor
+            .execute_with_options(&grown, &options)
+            .await
+            .unwrap();
+        assert!(report.is_success(), "{}", report.to_json());
+        assert_eq!(report.value.to_json(), json!(MAX_ARRAY_LENGTH));
+
+        for index in ["1e300", "200000000"] {
+            let code = format!("ι a = [];\na[{}] = 1;", index);
+            let report = executor
+                .execute_with_options(&code, &options)
+                .await
+                .unwrap();
+
+            let error = report.error.unwrap();
+            let message = format!(
+                "is too large, arrays can grow to {} items by assignment",
+                MAX_ARRAY_LENGTH
+            );
+            assert!(error.message.ends_with(&message), "{}", error.message);
+            assert_eq!(error.span.unwrap().line, 2);
+        }
+    }
+}
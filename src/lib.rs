@@ -2,14 +2,15 @@
 // This file re-exports all the modules for easy access
 
 pub mod core {
-    pub use core::Agent;
+    pub use core::{Agent, AgentBuilder};
     pub use core::Config;
     pub use core::Error;
-    pub use core::Core;
+    pub use core::{Core, CoreBuilder};
 }
 
 pub mod planner {
     pub use planner::Planner;
+    pub use planner::migrate_patterns;
     pub use planner::Config;
     pub use planner::Error;
 }
@@ -18,6 +19,13 @@ pub mod executor {
     pub use executor::Executor;
     pub use executor::Config;
     pub use executor::Error;
+    pub use executor::{Capability, CapabilityProfile, Engine, ExecutionOptions, SymbolSignature};
+    pub use executor::{SymbolProvider, SymbolRegistrar, Value, ValueType};
+    pub use executor::{Effect, ExecutionReport, ReportError, SymbolInvocation};
+    pub use executor::{Diagnostic, Severity};
+    pub use executor::{check_syntax, format_code, migrate_code, migrate_code_with_keys};
+    pub use executor::{Trace, TracedCall};
+    pub use executor::{DebugAction, Debugger, Pause, PauseReason, StackFrame, TerminalDebugger};
 }
 
 pub mod memory {
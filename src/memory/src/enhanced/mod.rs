@@ -3,5 +3,5 @@ mod database;
 mod memory;
 
 pub use memory::EnhancedMemory;
-pub use database::EnhancedDatabase;
+pub use database::{EnhancedDatabase, EnhancedMemoryEntry};
 pub use vector_store::{VectorStore, MemoryVector};
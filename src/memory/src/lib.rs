@@ -11,7 +11,7 @@ mod enhanced;
 
 pub use config::Config;
 pub use error::Error;
-pub use enhanced::EnhancedMemory;
+pub use enhanced::{EnhancedMemory, EnhancedMemoryEntry};
 
 /// Memory module that stores and retrieves information from previous executions
 pub struct Memory {
@@ -54,9 +54,13 @@ impl Memory {
     }
     
     /// Store a task result in memory
-    pub async fn store_result(&self, task: &str, code: &str, result: &str) -> Result<()> {
+    ///
+    /// `report` is the structured execution report; it is persisted as JSON.
+    pub async fn store_result(&self, task: &str, code: &str, report: &serde_json::Value) -> Result<()> {
+        let result = report.to_string();
+        
         // Store in basic database
-        self.db.store_execution(task, code, result)?;
+        self.db.store_execution(task, code, &result)?;
         
         // Store in enhanced memory if available
         if let Some(enhanced) = &self.enhanced {
@@ -64,7 +68,7 @@ impl Memory {
             let tags = vec!["execution".to_string()];
             let importance = 50; // Medium importance
             
-            enhanced.store_execution(task, code, result, tags, importance)?;
+            enhanced.store_execution(task, code, &result, tags, importance)?;
         }
         
         Ok(())
@@ -97,17 +101,17 @@ impl Memory {
         Ok(())
     }
     
-    /// Retrieve a value by key from memory (for Anarchy-Inference 📖 symbol)
+    /// Retrieve a value by key from memory (for Anarchy-Inference 📚 symbol)
+    ///
+    /// Fails with `Error::KeyNotFoundError` if the key was never stored or
+    /// has been deleted.
     pub async fn get_memory(&self, key: &str) -> Result<String> {
-        // Try enhanced memory first if available
+        // Enhanced memory holds every key stored while it is enabled, so a
+        // key it does not have is missing rather than in the basic database
         if let Some(enhanced) = &self.enhanced {
-            match enhanced.get_key_value(key) {
-                Ok(value) => return Ok(value),
-                Err(_) => {} // Fall back to basic database
-            }
+            return enhanced.get_key_value(key);
         }
         
-        // Fall back to basic database
         let value = self.db.get_key_value(key)?;
         Ok(value)
     }
@@ -126,7 +130,10 @@ impl Memory {
     }
     
     /// Search memory by tags (for Anarchy-Inference 🏷️ symbol)
-    pub async fn search_by_tags(&self, tags: &[String]) -> Result<String> {
+    ///
+    /// Returns the entries that have all of the tags, at most
+    /// `max_search_results` of them.
+    pub async fn search_by_tags(&self, tags: &[String]) -> Result<Vec<EnhancedMemoryEntry>> {
         // If enhanced memory is available, use it for tag search
         if let Some(enhanced) = &self.enhanced {
             let results = enhanced.search_by_tags(tags)?;
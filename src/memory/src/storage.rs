@@ -162,10 +162,8 @@ impl Database {
         // let mut stmt = conn.prepare("SELECT value FROM key_values WHERE key = ?")?;
         // let value: String = stmt.query_row([key], |row| row.get(0))?;
         
-        // For now, just return a placeholder
-        let value = format!("Value for key: {}", key);
-        
-        Ok(value)
+        // For now nothing is stored, so no key is found
+        Err(Error::KeyNotFoundError(key.to_string()).into())
     }
     
     /// Delete a key-value pair
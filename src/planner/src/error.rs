@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use executor::Diagnostic;
+
 /// Error types for the Planner module
 #[derive(Error, Debug)]
 pub enum Error {
@@ -15,6 +17,15 @@ pub enum Error {
     #[error("Validation error: {0}")]
     ValidationError(String),
     
+    /// Generated code has problems at known locations
+    #[error("Generated code is invalid:\n{rendered}")]
+    InvalidCode {
+        diagnostics: Vec<Diagnostic>,
+        
+        /// The diagnostics rendered against the generated code
+        rendered: String,
+    },
+    
     #[error("Memory access error: {0}")]
     MemoryError(String),
     
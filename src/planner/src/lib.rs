@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::fs;
@@ -14,14 +14,49 @@ pub use config::Config;
 pub use error::Error;
 pub use reasoning::{Pattern, ReasoningSystem};
 
+use executor::SymbolSignature;
 use memory::Memory;
 
+/// Migrate the templates of the patterns in `patterns_dir`
+///
+/// Rewrites the templates as [`executor::migrate_code_with_keys`] does,
+/// treating `memory_keys` as stored by earlier runs. Returns the pattern
+/// files whose templates change; they are only rewritten if `write` is set.
+pub fn migrate_patterns(patterns_dir: &Path, memory_keys: &[String], write: bool) -> Result<Vec<PathBuf>> {
+    let mut migrated = Vec::new();
+    
+    for entry in fs::read_dir(patterns_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        
+        let mut pattern: Pattern = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let template = executor::migrate_code_with_keys(&pattern.template, memory_keys)?;
+        if template == pattern.template {
+            continue;
+        }
+        
+        if write {
+            pattern.template = template;
+            fs::write(&path, serde_json::to_string_pretty(&pattern)?)?;
+        }
+        migrated.push(path);
+    }
+    
+    migrated.sort();
+    Ok(migrated)
+}
+
 /// Planner module that generates Anarchy-Inference code from task descriptions
 pub struct Planner {
     config: Config,
     llm: llm::LlmEngine,
     memory: Arc<Mutex<Memory>>,
     reasoning: reasoning::ReasoningSystem,
+    
+    /// Symbols generated code may call, used to validate it
+    symbols: Vec<SymbolSignature>,
 }
 
 impl Planner {
@@ -41,6 +76,7 @@ impl Planner {
             llm,
             memory,
             reasoning,
+            symbols: Vec::new(),
         })
     }
     
@@ -52,6 +88,13 @@ impl Planner {
         Ok(())
     }
     
+    /// Set the symbols generated code may call
+    ///
+    /// Until this is called every symbol call is reported as unknown.
+    pub fn set_symbols(&mut self, symbols: Vec<SymbolSignature>) {
+        self.symbols = symbols;
+    }
+    
     /// Generate Anarchy-Inference code for a given task description
     pub async fn generate_plan(&self, task_description: &str) -> Result<String> {
         // 1. Retrieve relevant context from memory
@@ -62,25 +105,33 @@ impl Planner {
         // 2. Process the task with the reasoning system
         let (code, reasoning) = self.reasoning.process_task_with_reasoning(task_description)?;
         
-        // 3. If the reasoning system produced valid code, return it
-        if Self::is_valid_code(&code) {
-            return Ok(code);
+        // 3. If the reasoning system produced valid code, return it formatted
+        if self.is_valid_code(&code) {
+            return Ok(executor::format_code(&code).unwrap_or(code));
         }
         
         // 4. Otherwise, fall back to LLM-based generation
         let prompt = planning::create_prompt(task_description, &context, &self.config);
         let anarchy_code = self.llm.generate(&prompt)?;
         
-        // 5. Validate the generated code
-        let validated_code = planning::validate_code(&anarchy_code)?;
-        
-        Ok(validated_code)
+        // 5. Validate the generated code, giving the LLM one chance to fix it
+        match planning::validate_code(&anarchy_code, &self.symbols) {
+            Ok(validated_code) => Ok(validated_code),
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::InvalidCode { diagnostics, rendered }) => {
+                    log::debug!("Generated code is invalid, asking for a fix:\n{}", rendered);
+                    let prompt = planning::create_repair_prompt(task_description, &anarchy_code, diagnostics, &self.config);
+                    let repaired_code = self.llm.generate(&prompt)?;
+                    planning::validate_code(&repaired_code, &self.symbols)
+                }
+                _ => Err(err),
+            },
+        }
     }
     
     /// Check if the generated code is valid
-    fn is_valid_code(code: &str) -> bool {
-        // Basic validation - check for function definition and return statement
-        code.contains("ƒ") && (code.contains("⟼") || code.contains("⌽"))
+    fn is_valid_code(&self, code: &str) -> bool {
+        !executor::has_errors(&executor::analyze_code(code, &self.symbols))
     }
     
     /// Load patterns from the patterns directory
@@ -103,6 +154,18 @@ impl Planner {
             if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
                 let content = fs::read_to_string(&path)?;
                 let pattern: Pattern = serde_json::from_str(&content)?;
+                
+                // Patterns saved by older versions read memory with 📖;
+                // loading leaves them alone and `migrate_patterns` fixes them
+                let migrated = executor::migrate_code(&pattern.template);
+                if migrated.map_or(false, |template| template != pattern.template) {
+                    log::warn!(
+                        "Pattern template {} reads memory with 📖, run `anarchy-agent migrate --patterns {}`",
+                        path.display(),
+                        patterns_dir.display()
+                    );
+                }
+                
                 patterns.push(pattern);
             }
         }
@@ -209,7 +272,7 @@ impl Planner {
                       } else if (\"{{action}}\" == \"retrieve\" || \"{{action}}\" == \"recall\" || \"{{action}}\" == \"get\") {\n\
                           ιkey = \"{{item}}\";\n\
                           ÷{\n\
-                              ιvalue = 📖(key);\n\
+                              ιvalue = 📚(key);\n\
                               ⌽(`Retrieved ${key} from memory: ${value}`);\n\
                           }{\n\
                               ⌽(`Could not find ${key} in memory`);\n\
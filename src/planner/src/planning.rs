@@ -4,6 +4,7 @@ use anyhow::Result;
 /// Functions for creating prompts and validating generated code
 pub mod planning {
     use anyhow::Result;
+    use executor::{analyze_code, codes, format_code, has_errors, render_all, Diagnostic, SymbolSignature};
     use crate::config::Config;
     use crate::error::Error;
 
@@ -22,34 +23,54 @@ pub mod planning {
         )
     }
 
-    /// Validate the generated Anarchy-Inference code
-    pub fn validate_code(code: &str) -> Result<String> {
-        // In a real implementation, this would parse and validate the code
-        // For now, just do some basic checks
+    /// Create a prompt asking the LLM to fix code that failed validation
+    ///
+    /// The diagnostics are included as JSON so the model gets exact locations.
+    pub fn create_repair_prompt(task_description: &str, code: &str, diagnostics: &[Diagnostic], config: &Config) -> String {
+        let diagnostics: Vec<serde_json::Value> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_json(code))
+            .collect();
         
-        if !code.contains("ƒ") {
-            return Err(Error::ValidationError(
-                "Generated code does not contain any function definitions".to_string()
-            ).into());
-        }
+        format!(
+            "{}\n\n\
+            Task: {}\n\n\
+            The following Anarchy-Inference code was generated for this task:\n{}\n\n\
+            It has these problems (JSON, lines and columns are 1-based):\n{}\n\n\
+            Generate corrected Anarchy-Inference code. \
+            The code should be complete and executable.",
+            config.system_prompt,
+            task_description,
+            code,
+            serde_json::to_string_pretty(&diagnostics).unwrap_or_default()
+        )
+    }
+    
+    /// Validate the generated Anarchy-Inference code against the symbols
+    /// the executor provides
+    ///
+    /// Fails with `Error::InvalidCode` if the static analyzer finds errors.
+    /// A `main` function that is never called is repaired by calling it;
+    /// other warnings are only logged. The returned code is in canonical
+    /// format.
+    pub fn validate_code(code: &str, symbols: &[SymbolSignature]) -> Result<String> {
+        let diagnostics = analyze_code(code, symbols);
         
-        // Check for basic syntax elements
-        if !code.contains("⟼") && !code.contains("⌽") {
-            return Err(Error::ValidationError(
-                "Generated code does not contain return or print statements".to_string()
-            ).into());
+        if has_errors(&diagnostics) {
+            let rendered = render_all(&diagnostics, code);
+            return Err(Error::InvalidCode { diagnostics, rendered }.into());
         }
         
-        // Ensure the code has a main function or immediate execution
-        if !code.contains("main()") && !code.contains("ƒmain()") {
-            // Add a main function wrapper if not present
-            return Ok(format!(
-                "ƒmain() {{\n{}\n}}\n\nmain();",
-                code
-            ));
+        let mut code = code.to_string();
+        for diagnostic in &diagnostics {
+            if diagnostic.code == codes::MISSING_MAIN_CALL {
+                code = format!("{}\n\nmain();\n", code.trim_end());
+            } else {
+                log::debug!("Generated code warning:\n{}", diagnostic.render(&code));
+            }
         }
         
-        Ok(code.to_string())
+        Ok(format_code(&code).unwrap_or(code))
     }
 }
 
@@ -133,13 +133,15 @@ impl ReasoningSystem {
             } else if line.contains("📂(") {
                 reasoning.push_str(&format!("- List directory: {}\n", line));
             } else if line.contains("📖(") {
-                reasoning.push_str(&format!("- Read file or memory: {}\n", line));
+                reasoning.push_str(&format!("- Read file: {}\n", line));
             } else if line.contains("✍(") {
                 reasoning.push_str(&format!("- Write file: {}\n", line));
             } else if line.contains("↗(") {
                 reasoning.push_str(&format!("- HTTP GET request: {}\n", line));
             } else if line.contains("📝(") {
                 reasoning.push_str(&format!("- Store in memory: {}\n", line));
+            } else if line.contains("📚(") {
+                reasoning.push_str(&format!("- Read from memory: {}\n", line));
             } else if line.contains("📥(") {
                 reasoning.push_str(&format!("- Get input from file: {}\n", line));
             } else if line.contains("📤(") {
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use log::info;
 
 use crate::error::Error;
@@ -21,19 +21,15 @@ pub mod file {
         // Check if the path is allowed
         sandbox.check_path_allowed(&full_path)?;
         
-        // In a real implementation, this would list the directory contents
-        // let entries = std::fs::read_dir(&full_path)?
-        //     .filter_map(|entry| {
-        //         entry.ok().map(|e| e.path().to_string_lossy().to_string())
-        //     })
-        //     .collect();
-        
-        // For now, just return a placeholder
-        let entries = vec![
-            format!("{}/file1.txt", path),
-            format!("{}/file2.txt", path),
-            format!("{}/subdir", path),
-        ];
+        // Entries are returned as paths the other file operations accept,
+        // sorted so that listings are stable
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&full_path).map_err(|e| io_error(path, e))? {
+            let entry = entry.map_err(|e| io_error(path, e))?;
+            let name = entry.file_name();
+            entries.push(Path::new(path).join(name).to_string_lossy().to_string());
+        }
+        entries.sort();
         
         Ok(entries)
     }
@@ -50,20 +46,16 @@ pub mod file {
         // Check if the path is allowed
         sandbox.check_path_allowed(&full_path)?;
         
-        // In a real implementation, this would read the file contents
-        // let contents = std::fs::read_to_string(&full_path)?;
-        
-        // For now, just return a placeholder
-        let contents = format!("Contents of file: {}", path);
+        let contents = std::fs::read_to_string(&full_path).map_err(|e| io_error(path, e))?;
         
         Ok(contents)
     }
     
-    /// Write to a file
+    /// Write to a file, creating it and its parent directories if needed
     pub fn write_file(
         working_dir: &Path,
         path: &str,
-        _contents: &str,
+        contents: &str,
         sandbox: &Sandbox,
     ) -> Result<()> {
         // Validate and normalize the path
@@ -72,11 +64,11 @@ pub mod file {
         // Check if the path is allowed
         sandbox.check_path_allowed(&full_path)?;
         
-        // In a real implementation, this would write to the file
-        // std::fs::write(&full_path, contents)?;
-        
-        // For now, just log the operation
         info!("Writing to file: {}", path);
+        if let Some(parent) = full_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(path, e))?;
+        }
+        std::fs::write(&full_path, contents).map_err(|e| io_error(path, e))?;
         
         Ok(())
     }
@@ -93,15 +85,12 @@ pub mod file {
         // Check if the path is allowed
         sandbox.check_path_allowed(&full_path)?;
         
-        // In a real implementation, this would remove the file or directory
-        // if full_path.is_dir() {
-        //     std::fs::remove_dir_all(&full_path)?;
-        // } else {
-        //     std::fs::remove_file(&full_path)?;
-        // }
-        
-        // For now, just log the operation
         info!("Removing path: {}", path);
+        if full_path.is_dir() {
+            std::fs::remove_dir_all(&full_path).map_err(|e| io_error(path, e))?;
+        } else {
+            std::fs::remove_file(&full_path).map_err(|e| io_error(path, e))?;
+        }
         
         Ok(())
     }
@@ -121,11 +110,8 @@ pub mod file {
         sandbox.check_path_allowed(&full_src)?;
         sandbox.check_path_allowed(&full_dst)?;
         
-        // In a real implementation, this would copy the file
-        // std::fs::copy(&full_src, &full_dst)?;
-        
-        // For now, just log the operation
         info!("Copying file from {} to {}", src, dst);
+        std::fs::copy(&full_src, &full_dst).map_err(|e| io_error(src, e))?;
         
         Ok(())
     }
@@ -145,11 +131,8 @@ pub mod file {
         sandbox.check_path_allowed(&full_src)?;
         sandbox.check_path_allowed(&full_dst)?;
         
-        // In a real implementation, this would move the file
-        // std::fs::rename(&full_src, &full_dst)?;
-        
-        // For now, just log the operation
         info!("Moving file from {} to {}", src, dst);
+        std::fs::rename(&full_src, &full_dst).map_err(|e| io_error(src, e))?;
         
         Ok(())
     }
@@ -166,34 +149,68 @@ pub mod file {
         // Check if the path is allowed
         sandbox.check_path_allowed(&full_path)?;
         
-        // In a real implementation, this would check if the file exists
-        // let exists = full_path.exists();
-        
-        // For now, just return a placeholder
-        let exists = true;
-        
-        Ok(exists)
+        Ok(full_path.exists())
     }
     
     /// Normalize a path relative to the working directory
     fn normalize_path(working_dir: &Path, path: &str) -> Result<PathBuf> {
-        let path = Path::new(path);
-        
-        // If the path is absolute, use it directly
-        if path.is_absolute() {
-            return Ok(path.to_path_buf());
+        if path.is_empty() {
+            return Err(Error::FileSystemError("Empty path".to_string()).into());
         }
         
-        // Otherwise, join it with the working directory
-        let full_path = working_dir.join(path);
-        
-        // Canonicalize the path to resolve any .. or . components
-        // In a real implementation, this would use std::fs::canonicalize
-        // let canonical_path = full_path.canonicalize()?;
-        
-        // For now, just return the joined path
-        Ok(full_path)
+        Ok(absolute(&working_dir.join(path)))
+    }
+    
+    /// Wrap an I/O error with the path it happened on
+    fn io_error(path: &str, err: std::io::Error) -> Error {
+        Error::FileSystemError(format!("{}: {}", path, err))
+    }
+}
+
+/// `path` as an absolute path with `.` and `..` resolved
+///
+/// The path does not have to exist, so this works on the components of the
+/// path rather than asking the file system.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `path` as an absolute path with symbolic links resolved
+///
+/// The longest part of the path that exists is canonicalized and the rest,
+/// which cannot contain links yet, is appended to it. Returns `None` if that
+/// part exists but cannot be canonicalized, such as a link to nowhere, since
+/// writing through it would create a file wherever the link points.
+pub(crate) fn resolved(path: &Path) -> Option<PathBuf> {
+    let path = absolute(path);
+    for existing in path.ancestors() {
+        if let Ok(real) = std::fs::canonicalize(existing) {
+            let rest = path.strip_prefix(existing).ok()?;
+            return Some(real.join(rest));
+        }
+        if existing.symlink_metadata().is_ok() {
+            return None;
+        }
     }
+    None
 }
 
 // Re-export the file functions
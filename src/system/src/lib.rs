@@ -20,10 +20,13 @@ pub struct System {
 
 impl System {
     /// Create a new System instance with the specified working directory and sandbox mode
+    ///
+    /// With the sandbox enabled, file operations are confined to the working directory.
     pub fn new(working_dir: &Path, sandbox_enabled: bool) -> Result<Self> {
         let config = Config {
             working_directory: working_dir.to_path_buf(),
             sandbox_enabled,
+            allowed_paths: vec![working_dir.to_path_buf()],
             ..Config::default()
         };
         
@@ -83,13 +83,13 @@ impl Sandbox {
 }
 
 /// Check if a path is within another path
+///
+/// Both paths are made absolute and compared component by component, so
+/// `workspace/../secret` is not within `workspace` and neither is
+/// `workspace-old`. Symbolic links are resolved first, so a link inside
+/// `workspace` that points elsewhere does not let a path escape it.
 fn is_path_within(path: &Path, container: &Path) -> bool {
-    // In a real implementation, this would canonicalize both paths and check
-    // if one is a prefix of the other
-    
-    // For now, just do a simple string prefix check
-    let path_str = path.to_string_lossy();
-    let container_str = container.to_string_lossy();
-    
-    path_str.starts_with(&*container_str)
+    let container =
+        crate::file::resolved(container).unwrap_or_else(|| crate::file::absolute(container));
+    crate::file::resolved(path).is_some_and(|path| path.starts_with(container))
 }
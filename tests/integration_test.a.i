@@ -13,8 +13,8 @@
     
     // Use the reasoning system to generate a prompt (simulated)
     ⌽("Generating personalized prompt based on memory...");
-    ιname = 📖("user_name");
-    ιcolor = 📖("favorite_color");
+    ιname = 📚("user_name");
+    ιcolor = 📚("favorite_color");
     ιprompt = `Hello ${name}! I see your favorite color is ${color}. What would you like to do today?`;
     
     // Use the input workaround to get user input
@@ -51,8 +51,8 @@
         }
         
         // Retrieve the stored result
-        ιlast_op = 📖("last_operation");
-        ιlast_result = 📖("last_result");
+        ιlast_op = 📚("last_operation");
+        ιlast_result = 📚("last_result");
         ⌽(`Last operation (${last_op}): ${last_result}`);
     } else {
         ⌽("No user response received");
@@ -0,0 +1,119 @@
+ƒfib(n) {
+    ↪(n < 2) {
+        ↩ n;
+    }
+    ↩ fib(n - 1) + fib(n - 2);
+}
+
+ƒcounter() {
+    ιcount = 0;
+    ↩ λ() {
+        count++;
+        ↩ count;
+    };
+}
+
+ƒsumUntil(items, stop) {
+    ιtotal = 0;
+    ∀(items, λ(item) {
+        ↪(item == stop) {
+            ↵;
+        }
+        ÷{
+            total += item;
+        }{
+            ⌽("Unexpected error");
+        }
+    });
+    ↩ total;
+}
+
+ƒfirstInLoop() {
+    ↻(true) {
+        ÷{
+            ↩ "returned from try";
+        }{
+        }
+    }
+}
+
+ƒmain() {
+    ⌽("Starting language test...");
+
+    // Values, templates and operators
+    ιitems = [1, 2, 3];
+    ιrecord = { name: "test", nested: { values: [1, { empty: null }] } };
+    ⌽(`Template: ${record.name} has ${items.length} items`);
+    ⌽(record?.missing?.field, record.nested.values[1].empty ?? "default");
+    ιx = 5;
+    x += 2;
+    x *= 3;
+    ⌽(x, x++, ++x, x--, --x, typeof x, typeof record, typeof fib);
+
+    // Functions, closures and parameters
+    ⌽(`fib(15) = ${fib(15)}`);
+    ιnext = counter();
+    next();
+    next();
+    ⌽(`Counter: ${next()}`);
+    ιparams = λ(a, b = a * 2, ...rest) {
+        ↩ [a, b, rest];
+    };
+    ⌽(params(1), params(1, 5, 6, 7), params(...items, ...[9]));
+    ⌽(hoisted());
+
+    // Loops
+    ιi = 0;
+    ↻(i < 10) {
+        i++;
+        ↪(i == 3) {
+            continue;
+        }
+        ↪(i == 7) {
+            ↵;
+        }
+        ⌽(`Loop: ${i}`);
+    }
+    ∀(record, λ(value, key) {
+        ⌽(`Key: ${key}`);
+    });
+    ⌽(`Sum until 3: ${sumUntil([1, 2, 3, 4], 3)}`);
+    ⌽(firstInLoop());
+
+    // Array methods
+    ιdoubled = items.map(λ(v) { ↩ v * 2; }).filter(λ(v) { ↩ v > 2; });
+    ⌽(doubled, items.some(λ(v) { ↩ v > 2; }), items.every(λ(v) { ↩ v > 2; }));
+
+    // Block expressions
+    ιblock = x > 0 ? { ιt = 3; t * 2; } : { 0; };
+    ιearly = {
+        ↪(true) {
+            ↩ "early";
+        }
+        "late";
+    };
+    ⌽(block, early);
+
+    // Errors
+    ÷{
+        ⌽("Before error");
+        undefinedFunction();
+        ⌽("Not printed");
+    }{
+        ⌽("Caught undefined function");
+    }
+    ÷{
+        ιempty = null;
+        empty.field.other;
+    }⚠(error){
+        ⌽(`Caught ${error.kind}: ${error.message}`);
+    }
+
+    ⟼("Language test completed");
+}
+
+ƒhoisted() {
+    ↩ "Hoisted function called";
+}
+
+main();
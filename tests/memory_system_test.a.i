@@ -11,15 +11,15 @@
     
     // Retrieve values from memory
     ⌽("Retrieving values from memory...");
-    ιvalue1 = 📖("test_key1");
-    ιvalue2 = 📖("test_key2");
+    ιvalue1 = 📚("test_key1");
+    ιvalue2 = 📚("test_key2");
     
     ⌽(`Retrieved values: ${value1}, ${value2}`);
     
     // Test updating a value
     ⌽("Testing value update...");
     📝("test_key1", "updated_value1");
-    ιupdated = 📖("test_key1");
+    ιupdated = 📚("test_key1");
     ⌽(`Updated value: ${updated}`);
     
     // Test deleting a value
@@ -28,7 +28,7 @@
     
     // Try to retrieve the deleted value (should fail gracefully)
     ÷{
-        ιdeleted = 📖("test_key2");
+        ιdeleted = 📚("test_key2");
         ⌽(`This should not print: ${deleted}`);
     }{
         ⌽("Successfully detected deleted key");
@@ -47,7 +47,7 @@
     📝("complex_test", data_string);
     
     // Retrieve and parse
-    ιretrieved_string = 📖("complex_test");
+    ιretrieved_string = 📚("complex_test");
     ιretrieved_data = ⎋(retrieved_string);
     
     ⌽(`Retrieved complex data: ${retrieved_data.name}, age ${retrieved_data.age}`);
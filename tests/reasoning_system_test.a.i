@@ -92,7 +92,7 @@
     else if (pattern_id == "memory_operations") {
         ⌽("Example code snippet:");
         ⌽("  📝(\"key\", \"value\");");
-        ⌽("  ιstored_value = 📖(\"key\");");
+        ⌽("  ιstored_value = 📚(\"key\");");
     }
 }
 